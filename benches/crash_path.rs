@@ -0,0 +1,56 @@
+//! Latency of the work done at crash time
+//!
+//! The hook runs while the user waits for the application to exit, so the
+//! crash path is held to a budget of 250 ms, see the README. Run with
+//! `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use human_panic_logger::{metadata, Report};
+use std::panic::{self, PanicHookInfo};
+use std::sync::Mutex;
+
+/// Panic inside `catch_unwind` with `hook` installed, returning what the
+/// hook left behind
+fn with_hook<T: Send + 'static>(
+    hook: impl Fn(&PanicHookInfo) -> T + Send + Sync + 'static,
+) -> impl FnMut() -> Option<T> {
+    let slot = std::sync::Arc::new(Mutex::new(None));
+    let hook_slot = slot.clone();
+    panic::set_hook(Box::new(move |info| {
+        *hook_slot.lock().unwrap() = Some(hook(info));
+    }));
+    move || {
+        let _ = panic::catch_unwind(|| panic!("benchmark panic"));
+        slot.lock().unwrap().take()
+    }
+}
+
+fn crash_path(c: &mut Criterion) {
+    let mut group = c.benchmark_group("crash_path");
+
+    // the cost of panicking and unwinding itself, to compare against
+    let mut unwind = with_hook(|_| ());
+    group.bench_function("unwind_only", |b| b.iter(&mut unwind));
+
+    let mut unresolved = with_hook(|_| {
+        backtrace::Backtrace::new_unresolved().frames().len()
+    });
+    group.bench_function("backtrace_unresolved", |b| {
+        b.iter(&mut unresolved)
+    });
+
+    let meta = metadata!();
+    let mut report = with_hook(move |info| Report::new(info, &meta));
+    group.bench_function("report_new", |b| b.iter(&mut report));
+
+    let report = report().expect("hook ran");
+    group.bench_function("report_render", |b| {
+        b.iter(|| black_box(&report).to_string())
+    });
+
+    let _ = panic::take_hook();
+    group.finish();
+}
+
+criterion_group!(benches, crash_path);
+criterion_main!(benches);
@@ -0,0 +1,42 @@
+//! Tracking of the last user interaction
+//!
+//! Interactive tools can call [`note_activity`] whenever the user does
+//! something. Reports then say how long before the crash that was, which
+//! tells crashes in direct response to the user apart from those in
+//! background work while the tool sat idle.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// Milliseconds since `epoch()` at the last user action, `NEVER` if none
+static LAST_ACTIVITY: AtomicU64 = AtomicU64::new(NEVER);
+const NEVER: u64 = u64::MAX;
+
+fn epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+/// Record that the user just interacted with the application
+///
+/// This is cheap enough to call on every key press or command.
+///
+/// ```
+/// human_panic_logger::note_activity();
+/// ```
+pub fn note_activity() {
+    let millis = epoch().elapsed().as_millis() as u64;
+    LAST_ACTIVITY.store(millis, Ordering::Relaxed);
+}
+
+/// Seconds since the last call to [`note_activity`], if there was one
+pub(crate) fn seconds_since_activity() -> Option<u64> {
+    match LAST_ACTIVITY.load(Ordering::Relaxed) {
+        NEVER => None,
+        last => {
+            let now = epoch().elapsed().as_millis() as u64;
+            Some(now.saturating_sub(last) / 1000)
+        }
+    }
+}
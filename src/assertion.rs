@@ -0,0 +1,112 @@
+//! Recognition of `assert_eq!`/`assert_ne!` panic messages
+//!
+//! Assertion failures are by far the most common kind of panic, but their
+//! message packs the compared values into a couple of loosely formatted
+//! lines. This module picks those messages apart so the values can be
+//! shown in a labeled block instead.
+
+use std::fmt::Write;
+
+/// The pieces of a failed `assert_eq!`/`assert_ne!`
+pub(crate) struct Assertion<'a> {
+    /// The comparison that failed, e.g. `left == right`
+    pub kind: &'a str,
+    /// The custom message passed to the assertion, if any
+    pub message: Option<&'a str>,
+    /// Debug representation of the left-hand value
+    pub left: &'a str,
+    /// Debug representation of the right-hand value
+    pub right: &'a str,
+}
+
+const LEFT: &str = "\n  left: ";
+const RIGHT: &str = "\n right: ";
+
+impl<'a> Assertion<'a> {
+    /// Try to recognise a panic message as a failed assertion
+    ///
+    /// Both the current message shape (``assertion `left == right` failed``)
+    /// and the one used before Rust 1.73
+    /// (``assertion failed: `(left == right)` ``) are understood.
+    pub fn parse(cause: &'a str) -> Option<Self> {
+        if let Some(rest) = cause.strip_prefix("assertion `") {
+            Self::parse_current(rest)
+        } else if let Some(rest) = cause.strip_prefix("assertion failed: `(") {
+            Self::parse_legacy(rest)
+        } else {
+            None
+        }
+    }
+
+    fn parse_current(rest: &'a str) -> Option<Self> {
+        let kind_end = rest.find("` failed")?;
+        let kind = &rest[..kind_end];
+        let rest = &rest[kind_end + "` failed".len()..];
+
+        let left_start = rest.find(LEFT)?;
+        let message = rest[..left_start].strip_prefix(": ");
+        let values = &rest[left_start + LEFT.len()..];
+
+        let right_start = values.find(RIGHT)?;
+        Some(Assertion {
+            kind,
+            message,
+            left: &values[..right_start],
+            right: &values[right_start + RIGHT.len()..],
+        })
+    }
+
+    fn parse_legacy(rest: &'a str) -> Option<Self> {
+        let kind_end = rest.find(")`")?;
+        let kind = &rest[..kind_end];
+        let rest = rest[kind_end + ")`".len()..].strip_prefix(LEFT)?;
+
+        let right_start = rest.find(RIGHT)?;
+        let left = rest[..right_start].strip_suffix(',')?;
+        let right = &rest[right_start + RIGHT.len()..];
+
+        let (right, message) = match right.rfind("`: ") {
+            Some(idx) => (&right[..=idx], Some(&right[idx + "`: ".len()..])),
+            None => (right, None),
+        };
+
+        Some(Assertion {
+            kind,
+            message,
+            left: strip_backticks(left),
+            right: strip_backticks(right),
+        })
+    }
+
+    /// Render the assertion as a labeled block, one value per label
+    pub fn to_block(&self) -> String {
+        let mut block = format!("assertion failed: `{}`", self.kind);
+        if let Some(message) = self.message {
+            push_labeled(&mut block, "message", message);
+        }
+        push_labeled(&mut block, "left", self.left);
+        push_labeled(&mut block, "right", self.right);
+        block
+    }
+}
+
+fn strip_backticks(value: &str) -> &str {
+    value
+        .strip_prefix('`')
+        .and_then(|v| v.strip_suffix('`'))
+        .unwrap_or(value)
+}
+
+/// Append `label: value`, lining up continuation lines of multi-line
+/// (pretty-printed) values under the first one
+fn push_labeled(block: &mut String, label: &str, value: &str) {
+    const LABEL_WIDTH: usize = 7;
+
+    let _ = write!(block, "\n{:>1$}: ", label, LABEL_WIDTH);
+    for (idx, line) in value.lines().enumerate() {
+        if idx != 0 {
+            let _ = write!(block, "\n{:1$}", "", LABEL_WIDTH + 2);
+        }
+        block.push_str(line);
+    }
+}
@@ -0,0 +1,57 @@
+//! Handling of the crate's author list
+
+use std::borrow::Cow;
+
+/// Split an author list as found in `CARGO_PKG_AUTHORS` and
+/// [`Metadata::authors`](crate::Metadata::authors), separated by `:`, into
+/// the individual authors
+///
+/// Commas are part of names, as in company names:
+///
+/// ```
+/// use human_panic_logger::split_authors;
+///
+/// assert_eq!(
+///     split_authors("Jane Doe <jane@example.com>:John Roe"),
+///     vec!["Jane Doe <jane@example.com>", "John Roe"]
+/// );
+/// assert_eq!(
+///     split_authors("Acme, Inc. <support@acme.example>:Jane Doe"),
+///     vec!["Acme, Inc. <support@acme.example>", "Jane Doe"]
+/// );
+/// assert!(split_authors("").is_empty());
+/// ```
+pub fn split_authors(authors: &str) -> Vec<&str> {
+    authors
+        .split(':')
+        .map(str::trim)
+        .filter(|author| !author.is_empty())
+        .collect()
+}
+
+/// Turn `CARGO_PKG_AUTHORS` into a human readable, comma separated list
+///
+/// ```
+/// use human_panic_logger::join_authors;
+///
+/// assert_eq!(
+///     join_authors("Jane Doe <jane@example.com>:John Roe"),
+///     "Jane Doe <jane@example.com>, John Roe"
+/// );
+/// ```
+pub fn join_authors(authors: &str) -> String {
+    split_authors(authors).join(", ")
+}
+
+/// How the crate's authors are listed in the crash message
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum AuthorsDisplay {
+    /// All authors on one line: `- Authors: Jane Doe, John Roe`
+    #[default]
+    Inline,
+    /// One line per author: `- Author: Jane Doe`
+    List,
+    /// A single contact shown instead of the authors, e.g. a support team:
+    /// `- Contact: support@example.com`
+    Contact(Cow<'static, str>),
+}
@@ -0,0 +1,310 @@
+//! `cargo human-panic`: tools for an application's crash reports
+//!
+//! `cargo human-panic test` checks the crash path end to end. It builds the
+//! application in the current directory in release mode with a panic
+//! injected right after the hook is installed, runs it, and checks that the
+//! crash message was printed and the report file can be read back.
+//! Arguments are passed on to `cargo run`, e.g.
+//! `cargo human-panic test --bin app -- --some-flag`.
+//!
+//! `cargo human-panic diff <a> <b>` compares two report files: the details
+//! that tell crashes apart, like the version, the panic location and the
+//! fingerprint, and the function names of their backtraces. It exits with
+//! 0 if the reports describe the same crash, and 1 if not, e.g. to check
+//! whether a fix changed a crash's signature.
+//!
+//! Installed with `cargo install human-panic-logger --features
+//! test-command`.
+
+use human_panic_logger::{Report, INJECTED_PANIC};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{exit, Command};
+
+const USAGE: &str = "\
+Check the crash path of the application in the current directory, or
+compare two of its crash reports
+
+Usage: cargo human-panic test [CARGO RUN ARGS]... [-- APP ARGS...]
+       cargo human-panic diff <REPORT A> <REPORT B>";
+
+/// Unchanged frames shown around changed ones in a diff
+const CONTEXT: usize = 3;
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // cargo passes the subcommand's name on
+    if args.first().map(String::as_str) == Some("human-panic") {
+        args.remove(0);
+    }
+    match args.first().map(String::as_str) {
+        Some("test") => {}
+        Some("diff") if args.len() == 3 => {
+            match diff(Path::new(&args[1]), Path::new(&args[2])) {
+                Ok(true) => {}
+                Ok(false) => exit(1),
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    exit(2);
+                }
+            }
+            return;
+        }
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            return;
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    }
+
+    match test(&args[1..]) {
+        Ok(true) => println!("crash path ok"),
+        Ok(false) => {
+            println!("crash path broken");
+            exit(1);
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            exit(2);
+        }
+    }
+}
+
+/// Build and crash the application, `Ok(false)` if a check failed
+fn test(cargo_args: &[String]) -> Result<bool, String> {
+    // a target directory of its own, so the injected panic never ends up
+    // in regular builds and these don't rebuild everything
+    let target_dir = std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("target"))
+        .join("human-panic-test");
+    let report_dir = target_dir.join("reports");
+    let _ = std::fs::remove_dir_all(&report_dir);
+    std::fs::create_dir_all(&report_dir)
+        .map_err(|e| format!("{}: {}", report_dir.display(), e))?;
+    // the application may change its working directory
+    let report_dir = report_dir.canonicalize().unwrap_or(report_dir);
+
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut command = Command::new(cargo);
+    command
+        .args(["run", "--quiet", "--release"])
+        .args(cargo_args)
+        .env("CARGO_TARGET_DIR", &target_dir)
+        .env("HUMAN_PANIC_REPORT_DIR", &report_dir)
+        .env_remove("RUST_BACKTRACE")
+        .env_remove("HUMAN_PANIC_SUPERVISOR");
+    match std::env::var_os("CARGO_ENCODED_RUSTFLAGS") {
+        Some(flags) => command.env(
+            "CARGO_ENCODED_RUSTFLAGS",
+            append(flags, "\x1f--cfg\x1fhuman_panic_inject"),
+        ),
+        None => command.env(
+            "RUSTFLAGS",
+            append(
+                std::env::var_os("RUSTFLAGS").unwrap_or_default(),
+                " --cfg human_panic_inject",
+            ),
+        ),
+    };
+
+    println!("building and running the application with a panic injected");
+    let output = command
+        .output()
+        .map_err(|e| format!("could not run cargo: {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let mut ok = true;
+    let mut check = |passed: bool, what: &str| {
+        println!("{}: {}", if passed { "ok" } else { "FAILED" }, what);
+        ok &= passed;
+    };
+
+    if stderr.contains("error: could not compile") {
+        return Err(format!("the build failed:\n{}", stderr.trim_end()));
+    }
+    check(!output.status.success(), "the application panicked at setup");
+    if output.status.success() {
+        println!("  is human_panic_logger set up in main?");
+        return Ok(false);
+    }
+    // the standard hook would have printed the panic message
+    check(!stderr.contains(INJECTED_PANIC), "the panic hook was installed");
+    check(
+        stderr.contains("crashed") || stdout.contains("crashed"),
+        "the crash message was printed",
+    );
+
+    let reports = std::fs::read_dir(&report_dir)
+        .map_err(|e| format!("{}: {}", report_dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| !is_signature(path))
+        .collect::<Vec<_>>();
+    check(reports.len() == 1, "one report file was written");
+    for report in &reports {
+        println!("  {}", report.display());
+        let readable = |e: &std::ffi::OsStr| {
+            e == "toml" || (cfg!(feature = "json") && e == "json")
+        };
+        if report.extension().is_some_and(readable) {
+            let parsed = Report::load(report);
+            check(
+                parsed.is_ok_and(|r| r.cause == INJECTED_PANIC),
+                "the report can be read back",
+            );
+        } else {
+            println!("skipped: reading back reports that aren't TOML or JSON");
+        }
+    }
+    Ok(ok)
+}
+
+/// Print how the reports at `a` and `b` differ, `Ok(true)` if they
+/// describe the same crash
+fn diff(a: &Path, b: &Path) -> Result<bool, String> {
+    let load = |path: &Path| {
+        Report::load(path).map_err(|e| format!("{}: {}", path.display(), e))
+    };
+    let (old, new) = (load(a)?, load(b)?);
+    println!("--- {}\n+++ {}", a.display(), b.display());
+
+    let mut same = true;
+    let fields = details(&old).into_iter().zip(details(&new));
+    for ((field, old), (_, new)) in fields {
+        if old == new {
+            println!("  {}: {}", field, old);
+        } else {
+            println!("- {}: {}\n+ {}: {}", field, old, field, new);
+            same = false;
+        }
+    }
+
+    let (old, new) = (frame_names(&old), frame_names(&new));
+    println!("  backtrace:");
+    let lines = diff_lines(&old, &new);
+    if lines.iter().all(|(change, _)| *change == ' ') {
+        println!("    {} frames, all the same", old.len());
+        return Ok(same);
+    }
+    // unchanged frames far from changed ones are left out
+    let near_change = |idx: usize| {
+        let start = idx.saturating_sub(CONTEXT);
+        let end = (idx + CONTEXT + 1).min(lines.len());
+        lines[start..end].iter().any(|(change, _)| *change != ' ')
+    };
+    let mut skipped = false;
+    for (idx, (change, name)) in lines.iter().enumerate() {
+        if *change == ' ' && !near_change(idx) {
+            if !skipped {
+                println!("    ...");
+            }
+            skipped = true;
+            continue;
+        }
+        skipped = false;
+        println!("{}   {}", change, name);
+    }
+    Ok(false)
+}
+
+/// The details of `report` that tell crashes apart, by name
+fn details(report: &Report) -> Vec<(&'static str, String)> {
+    let or_none = |value: Option<&str>| value.unwrap_or("none").to_string();
+    vec![
+        ("fingerprint", report.fingerprint()),
+        ("name", report.name.clone()),
+        ("version", report.version.clone()),
+        ("channel", report.channel.to_string()),
+        ("build id", or_none(report.build_id.as_deref())),
+        ("operating system", report.operating_system.clone()),
+        (
+            "location",
+            report.location.as_ref().map_or("unknown".into(), |l| {
+                format!("{}:{}", l.file, l.line)
+            }),
+        ),
+        ("cause", report.cause.lines().next().unwrap_or("").to_string()),
+        ("plugin", or_none(report.plugin.as_ref().map(|p| &*p.name))),
+        ("ffi boundary", or_none(report.ffi_boundary.as_deref())),
+    ]
+}
+
+/// The function names of the backtrace of `report`, one per symbol and
+/// without the hashes that change from build to build
+fn frame_names(report: &Report) -> Vec<String> {
+    let mut names = Vec::new();
+    for frame in &report.backtrace {
+        if frame.symbols.is_empty() {
+            names.push("<unresolved>".to_string());
+        }
+        for symbol in &frame.symbols {
+            let name = symbol.name.as_deref().unwrap_or("<unknown>");
+            let name = match name.rsplit_once("::h") {
+                Some((name, hash))
+                    if hash.len() == 16
+                        && hash.bytes().all(|b| b.is_ascii_hexdigit()) =>
+                {
+                    name
+                }
+                _ => name,
+            };
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// The lines of `old` and `new` marked ' ' where both have them, '-' where
+/// only `old` does and '+' where only `new` does
+fn diff_lines<'a>(
+    old: &'a [String],
+    new: &'a [String],
+) -> Vec<(char, &'a str)> {
+    // the length of the longest common subsequence of every pair of tails
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = match old[i] == new[j] {
+                true => common[i + 1][j + 1] + 1,
+                false => common[i + 1][j].max(common[i][j + 1]),
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i].as_str()));
+            i += 1;
+            j += 1;
+        } else if i < old.len()
+            && (j == new.len() || common[i + 1][j] >= common[i][j + 1])
+        {
+            lines.push(('-', old[i].as_str()));
+            i += 1;
+        } else {
+            lines.push(('+', new[j].as_str()));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// `flags` with `extra` appended
+fn append(mut flags: OsString, extra: &str) -> OsString {
+    if !flags.is_empty() {
+        flags.push(extra);
+    } else {
+        flags.push(extra.trim_start_matches(['\x1f', ' ']));
+    }
+    flags
+}
+
+/// Whether `path` is a detached signature rather than a report
+fn is_signature(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "sig")
+}
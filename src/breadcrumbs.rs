@@ -0,0 +1,138 @@
+//! A bounded trail of what the application did before a crash
+//!
+//! [`Breadcrumbs`] keep the last few events an application notes, e.g.
+//! "opened project" or "sync started", and hand them to reports as a
+//! state section. Their memory is taken once, when they are created, so
+//! long-running daemons can leave them on for good.
+
+use crate::clock::DateTime;
+use crate::state::StateProvider;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+/// The longest message a breadcrumb keeps, in bytes
+const MAX_MESSAGE_BYTES: usize = 120;
+
+/// A fixed-size ring of the latest events, oldest overwritten first
+///
+/// All the memory is allocated by [`Breadcrumbs::new`]: about
+/// `capacity * 144` bytes, as each breadcrumb keeps its time and up to
+/// 120 bytes of message, so 100 breadcrumbs take about 14 KiB however long
+/// the application runs. [`push`](Breadcrumbs::push) never allocates and
+/// only holds a lock while copying the message, so it is cheap enough for
+/// hot paths.
+///
+/// Clones share the same ring. Register one as a state provider, and the
+/// breadcrumbs are in every report, oldest first:
+///
+/// ```
+/// use human_panic_logger::{metadata, Breadcrumbs, Builder};
+///
+/// let breadcrumbs = Breadcrumbs::new(100);
+/// let builder = Builder::new(metadata!(), "app.log")
+///     .state("breadcrumbs", breadcrumbs.clone());
+/// # drop(builder);
+///
+/// breadcrumbs.push("opened project");
+/// breadcrumbs.push("sync started");
+/// ```
+#[derive(Clone)]
+pub struct Breadcrumbs(Arc<Mutex<Ring>>);
+
+/// One event, kept inline so pushing never allocates
+#[derive(Clone, Copy)]
+struct Breadcrumb {
+    time: SystemTime,
+    len: usize,
+    message: [u8; MAX_MESSAGE_BYTES],
+}
+
+struct Ring {
+    crumbs: Box<[Breadcrumb]>,
+    /// Where the next breadcrumb goes
+    next: usize,
+    /// How many breadcrumbs were pushed, up to the capacity
+    len: usize,
+}
+
+impl Breadcrumbs {
+    /// Keep the last `capacity` breadcrumbs
+    pub fn new(capacity: usize) -> Self {
+        let empty = Breadcrumb {
+            time: SystemTime::UNIX_EPOCH,
+            len: 0,
+            message: [0; MAX_MESSAGE_BYTES],
+        };
+        Breadcrumbs(Arc::new(Mutex::new(Ring {
+            crumbs: vec![empty; capacity].into_boxed_slice(),
+            next: 0,
+            len: 0,
+        })))
+    }
+
+    /// Note that `message` just happened, replacing the oldest breadcrumb
+    /// if the ring is full
+    ///
+    /// Messages longer than 120 bytes are cut at the last whole character
+    /// that fits.
+    pub fn push(&self, message: &str) {
+        let mut len = message.len().min(MAX_MESSAGE_BYTES);
+        while !message.is_char_boundary(len) {
+            len -= 1;
+        }
+        let time = SystemTime::now();
+
+        let mut ring = self.ring();
+        let capacity = ring.crumbs.len();
+        if capacity == 0 {
+            return;
+        }
+        let next = ring.next;
+        let crumb = &mut ring.crumbs[next];
+        crumb.time = time;
+        crumb.len = len;
+        crumb.message[..len].copy_from_slice(&message.as_bytes()[..len]);
+        ring.next = (next + 1) % capacity;
+        ring.len = (ring.len + 1).min(capacity);
+    }
+
+    /// Forget all breadcrumbs, e.g. when a new job starts
+    pub fn clear(&self) {
+        let mut ring = self.ring();
+        ring.next = 0;
+        ring.len = 0;
+    }
+
+    fn ring(&self) -> MutexGuard<'_, Ring> {
+        // pushing can't leave the ring half written
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl StateProvider for Breadcrumbs {
+    /// The breadcrumbs, oldest first, as a `crumbs` array of tables with
+    /// the `time` and the `event`
+    fn snapshot(&self) -> toml::Value {
+        let ring = self.ring();
+        let capacity = ring.crumbs.len();
+        let oldest = (ring.next + capacity - ring.len) % capacity.max(1);
+        let crumbs = (0..ring.len)
+            .map(|i| &ring.crumbs[(oldest + i) % capacity])
+            .map(|crumb| {
+                let time = DateTime::from(crumb.time);
+                let event = &crumb.message[..crumb.len];
+                let mut table = toml::value::Table::new();
+                table.insert("time".into(), time.rfc3339().into());
+                table.insert(
+                    "event".into(),
+                    String::from_utf8_lossy(event).into_owned().into(),
+                );
+                toml::Value::Table(table)
+            })
+            .collect::<Vec<_>>();
+
+        let mut section = toml::value::Table::new();
+        section.insert("crumbs".into(), toml::Value::Array(crumbs));
+        toml::Value::Table(section)
+    }
+}
@@ -0,0 +1,82 @@
+//! Opening the prefilled issue page in the user's browser
+//!
+//! See [`Builder::open_issue`](crate::Builder::open_issue).
+
+use std::io::IsTerminal;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long to wait for the opener to hand the page to the browser
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Open `url` in the default browser, if a user is there to see it
+///
+/// Openers that take longer than [`TIMEOUT`] are left to finish on their
+/// own, so a hanging one can't keep the process from exiting.
+pub(crate) fn open(url: &str) {
+    if !interactive() {
+        return;
+    }
+    let mut child = match opener(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            log::warn!("Could not open the issue page: {}", e);
+            return;
+        }
+    };
+    let start = Instant::now();
+    while start.elapsed() < TIMEOUT {
+        match child.try_wait() {
+            Ok(Some(status)) if !status.success() => {
+                log::warn!("Could not open the issue page: {}", status);
+                return;
+            }
+            Ok(Some(_)) | Err(_) => return,
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+        }
+    }
+}
+
+/// Whether someone is looking at the crash message and has a desktop to
+/// open a browser on
+fn interactive() -> bool {
+    if !std::io::stderr().is_terminal()
+        || std::env::var_os("CI").is_some_and(|ci| !ci.is_empty())
+    {
+        return false;
+    }
+    if cfg!(any(target_os = "macos", windows)) {
+        return true;
+    }
+    // e.g. an SSH session or a text console
+    ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()))
+}
+
+#[cfg(target_os = "macos")]
+fn opener(url: &str) -> Command {
+    let mut command = Command::new("open");
+    command.arg(url);
+    command
+}
+
+#[cfg(windows)]
+fn opener(url: &str) -> Command {
+    // `cmd /c start` would treat the `&` and `%` of the query as its own
+    let mut command = Command::new("rundll32.exe");
+    command.arg("url.dll,FileProtocolHandler").arg(url);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn opener(url: &str) -> Command {
+    let mut command = Command::new("xdg-open");
+    command.arg(url);
+    command
+}
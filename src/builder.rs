@@ -0,0 +1,1167 @@
+//! Programmatic setup of the panic hook
+//!
+//! [`setup_panic_logger!`](crate::setup_panic_logger) covers the common
+//! case. [`Builder`] does the same at runtime, for applications that
+//! compute their configuration first, e.g. from command line arguments.
+
+#[cfg(feature = "signing")]
+use crate::format::sync_file;
+use crate::format::{sync_dir, Retention};
+use crate::observe::{HookEvent, HookStage, Observers};
+use crate::origin::{ForeignPanics, OwnCrates};
+use crate::permissions::Permissions;
+use crate::sink::{Reporter, Sinks};
+use crate::{
+    doctor, notify_system, open_log_file_with, print_msg_with, AuthorsDisplay,
+    MessageOutput, Metadata, Report, ReportFormat, MessageDetails, ReportNaming,
+    ReportSerializer, ReportSink, Settings, Theme,
+};
+use simplelog::{CombinedLogger, Config, LevelFilter, SharedLogger, WriteLogger};
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Configures and installs the panic hook
+///
+/// ```no_run
+/// use human_panic_logger::{metadata, Builder, Theme};
+///
+/// let log_file = std::env::args().nth(1).unwrap_or("app.log".into());
+///
+/// Builder::new(metadata!(), log_file)
+///     .theme(Theme::MINIMAL)
+///     .install()
+///     .expect("crash reporting could not be set up");
+/// ```
+pub struct Builder {
+    meta: Metadata,
+    resolvers: Resolvers,
+    log_file: PathBuf,
+    settings: Settings,
+    message_template_file: Option<PathBuf>,
+    level: Option<LevelFilter>,
+    loggers: Vec<Box<dyn SharedLogger>>,
+    debug: bool,
+    defer_to_debugger: bool,
+    own_crates: OwnCrates,
+    serializer: Option<Box<dyn ReportSerializer>>,
+    sinks: Vec<Box<dyn ReportSink>>,
+    upload_timeout: Duration,
+    consent_file: Option<PathBuf>,
+    naming: ReportNaming,
+    report_dir: Option<PathBuf>,
+    retention: Retention,
+    rollup: bool,
+    crash_reports: bool,
+    durable_writes: bool,
+    max_cause_chars: Option<usize>,
+    open_issue: bool,
+    permissions: Permissions,
+    observers: Observers,
+    #[cfg(feature = "signing")]
+    signing_key: Option<crate::signing::SigningKey>,
+    #[cfg(feature = "state")]
+    providers: crate::state::Providers,
+    #[cfg(feature = "screenshot")]
+    screenshot_consent: Option<ScreenshotConsent>,
+}
+
+impl Builder {
+    /// Report crashes of the crate described by `meta` to `log_file`
+    pub fn new(meta: Metadata, log_file: impl Into<PathBuf>) -> Self {
+        Builder {
+            meta,
+            resolvers: Vec::new(),
+            log_file: log_file.into(),
+            settings: Settings::default(),
+            message_template_file: None,
+            level: None,
+            loggers: Vec::new(),
+            debug: cfg!(debug_assertions),
+            defer_to_debugger: false,
+            own_crates: OwnCrates::default(),
+            serializer: None,
+            sinks: Vec::new(),
+            upload_timeout: crate::sink::UPLOAD_TIMEOUT,
+            consent_file: None,
+            naming: ReportNaming::default(),
+            report_dir: None,
+            retention: Retention::default(),
+            rollup: false,
+            crash_reports: true,
+            durable_writes: false,
+            max_cause_chars: None,
+            open_issue: false,
+            permissions: Permissions::default(),
+            observers: Observers::default(),
+            #[cfg(feature = "signing")]
+            signing_key: None,
+            #[cfg(feature = "state")]
+            providers: Default::default(),
+            #[cfg(feature = "screenshot")]
+            screenshot_consent: None,
+        }
+    }
+
+    /// Fill in metadata with `resolve` when a crash happens, for details
+    /// that change while the application runs
+    ///
+    /// `resolve` gets the metadata passed to [`Builder::new`] and can
+    /// change any of it, e.g. pick the support address of the tenant that
+    /// is logged in. Like sinks, it runs inside the panic hook, so it must
+    /// be quick and must not panic. Resolvers run in the order they were
+    /// added.
+    ///
+    /// ```no_run
+    /// use human_panic_logger::{metadata, Builder};
+    /// use std::sync::Mutex;
+    ///
+    /// static TENANT: Mutex<String> = Mutex::new(String::new());
+    ///
+    /// Builder::new(metadata!(), "app.log")
+    ///     .resolve_metadata(|meta| {
+    ///         // the panicking thread may hold the lock
+    ///         if let Ok(tenant) = TENANT.try_lock() {
+    ///             if !tenant.is_empty() {
+    ///                 meta.support_email =
+    ///                     format!("support+{}@example.com", tenant).into();
+    ///             }
+    ///         }
+    ///     })
+    ///     .install()
+    ///     .expect("crash reporting could not be set up");
+    /// ```
+    pub fn resolve_metadata(
+        mut self,
+        resolve: impl Fn(&mut Metadata) + Send + Sync + 'static,
+    ) -> Self {
+        self.resolvers.push(Box::new(resolve));
+        self
+    }
+
+    /// Set the level of messages written to the log file
+    ///
+    /// Defaults to `Debug` in debug builds and `Info` in release builds.
+    pub fn level(mut self, level: LevelFilter) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Log to another logger as well, e.g. a `TermLogger`
+    pub fn logger(mut self, logger: Box<dyn SharedLogger>) -> Self {
+        self.loggers.push(logger);
+        self
+    }
+
+    /// Replace all presentation [`Settings`] at once
+    pub fn settings(mut self, settings: Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Set the look of the crash message
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.settings.theme = theme;
+        self
+    }
+
+    /// Print the crash message for screen readers, see
+    /// [`Settings::accessible`]
+    pub fn accessible(mut self, accessible: bool) -> Self {
+        self.settings.accessible = accessible;
+        self
+    }
+
+    /// Explain well-known panic messages in plain words, see
+    /// [`Settings::explain_panics`]
+    pub fn explain_panics(mut self, explain_panics: bool) -> Self {
+        self.settings.explain_panics = explain_panics;
+        self
+    }
+
+    /// Also print the whole report after the message, see
+    /// [`Settings::show_report`](Settings#structfield.show_report)
+    pub fn show_report(mut self, show_report: bool) -> Self {
+        self.settings.show_report = show_report;
+        self
+    }
+
+    /// Replace the sentence telling users where to send the crash log, see
+    /// [`Settings::call_to_action`]
+    pub fn call_to_action(
+        mut self,
+        call_to_action: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.settings.call_to_action = Some(call_to_action.into());
+        self
+    }
+
+    /// Replace the whole crash message, see
+    /// [`Settings::message_template`](Settings#structfield.message_template)
+    ///
+    /// Templates kept in a file of the application's source tree, where
+    /// whoever writes the copy can edit them, can be built in with
+    /// `.message_template(include_str!("crash-message.txt"))`, or read at
+    /// run time with [`message_template_file`](Builder::message_template_file).
+    pub fn message_template(
+        mut self,
+        template: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.settings.message_template = Some(template.into());
+        self
+    }
+
+    /// Read the crash message template from `file` when the hook is
+    /// installed, so its wording can change without a rebuild
+    ///
+    /// If the file can't be read or the template in it is malformed, a
+    /// warning is logged and the crash message falls back to the
+    /// [`message_template`](Builder::message_template) built into the
+    /// application, or else the default one.
+    ///
+    /// ```no_run
+    /// use human_panic_logger::{metadata, Builder};
+    ///
+    /// Builder::new(metadata!(), "app.log")
+    ///     .message_template("{name} crashed, see \"{report_path}\".")
+    ///     .message_template_file("/etc/app/crash-message.txt")
+    ///     .install()
+    ///     .expect("crash reporting could not be set up");
+    /// ```
+    pub fn message_template_file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.message_template_file = Some(file.into());
+        self
+    }
+
+    /// Have issue links and Markdown reports follow the project's bug
+    /// report template, see
+    /// [`Settings::issue_template`](Settings#structfield.issue_template)
+    ///
+    /// ```no_run
+    /// use human_panic_logger::{metadata, Builder, ReportFormat};
+    ///
+    /// Builder::new(metadata!(), "app.log")
+    ///     .report_format(ReportFormat::Markdown)
+    ///     .issue_template(
+    ///         "## Steps to reproduce\n\n\n\n## Expected\n\n\n\n\
+    ///          ## Actual\n\n{cause}\n\n## Crash report\n\n{crash_report}",
+    ///     )
+    ///     .install()
+    ///     .expect("crash reporting could not be set up");
+    /// ```
+    ///
+    /// A template without a place for the crash report is turned down:
+    ///
+    /// ```
+    /// use human_panic_logger::{metadata, Builder, SetupError};
+    ///
+    /// let result = Builder::new(metadata!(), "app.log")
+    ///     .issue_template("## Steps to reproduce\n\n## Crash report\n")
+    ///     .install();
+    /// assert!(matches!(result, Err(SetupError::InvalidTemplate { .. })));
+    /// ```
+    pub fn issue_template(
+        mut self,
+        template: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.settings.issue_template = Some(template.into());
+        self
+    }
+
+    /// Show the crash message in `language`, e.g. `"de"`, rather than the
+    /// user's, see [`Settings::language`](Settings#structfield.language)
+    pub fn language(mut self, language: impl Into<Cow<'static, str>>) -> Self {
+        self.settings.language = Some(language.into());
+        self
+    }
+
+    /// Set how the authors are listed in the crash message
+    pub fn authors(mut self, authors: AuthorsDisplay) -> Self {
+        self.settings.authors = authors;
+        self
+    }
+
+    /// Also print the report file's path quoted for the shell, see
+    /// [`Settings::shell_path`]
+    pub fn shell_path(mut self, shell_path: bool) -> Self {
+        self.settings.shell_path = shell_path;
+        self
+    }
+
+    /// Set where the crash message goes, see [`Settings::output`]
+    pub fn output(mut self, output: MessageOutput) -> Self {
+        self.settings.output = output;
+        self
+    }
+
+    /// After printing the crash message, open the prefilled issue page in
+    /// the user's browser, see [`Metadata::issue_url`]
+    ///
+    /// Nothing is opened when stderr isn't a terminal, under CI, or on
+    /// Linux and other Unixes without a graphical session, or if the crash
+    /// message doesn't go to stderr, see [`Settings::output`].
+    pub fn open_issue(mut self, open: bool) -> Self {
+        self.open_issue = open;
+        self
+    }
+
+    /// Set the format of report files, see [`Settings::report_format`]
+    pub fn report_format(mut self, format: ReportFormat) -> Self {
+        self.settings.report_format = format;
+        self
+    }
+
+    /// Whether crashes are written to report files and handed to the
+    /// sinks and the system's crash tooling, on by default
+    ///
+    /// Without, crashes are only logged, and the crash message points to
+    /// the log file.
+    pub fn crash_reports(mut self, enabled: bool) -> Self {
+        self.crash_reports = enabled;
+        self
+    }
+
+    /// Set how report files are named, see [`ReportNaming`]
+    pub fn report_naming(mut self, naming: ReportNaming) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    /// Write report files into `dir` rather than the temporary directory
+    ///
+    /// Users can override this with the `HUMAN_PANIC_REPORT_DIR`
+    /// environment variable, e.g. in containers where only a mounted volume
+    /// is writable.
+    pub fn report_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.report_dir = Some(dir.into());
+        self
+    }
+
+    /// Write report files into the platform's conventional place for them,
+    /// see [`platform_report_dir`](crate::platform_report_dir)
+    ///
+    /// The directory is named after the crate and created on
+    /// [`install`](Builder::install). Platforms without such a place keep
+    /// using the temporary directory.
+    pub fn platform_report_dir(mut self) -> Self {
+        self.report_dir = crate::platform_report_dir(&self.meta.name);
+        self
+    }
+
+    /// Keep at most the newest `max` report files in the report directory
+    ///
+    /// Older ones are deleted whenever a new report is written, so
+    /// long-lived tools don't fill the disk. The report just written is
+    /// always kept, and 0 keeps them all, like not setting a limit.
+    pub fn max_reports(mut self, max: usize) -> Self {
+        self.retention.max_reports = Some(max).filter(|&max| max > 0);
+        self
+    }
+
+    /// Write one report file per distinct crash, and count how often each
+    /// happened
+    ///
+    /// A crash with the same [fingerprint](Report::fingerprint) as an
+    /// earlier one points to the earlier report file instead of getting
+    /// one of its own. How often and when each crash happened is kept in
+    /// `rollup.toml` in the report directory, see
+    /// [`read_rollup`](crate::read_rollup). Off by default.
+    pub fn rollup(mut self, rollup: bool) -> Self {
+        self.rollup = rollup;
+        self
+    }
+
+    /// Cut panic messages longer than `max` characters, see
+    /// [`Report::truncate_cause`]
+    ///
+    /// Messages are kept whole by default.
+    pub fn max_cause_chars(mut self, max: usize) -> Self {
+        self.max_cause_chars = Some(max);
+        self
+    }
+
+    /// Flush the report file, its signature and the report directory to
+    /// disk before the crash message points to them
+    ///
+    /// For embedded devices that often lose power right after a crash,
+    /// e.g. when a watchdog cuts it. This makes the crash path slower,
+    /// especially on flash storage, so it's off by default.
+    pub fn durable_writes(mut self, durable: bool) -> Self {
+        self.durable_writes = durable;
+        self
+    }
+
+    /// Create report and log files with the Unix permissions `mode`, e.g.
+    /// `0o600` so only the user can read them
+    ///
+    /// Crash reports can contain paths and other details that shouldn't be
+    /// readable by everyone on shared machines. Files that already exist
+    /// keep their permissions, and the umask still applies.
+    #[cfg(unix)]
+    pub fn file_mode(mut self, mode: u32) -> Self {
+        self.permissions.file = Some(mode);
+        self
+    }
+
+    /// Create the report directory, see [`report_dir`](Builder::report_dir),
+    /// with the Unix permissions `mode`, e.g. `0o700`
+    ///
+    /// Like [`file_mode`](Builder::file_mode), this only applies to
+    /// directories that don't exist yet.
+    #[cfg(unix)]
+    pub fn dir_mode(mut self, mode: u32) -> Self {
+        self.permissions.dir = Some(mode);
+        self
+    }
+
+    /// Delete report files older than `max_age` from the report directory
+    /// whenever a new report is written
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.retention.max_age = Some(max_age);
+        self
+    }
+
+    /// Write report files with `serializer`, in a format of the
+    /// application's own
+    ///
+    /// This takes precedence over [`Settings::report_format`].
+    pub fn report_serializer(
+        mut self,
+        serializer: impl ReportSerializer + 'static,
+    ) -> Self {
+        self.serializer = Some(Box::new(serializer));
+        self
+    }
+
+    /// Send every report to `sink` as well, see [`ReportSink`]
+    ///
+    /// Sinks get reports in debug builds too, and in the order they were
+    /// added, except that those that upload go last.
+    pub fn sink(mut self, sink: impl ReportSink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Send the reports of `profile` to `sink`, see
+    /// [`set_profile`](crate::set_profile)
+    ///
+    /// ```no_run
+    /// use human_panic_logger::{metadata, set_profile, Builder, FileSink};
+    ///
+    /// let profile = std::env::args().nth(2);
+    /// set_profile(profile.as_deref());
+    /// Builder::new(metadata!(), "app.log")
+    ///     .profile_sink("staging", FileSink::new("/srv/crashes/staging"))
+    ///     .profile_sink("production", FileSink::new("/srv/crashes/prod"))
+    ///     .install()
+    ///     .expect("crash reporting could not be set up");
+    /// ```
+    pub fn profile_sink(
+        mut self,
+        profile: impl Into<String>,
+        sink: impl ReportSink + 'static,
+    ) -> Self {
+        self.sinks.push(Box::new(crate::profile::ProfileSink {
+            profile: profile.into(),
+            sink: Box::new(sink),
+        }));
+        self
+    }
+
+    /// Wait at most `timeout` for the sinks that
+    /// [upload](ReportSink::uploads) reports, 5 seconds by default
+    ///
+    /// They run on a thread of their own, so stalled DNS lookups or
+    /// connections keep a crashed process from exiting no longer than
+    /// this. Sinks that queue reports, like `HttpSink`, send what didn't
+    /// get through on the next start.
+    pub fn upload_timeout(mut self, timeout: Duration) -> Self {
+        self.upload_timeout = timeout;
+        self
+    }
+
+    /// Keep the user's choice about sending reports over the network in
+    /// `file`, see [`UploadConsent`](crate::UploadConsent)
+    ///
+    /// Defaults to the [`platform_consent_file`](crate::platform_consent_file)
+    /// of the application.
+    pub fn consent_file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.consent_file = Some(file.into());
+        self
+    }
+
+    /// Call `observer` at the beginning and end of each stage of the hook,
+    /// e.g. to time the crash path in telemetry
+    ///
+    /// ```no_run
+    /// use human_panic_logger::{metadata, Builder, HookEvent};
+    ///
+    /// Builder::new(metadata!(), "app.log")
+    ///     .observe(|event| {
+    ///         if let HookEvent::End(stage, took) = event {
+    ///             eprintln!("{:?} took {:?}", stage, took);
+    ///         }
+    ///     })
+    ///     .install()
+    ///     .expect("crash reporting could not be set up");
+    /// ```
+    pub fn observe(
+        mut self,
+        observer: impl Fn(HookEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.observers.register(Box::new(observer));
+        self
+    }
+
+    /// Sign every report file with `key`, writing the signature next to
+    /// it, see [`signing::sign_file`](crate::signing::sign_file)
+    ///
+    /// Official builds embed the key, so the intake pipeline can reject
+    /// reports that were edited. Anyone with the binary can extract the key,
+    /// see [`signing`](crate::signing).
+    #[cfg(feature = "signing")]
+    pub fn sign_reports(mut self, key: crate::signing::SigningKey) -> Self {
+        self.signing_key = Some(key);
+        self
+    }
+
+    /// Save a screenshot next to each report file if `consent` agrees,
+    /// see [`ask_screenshot_consent`](crate::ask_screenshot_consent)
+    ///
+    /// `consent` is called after the crash message is shown, and nothing is
+    /// captured unless it returns `true`. GUI applications can show a
+    /// dialog of their own in it, or go by a choice the user made in their
+    /// settings.
+    ///
+    /// ```no_run
+    /// use human_panic_logger::{ask_screenshot_consent, metadata, Builder};
+    ///
+    /// Builder::new(metadata!(), "app.log")
+    ///     .screenshot(ask_screenshot_consent)
+    ///     .install()
+    ///     .expect("crash reporting could not be set up");
+    /// ```
+    #[cfg(feature = "screenshot")]
+    pub fn screenshot(
+        mut self,
+        consent: impl Fn(&Report) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.screenshot_consent = Some(Box::new(consent));
+        self
+    }
+
+    /// Whether this is a debug build: debug builds print the standard panic
+    /// message, release builds the friendly one
+    ///
+    /// Defaults to whether `human-panic-logger` itself was built with debug
+    /// assertions, which matches the application unless its profile
+    /// overrides dependencies.
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    /// Leave panics to the previously installed hook while a debugger is
+    /// attached, so developers get the standard output instead of a crash
+    /// message and a report
+    ///
+    /// Debuggers are detected on Linux, Android, macOS and Windows.
+    pub fn defer_to_debugger(mut self, defer: bool) -> Self {
+        self.defer_to_debugger = defer;
+        self
+    }
+
+    /// Treat panics outside the crates whose names start with one of
+    /// `prefixes` as described by `foreign`
+    ///
+    /// A panic's origin is the innermost frame of its backtrace outside the
+    /// standard library, so this needs the `backtrace` feature. Panics whose
+    /// origin can't be told are always reported.
+    ///
+    /// ```no_run
+    /// use human_panic_logger::{metadata, Builder, ForeignPanics};
+    ///
+    /// Builder::new(metadata!(), "host.log")
+    ///     .own_crates(["plugin_host"], ForeignPanics::MessageOnly)
+    ///     .install()
+    ///     .expect("crash reporting could not be set up");
+    /// ```
+    pub fn own_crates<I>(mut self, prefixes: I, foreign: ForeignPanics) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.own_crates = OwnCrates {
+            prefixes: prefixes
+                .into_iter()
+                .map(|prefix| prefix.into().replace('-', "_"))
+                .collect(),
+            foreign,
+        };
+        self
+    }
+
+    /// Include a snapshot from `provider` in every report, in the section
+    /// `[state.<name>]`
+    #[cfg(feature = "state")]
+    pub fn state(
+        mut self,
+        name: impl Into<String>,
+        provider: impl crate::StateProvider + 'static,
+    ) -> Self {
+        self.providers.register(name.into(), Box::new(provider));
+        self
+    }
+
+    /// Apply the crash reporting arguments users passed, see
+    /// [`crash_report_args`](crate::crash_report_args)
+    ///
+    /// `--crash-report-dir` takes precedence over
+    /// [`report_dir`](Builder::report_dir), `--no-crash-reports` turns
+    /// [`crash_reports`](Builder::crash_reports) off and
+    /// `--show-crash-report` turns [`show_report`](Builder::show_report)
+    /// on.
+    #[cfg(feature = "clap")]
+    pub fn cli_args(mut self, matches: &clap::ArgMatches) -> Self {
+        if let Some(dir) = crate::cli::report_dir(matches) {
+            self.report_dir = Some(dir);
+        }
+        if crate::cli::no_reports(matches) {
+            self.crash_reports = false;
+        }
+        if crate::cli::show_report(matches) {
+            self.settings.show_report = true;
+        }
+        self
+    }
+
+    /// Set up the logger and install the panic hook
+    ///
+    /// As with the default hook, nothing is installed if `RUST_BACKTRACE`
+    /// is set, so developers get the standard output.
+    ///
+    /// With the `disabled` feature this does nothing at all, neither the
+    /// logger nor the hook are set up, and the hook isn't even compiled.
+    ///
+    /// Nothing is set up either if the configuration is wrong, see
+    /// [`SetupError`], so applications can fix it and try again.
+    pub fn install(self) -> Result<(), SetupError> {
+        #[cfg(feature = "disabled")]
+        {
+            drop(self);
+            Ok(())
+        }
+        #[cfg(not(feature = "disabled"))]
+        self.install_hook()
+    }
+
+    /// Set up the logger and install the panic hook, see
+    /// [`install`](Builder::install)
+    #[cfg(not(feature = "disabled"))]
+    fn install_hook(self) -> Result<(), SetupError> {
+        let Builder {
+            meta,
+            resolvers,
+            log_file,
+            mut settings,
+            message_template_file,
+            level,
+            loggers,
+            debug,
+            defer_to_debugger,
+            own_crates,
+            serializer,
+            sinks,
+            upload_timeout,
+            consent_file,
+            naming,
+            report_dir,
+            retention,
+            rollup,
+            crash_reports,
+            durable_writes,
+            max_cause_chars,
+            open_issue,
+            permissions,
+            observers,
+            #[cfg(feature = "signing")]
+            signing_key,
+            #[cfg(feature = "state")]
+            providers,
+            #[cfg(feature = "screenshot")]
+            screenshot_consent,
+        } = self;
+        let serializer =
+            serializer.unwrap_or_else(|| settings.report_serializer());
+
+        // everything that can fail is checked before the logger is set up,
+        // which can only happen once
+        meta.issue_tracker.check()?;
+        settings.check()?;
+        for sink in sinks.iter() {
+            sink.check().map_err(SetupError::SinkConfig)?;
+        }
+        // where reports go, unless users moved them elsewhere
+        let configured_dir = report_dir
+            .as_deref()
+            .and_then(|dir| crate::format::report_dir(Some(dir)));
+        if let Some(dir) = configured_dir {
+            permissions
+                .create_dir_all(&dir)
+                .and_then(|()| crate::doctor::probe_dir(&dir))
+                .map_err(|error| SetupError::ReportDirUnwritable {
+                    path: dir,
+                    error,
+                })?;
+        }
+        // the log file may be in a directory shared with other users
+        let log_file = crate::shared::own_file(&log_file);
+        let log = open_log_file_with(&log_file, permissions).map_err(
+            |error| SetupError::LogFileUnwritable {
+                path: log_file.clone(),
+                error,
+            },
+        )?;
+
+        let level = level.unwrap_or(if debug {
+            LevelFilter::Debug
+        } else {
+            LevelFilter::Info
+        });
+        let mut all: Vec<Box<dyn SharedLogger>> =
+            vec![WriteLogger::new(level, Config::default(), log)];
+        all.extend(loggers);
+        CombinedLogger::init(all).map_err(SetupError::LoggerAlreadyInit)?;
+
+        if let Some(file) = &message_template_file {
+            if let Err(e) = settings.load_message_template(file) {
+                log::warn!(
+                    "Could not load the crash message template from \"{}\", \
+                     using the built-in one: {}",
+                    file.display(),
+                    e
+                );
+            }
+        }
+        crate::session::start(&log_file);
+        #[cfg(feature = "backtrace")]
+        if let Some(dir) = crate::format::report_dir(report_dir.as_deref()) {
+            crate::symbol_cache::start(&dir, &meta, permissions);
+        }
+        let install = std::env::var("RUST_BACKTRACE").is_err();
+        doctor::register_installation(&log_file, report_dir.clone(), install);
+        if !install {
+            return Ok(());
+        }
+
+        let sinks: Sinks = Arc::new(sinks);
+        let consent_file = consent_file
+            .or_else(|| crate::consent::platform_consent_file(&meta.name));
+        crate::sink::install(&sinks);
+        let reporter = Reporter::spawn(&sinks, upload_timeout);
+
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info: &PanicHookInfo| {
+            let _guard = match HookGuard::enter() {
+                Some(guard) => guard,
+                None => {
+                    // our own code or another thread panicked while a crash
+                    // is reported; leave it to the default message rather
+                    // than starting over
+                    default_hook(info);
+                    return;
+                }
+            };
+
+            if defer_to_debugger && crate::debugger::attached() {
+                default_hook(info);
+                return;
+            }
+
+            // call standard hook in debug mode, unless there must be no
+            // output but the protocol
+            if debug && settings.output == MessageOutput::Stderr {
+                default_hook(info);
+            }
+
+            let (meta, report) = observers.stage(HookStage::Capture, || {
+                let meta = resolve_metadata(&meta, &resolvers);
+                let mut report = Report::new(info, &meta);
+                if let Some(max) = max_cause_chars {
+                    report.truncate_cause(max);
+                }
+                #[cfg(feature = "state")]
+                {
+                    report.state = providers.snapshot();
+                }
+                (meta, report)
+            });
+            let response = own_crates.response(&report);
+            if response == ForeignPanics::Passthrough {
+                if !debug {
+                    default_hook(info);
+                }
+                return;
+            }
+
+            // output panic to logfile, before anything touches stderr: if
+            // the app restored the default SIGPIPE handler, printing to a
+            // closed pipe ends the process on the spot
+            let rendered =
+                observers.stage(HookStage::Format, || report.to_string());
+            observers.stage(HookStage::Log, || {
+                log::error!("Panic! :: {}\n{}", report.summary(), rendered);
+            });
+            crate::session::mark_crashed();
+            let collect = crash_reports && response == ForeignPanics::Report;
+
+            // do human error message in release mode; the crash is in the
+            // log already, so there's nothing left to do if stderr is gone,
+            // and panicking here would only abort
+            let report_file = if debug {
+                None
+            } else {
+                // on WASI there may be nowhere to write reports to
+                let dir = crate::format::report_dir(report_dir.as_deref())
+                    .map(crate::profile::report_dir);
+                let report_file = dir
+                    .filter(|_| collect)
+                    .map(|dir| {
+                        observers.stage(HookStage::Write, || {
+                            // a profile's directory may not exist yet
+                            let _ = permissions.create_dir_all(&dir);
+                            let write = || {
+                                report.write_file_with(
+                                    &dir,
+                                    &*serializer,
+                                    &naming,
+                                    permissions,
+                                    durable_writes,
+                                )
+                            };
+                            let file = match rollup {
+                                true => crate::rollup::record(
+                                    &dir,
+                                    &report,
+                                    permissions,
+                                    write,
+                                ),
+                                false => write(),
+                            };
+                            #[cfg(feature = "signing")]
+                            if let (Ok(file), Some(key)) = (&file, &signing_key)
+                            {
+                                let signature = crate::signing::sign_file_with(
+                                    file,
+                                    key,
+                                    permissions,
+                                );
+                                if let (Ok(signature), true) =
+                                    (signature, durable_writes)
+                                {
+                                    let _ = sync_file(&signature);
+                                }
+                            }
+                            if durable_writes && file.is_ok() {
+                                let _ = sync_dir(&dir);
+                            }
+                            retention.prune(
+                                &dir,
+                                &naming,
+                                serializer.extension(),
+                            );
+                            file
+                        })
+                    })
+                    .and_then(Result::ok);
+                let plugin_settings;
+                let settings = match &report.plugin {
+                    Some(plugin) => {
+                        plugin_settings = settings.for_plugin(plugin);
+                        &plugin_settings
+                    }
+                    None => &settings,
+                };
+                let explanation = match settings.explain_panics {
+                    true => crate::plain::explain(&report.cause),
+                    false => None,
+                };
+                let shown_report = settings.show_report().then(|| {
+                    let mut text = Vec::new();
+                    let _ =
+                        settings.report_serializer().write(&report, &mut text);
+                    String::from_utf8_lossy(&text).into_owned()
+                });
+                let links = match &report.plugin {
+                    // crashes in plugins go to the plugin's authors instead
+                    Some(_) => MessageDetails {
+                        explanation,
+                        report: shown_report,
+                        ..MessageDetails::default()
+                    },
+                    None => MessageDetails {
+                        explanation,
+                        report: shown_report,
+                        issue: crate::format::issue_url(
+                            &meta,
+                            &report,
+                            report_file.as_deref(),
+                            settings.issue_template.as_deref(),
+                        ),
+                        mail: meta.mailto(&mail_body(
+                            &report,
+                            report_file.as_deref().unwrap_or(&log_file),
+                        )),
+                    },
+                };
+                let _ = observers.stage(HookStage::Print, || {
+                    print_msg_with(
+                        &log_file,
+                        report_file.as_deref(),
+                        &meta,
+                        info.location(),
+                        settings,
+                        &links,
+                    )
+                });
+                if open_issue && settings.output == MessageOutput::Stderr {
+                    if let Some(issue_url) = &links.issue {
+                        crate::browser::open(issue_url);
+                    }
+                }
+                #[cfg(feature = "screenshot")]
+                if let (Some(file), Some(consent)) =
+                    (&report_file, &screenshot_consent)
+                {
+                    if consent(&report) {
+                        match crate::screenshot::save(file, permissions) {
+                            Some(screenshot) => log::info!(
+                                "Saved a screenshot of the crash to \"{}\"",
+                                screenshot.display()
+                            ),
+                            None => {
+                                log::warn!("Could not capture the screen")
+                            }
+                        }
+                    }
+                }
+                report_file
+            };
+
+            // sinks go last: the report file is written and the message
+            // printed by now, so users can look at what they're asked to
+            // send
+            if collect {
+                observers.stage(HookStage::Submit, || {
+                    notify_system(&report);
+                    if let Err(e) = crate::supervisor::notify(&report) {
+                        log::warn!("Could not notify the supervisor: {}", e);
+                    }
+                    for sink in sinks.iter().filter(|sink| !sink.uploads()) {
+                        if let Err(e) = sink.submit(&report) {
+                            log::warn!(
+                                "Could not submit the crash report: {}",
+                                e
+                            );
+                        }
+                    }
+                    // ask only if a sink uploads
+                    if sinks.iter().any(|sink| sink.uploads())
+                        && crate::consent::allowed(
+                            consent_file.as_deref(),
+                            &report,
+                        )
+                    {
+                        let delivery = reporter.submit(&report);
+                        let saved = report_file.as_deref().unwrap_or(&log_file);
+                        let _ = crate::print_upload_failure(
+                            delivery,
+                            saved,
+                            &settings,
+                        );
+                    }
+                });
+            }
+        }));
+
+        // `cargo human-panic test` builds applications with this cfg to
+        // check their whole crash path
+        #[cfg(human_panic_inject)]
+        panic!("{}", crate::INJECTED_PANIC);
+        #[cfg(not(human_panic_inject))]
+        Ok(())
+    }
+}
+
+/// Why [`Builder::install`] couldn't set up crash reporting
+///
+/// Each reason has a stable [`code`](SetupError::code), e.g. for telemetry
+/// or support scripts, and applications can branch on it:
+///
+/// ```no_run
+/// use human_panic_logger::{metadata, Builder, SetupError};
+///
+/// match Builder::new(metadata!(), "app.log").install() {
+///     Ok(()) => {}
+///     // a test harness set up logging already, crashes still go there
+///     Err(SetupError::LoggerAlreadyInit(_)) => {}
+///     Err(e) => eprintln!("crash reporting is off [{}]: {}", e.code(), e),
+/// }
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SetupError {
+    /// Another logger was set up already, by the application or an earlier
+    /// call to [`Builder::install`]
+    LoggerAlreadyInit(log::SetLoggerError),
+    /// The log file can't be opened for writing
+    LogFileUnwritable {
+        /// The configured log file
+        path: PathBuf,
+        /// Why opening it failed
+        error: io::Error,
+    },
+    /// No report files can be created in the report directory, see
+    /// [`Builder::report_dir`]
+    ReportDirUnwritable {
+        /// The configured report directory
+        path: PathBuf,
+        /// Why creating it or a file in it failed
+        error: io::Error,
+    },
+    /// A template, e.g. that of a
+    /// [custom issue tracker](crate::IssueTracker::Custom), is malformed
+    InvalidTemplate {
+        /// The template
+        template: String,
+        /// What is wrong with it
+        reason: String,
+    },
+    /// A sink is misconfigured, see [`ReportSink::check`]
+    SinkConfig(Box<dyn Error + Send + Sync>),
+}
+
+impl SetupError {
+    /// A short code for the reason, which stays the same across versions
+    ///
+    /// `logger-already-init`, `log-file-unwritable`,
+    /// `report-dir-unwritable`, `invalid-template` or `sink-config`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SetupError::LoggerAlreadyInit(_) => "logger-already-init",
+            SetupError::LogFileUnwritable { .. } => "log-file-unwritable",
+            SetupError::ReportDirUnwritable { .. } => "report-dir-unwritable",
+            SetupError::InvalidTemplate { .. } => "invalid-template",
+            SetupError::SinkConfig(_) => "sink-config",
+        }
+    }
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::LoggerAlreadyInit(_) => {
+                write!(f, "a logger was set up already")
+            }
+            SetupError::LogFileUnwritable { path, error } => write!(
+                f,
+                "the log file \"{}\" can't be written: {}",
+                crate::long_path::for_display(path).display(),
+                error
+            ),
+            SetupError::ReportDirUnwritable { path, error } => write!(
+                f,
+                "no report files can be created in \"{}\": {}",
+                crate::long_path::for_display(path).display(),
+                error
+            ),
+            SetupError::InvalidTemplate { template, reason } => {
+                write!(f, "invalid template \"{}\": {}", template, reason)
+            }
+            SetupError::SinkConfig(error) => {
+                write!(f, "a report sink is misconfigured: {}", error)
+            }
+        }
+    }
+}
+
+impl Error for SetupError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SetupError::LoggerAlreadyInit(error) => Some(error),
+            SetupError::LogFileUnwritable { error, .. }
+            | SetupError::ReportDirUnwritable { error, .. } => Some(error),
+            SetupError::SinkConfig(error) => Some(&**error),
+            SetupError::InvalidTemplate { .. } => None,
+        }
+    }
+}
+
+/// Functions filling in metadata at crash time, see
+/// [`Builder::resolve_metadata`]
+type Resolvers = Vec<Box<dyn Fn(&mut Metadata) + Send + Sync>>;
+
+/// Whether the user agrees to a screenshot of a crash, see
+/// [`Builder::screenshot`]
+#[cfg(feature = "screenshot")]
+type ScreenshotConsent = Box<dyn Fn(&Report) -> bool + Send + Sync>;
+
+/// `meta` with what `resolvers` fill in at the time of the crash
+fn resolve_metadata<'a>(
+    meta: &'a Metadata,
+    resolvers: &Resolvers,
+) -> Cow<'a, Metadata> {
+    if resolvers.is_empty() {
+        return Cow::Borrowed(meta);
+    }
+    let mut meta = meta.clone();
+    for resolve in resolvers {
+        resolve(&mut meta);
+    }
+    Cow::Owned(meta)
+}
+
+/// The body of an email about `report`, whose details are in `file`
+fn mail_body(report: &Report, file: &Path) -> String {
+    format!(
+        "{}\n\nPlease find the crash details attached. They were written \
+         to:\n{}\n",
+        report.summary(),
+        crate::long_path::for_display(file).display()
+    )
+}
+
+/// Whether some thread is running the hook
+static IN_HOOK: AtomicBool = AtomicBool::new(false);
+
+/// Marks the hook as running, until dropped
+///
+/// The runtime aborts on a panic in the hook's own thread, but the hook
+/// also runs code on other threads, like sinks and symbol resolution, and
+/// calls into the application, like state providers. A panic there would
+/// run the hook again, which must not start over: a bug in formatting or a
+/// sink would then loop, or report crashes of its own. Panics of other
+/// threads while a crash is reported are left to the default message.
+struct HookGuard;
+
+impl HookGuard {
+    /// Enter the hook, unless some thread is in it already
+    fn enter() -> Option<HookGuard> {
+        match IN_HOOK.swap(true, Ordering::SeqCst) {
+            true => None,
+            false => Some(HookGuard),
+        }
+    }
+}
+
+impl Drop for HookGuard {
+    fn drop(&mut self) {
+        IN_HOOK.store(false, Ordering::SeqCst);
+    }
+}
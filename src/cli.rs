@@ -0,0 +1,89 @@
+//! Standard crash reporting arguments for clap-based CLIs
+//!
+//! With the `clap` feature, [`crash_report_args`] adds hidden
+//! `--crash-report-dir <DIR>`, `--no-crash-reports` and
+//! `--show-crash-report` arguments to a command, and
+//! [`Builder::cli_args`](crate::Builder::cli_args) applies what users
+//! passed, so every tool spells them the same way.
+
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use std::path::PathBuf;
+
+/// The id of the argument overriding the report directory
+const REPORT_DIR_ARG: &str = "crash-report-dir";
+/// The id of the argument turning report files off
+const NO_REPORTS_ARG: &str = "no-crash-reports";
+/// The id of the argument printing the report with the crash message
+const SHOW_REPORT_ARG: &str = "show-crash-report";
+
+/// Add the crash reporting arguments to `command`
+///
+/// They are hidden from the help, so they don't clutter it for users who
+/// never need them, and global, so they work with every subcommand.
+///
+/// ```
+/// use human_panic_logger::{crash_report_args, metadata, Builder};
+///
+/// let matches = crash_report_args(clap::Command::new("app"))
+///     .try_get_matches_from(["app", "--crash-report-dir", "/var/crash"])?;
+/// let builder = Builder::new(metadata!(), "app.log").cli_args(&matches);
+/// # drop(builder);
+/// # Ok::<(), clap::Error>(())
+/// ```
+pub fn crash_report_args(command: Command) -> Command {
+    command
+        .arg(
+            Arg::new(REPORT_DIR_ARG)
+                .long(REPORT_DIR_ARG)
+                .value_name("DIR")
+                .value_parser(value_parser!(PathBuf))
+                .help("Write crash reports into DIR")
+                .hide(true)
+                .global(true),
+        )
+        .arg(
+            Arg::new(NO_REPORTS_ARG)
+                .long(NO_REPORTS_ARG)
+                .action(ArgAction::SetTrue)
+                .help("Only log crashes, without writing or sending reports")
+                .hide(true)
+                .global(true),
+        )
+        .arg(
+            Arg::new(SHOW_REPORT_ARG)
+                .long(SHOW_REPORT_ARG)
+                .action(ArgAction::SetTrue)
+                .help("Print the whole crash report with the crash message")
+                .hide(true)
+                .global(true),
+        )
+}
+
+/// The report directory passed in `matches`, if any
+pub(crate) fn report_dir(matches: &ArgMatches) -> Option<PathBuf> {
+    // commands without the arguments just don't have them set
+    matches
+        .try_get_one::<PathBuf>(REPORT_DIR_ARG)
+        .ok()
+        .flatten()
+        .cloned()
+}
+
+/// Whether `matches` turn crash reports off
+pub(crate) fn no_reports(matches: &ArgMatches) -> bool {
+    flag(matches, NO_REPORTS_ARG)
+}
+
+/// Whether `matches` ask for the report with the crash message
+pub(crate) fn show_report(matches: &ArgMatches) -> bool {
+    flag(matches, SHOW_REPORT_ARG)
+}
+
+fn flag(matches: &ArgMatches, id: &str) -> bool {
+    matches
+        .try_get_one::<bool>(id)
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or(false)
+}
@@ -0,0 +1,349 @@
+//! Dates and times of crashes
+//!
+//! With the `local-time` feature, times are in the user's time zone, which
+//! chrono looks up. Without it they are in UTC, which std alone can tell.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct",
+    "Nov", "Dec",
+];
+
+/// A point in time together with the UTC offset it is shown in
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DateTime {
+    /// Seconds since the Unix epoch
+    secs: i64,
+    nanos: u32,
+    /// Seconds east of UTC
+    offset: i32,
+}
+
+impl DateTime {
+    /// The current time
+    pub(crate) fn now() -> Self {
+        DateTime::from(SystemTime::now())
+    }
+
+    /// The time of an [RFC 3339](https://www.rfc-editor.org/rfc/rfc3339)
+    /// timestamp like `2024-05-01T12:00:00.5+02:00`, in its own offset
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        let number = |range: std::ops::Range<usize>| -> Option<i64> {
+            let digits = text.get(range)?;
+            match digits.bytes().all(|b| b.is_ascii_digit()) {
+                true => digits.parse().ok(),
+                false => None,
+            }
+        };
+        let bytes = text.as_bytes();
+        let separators = [(4, b'-'), (7, b'-'), (13, b':'), (16, b':')];
+        if separators.iter().any(|&(idx, sep)| bytes.get(idx) != Some(&sep))
+            || !matches!(bytes.get(10), Some(b'T' | b't' | b' '))
+        {
+            return None;
+        }
+        let (year, month, day) =
+            (number(0..4)?, number(5..7)?, number(8..10)?);
+        let (hour, minute, second) =
+            (number(11..13)?, number(14..16)?, number(17..19)?);
+        if !(1..=12).contains(&month)
+            || !(1..=31).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
+            return None;
+        }
+
+        let mut rest = &text[19..];
+        let mut nanos = 0;
+        if let Some(fraction) = rest.strip_prefix('.') {
+            let len = fraction
+                .bytes()
+                .take_while(u8::is_ascii_digit)
+                .count();
+            if len == 0 {
+                return None;
+            }
+            let digits: String =
+                fraction[..len].chars().chain("000000000".chars()).collect();
+            nanos = digits[..9].parse().ok()?;
+            rest = &fraction[len..];
+        }
+        let offset = match rest {
+            "Z" | "z" => 0,
+            _ => {
+                let sign = match rest.as_bytes().first() {
+                    Some(b'+') => 1,
+                    Some(b'-') => -1,
+                    _ => return None,
+                };
+                if rest.len() != 6 || rest.as_bytes()[3] != b':' {
+                    return None;
+                }
+                let hours: i32 = rest[1..3].parse().ok()?;
+                let minutes: i32 = rest[4..6].parse().ok()?;
+                sign * (hours * 3600 + minutes * 60)
+            }
+        };
+
+        let days = days_from_civil(year, month, day);
+        let local = days * 86_400 + hour * 3600 + minute * 60 + second;
+        Some(DateTime {
+            secs: local - i64::from(offset),
+            nanos,
+            offset,
+        })
+    }
+
+    /// The time as a [`SystemTime`]
+    #[cfg(feature = "sentry")]
+    pub(crate) fn system_time(self) -> SystemTime {
+        use std::convert::TryFrom;
+        use std::time::Duration;
+
+        let nanos = Duration::from_nanos(u64::from(self.nanos));
+        match u64::try_from(self.secs) {
+            Ok(secs) => UNIX_EPOCH + Duration::from_secs(secs) + nanos,
+            Err(_) => {
+                UNIX_EPOCH - Duration::from_secs(self.secs.unsigned_abs())
+                    + nanos
+            }
+        }
+    }
+
+    /// The time in RFC 3339 format, with as many fractional digits as it
+    /// needs, e.g. `2024-05-01T12:00:00.123+02:00`
+    pub(crate) fn rfc3339(self) -> String {
+        let fraction = match self.nanos {
+            0 => String::new(),
+            n if n % 1_000_000 == 0 => format!(".{:03}", n / 1_000_000),
+            n if n % 1_000 == 0 => format!(".{:06}", n / 1_000),
+            n => format!(".{:09}", n),
+        };
+        let (sign, minutes) = self.offset_minutes();
+        format!(
+            "{}{}{}{:02}:{:02}",
+            self.format("%Y-%m-%dT%H:%M:%S"),
+            fraction,
+            sign,
+            minutes / 60,
+            minutes % 60
+        )
+    }
+
+    /// The time laid out by `pattern`, where `%Y`, `%m`, `%d`, `%e`, `%H`,
+    /// `%M`, `%S`, `%a`, `%b` and `%z` stand for what they do in `strftime`,
+    /// and `%.3f` for the milliseconds like in chrono
+    pub(crate) fn format(self, pattern: &str) -> String {
+        let local = self.secs + i64::from(self.offset);
+        let days = local.div_euclid(86_400);
+        let seconds = local.rem_euclid(86_400);
+        let (year, month, day) = civil_from_days(days);
+        let weekday = (days + 4).rem_euclid(7) as usize;
+
+        let mut out = String::with_capacity(pattern.len() + 8);
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => out.push_str(&format!("{:04}", year)),
+                Some('m') => out.push_str(&format!("{:02}", month)),
+                Some('d') => out.push_str(&format!("{:02}", day)),
+                Some('e') => out.push_str(&format!("{:>2}", day)),
+                Some('H') => out.push_str(&format!("{:02}", seconds / 3600)),
+                Some('M') => {
+                    out.push_str(&format!("{:02}", seconds / 60 % 60))
+                }
+                Some('S') => out.push_str(&format!("{:02}", seconds % 60)),
+                Some('.') if chars.as_str().starts_with("3f") => {
+                    chars.nth(1);
+                    out.push_str(&format!(".{:03}", self.nanos / 1_000_000));
+                }
+                Some('z') => {
+                    let (sign, minutes) = self.offset_minutes();
+                    out.push_str(&format!(
+                        "{}{:02}{:02}",
+                        sign,
+                        minutes / 60,
+                        minutes % 60
+                    ));
+                }
+                Some('a') => out.push_str(WEEKDAYS[weekday]),
+                Some('b') => out.push_str(MONTHS[month as usize - 1]),
+                Some(other) => {
+                    out.push('%');
+                    out.push(other);
+                }
+                None => out.push('%'),
+            }
+        }
+        out
+    }
+
+    /// The sign of the offset, and the offset in whole minutes
+    fn offset_minutes(self) -> (char, u32) {
+        let sign = if self.offset < 0 { '-' } else { '+' };
+        // historic offsets can have seconds, which RFC 3339 can't show
+        (sign, (self.offset.unsigned_abs() + 30) / 60)
+    }
+}
+
+impl From<SystemTime> for DateTime {
+    /// `time` in the user's time zone, or in UTC without `local-time`
+    fn from(time: SystemTime) -> Self {
+        let (secs, nanos) = match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => (since.as_secs() as i64, since.subsec_nanos()),
+            Err(e) => {
+                let before = e.duration();
+                let secs = -(before.as_secs() as i64);
+                match before.subsec_nanos() {
+                    0 => (secs, 0),
+                    nanos => (secs - 1, 1_000_000_000 - nanos),
+                }
+            }
+        };
+        DateTime {
+            secs,
+            nanos,
+            offset: offset_at(time),
+        }
+    }
+}
+
+/// The offset of the user's time zone from UTC at `time`, in seconds
+#[cfg(feature = "local-time")]
+fn offset_at(time: SystemTime) -> i32 {
+    use chrono::Offset;
+
+    chrono::DateTime::<chrono::Local>::from(time)
+        .offset()
+        .fix()
+        .local_minus_utc()
+}
+
+#[cfg(not(feature = "local-time"))]
+fn offset_at(_time: SystemTime) -> i32 {
+    0
+}
+
+/// The days since 1970-01-01 of a date in the proleptic Gregorian calendar
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html>.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = (month + 9) % 12;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100
+        + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The year, month and day of a number of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_count_leap_days_of_the_gregorian_calendar() {
+        let dates = [
+            ((1970, 1, 1), 0),
+            ((1969, 12, 31), -1),
+            ((2000, 2, 29), 11_016),
+            ((2000, 3, 1), 11_017),
+            // not a leap year, as it is divisible by 100 but not 400
+            ((1900, 3, 1), -25_508),
+            ((1600, 2, 29), -135_081),
+            ((2024, 12, 31), 20_088),
+        ];
+        for ((year, month, day), days) in dates {
+            assert_eq!(days_from_civil(year, month, day), days);
+            assert_eq!(
+                civil_from_days(days),
+                (year, month as u32, day as u32)
+            );
+        }
+        assert_eq!(civil_from_days(-25_509), (1900, 2, 28));
+        assert_eq!(civil_from_days(20_088 - 365), (2024, 1, 1));
+    }
+
+    #[test]
+    fn days_round_trip_across_four_centuries() {
+        for days in -146_097..146_097 {
+            let (year, month, day) = civil_from_days(days);
+            let back =
+                days_from_civil(year, i64::from(month), i64::from(day));
+            assert_eq!(back, days);
+        }
+    }
+
+    #[test]
+    fn offsets_carry_times_across_the_year_boundary() {
+        let time = DateTime::parse("2023-12-31T23:30:00-01:00").unwrap();
+        assert_eq!(time.rfc3339(), "2023-12-31T23:30:00-01:00");
+        assert_eq!(time.format("%a %e %b %Y %z"), "Sun 31 Dec 2023 -0100");
+
+        let utc = DateTime { offset: 0, ..time };
+        assert_eq!(utc.rfc3339(), "2024-01-01T00:30:00+00:00");
+        assert_eq!(utc.format("%a %e %b %Y"), "Mon  1 Jan 2024");
+    }
+
+    #[test]
+    fn timestamps_round_trip_with_their_fraction() {
+        let timestamps = [
+            "2024-02-29T12:00:00+02:00",
+            "2024-05-01T12:00:00.5+00:00",
+            "1969-12-31T23:59:59.123456+05:30",
+            "2100-03-01T00:00:00.000000001-08:00",
+        ];
+        for timestamp in timestamps {
+            let time = DateTime::parse(timestamp).unwrap();
+            let expected = timestamp.replace(".5+", ".500+");
+            assert_eq!(time.rfc3339(), expected);
+        }
+        assert_eq!(
+            DateTime::parse("2024-05-01T12:00:00Z").unwrap().format("%.3f"),
+            ".000"
+        );
+    }
+
+    #[test]
+    fn malformed_timestamps_are_rejected() {
+        let malformed = [
+            "",
+            "2024-05-01",
+            "2024-13-01T00:00:00Z",
+            "2024-05-01T24:00:00Z",
+            "2024-05-01T12:00:00",
+            "2024-05-01T12:00:00.Z",
+            "2024-05-01T12:00:00+0200",
+            "2024/05/01T12:00:00Z",
+        ];
+        for timestamp in malformed {
+            assert!(DateTime::parse(timestamp).is_none(), "{}", timestamp);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Colored output, or a stand-in writing plain text when the `color`
+//! feature is disabled
+
+#[cfg(feature = "color")]
+pub(crate) use termcolor::{
+    Buffer, BufferWriter, Color, ColorChoice, ColorSpec, WriteColor,
+};
+
+#[cfg(not(feature = "color"))]
+pub(crate) use self::plain::*;
+
+/// The subset of `termcolor` we use, without the colors
+#[cfg(not(feature = "color"))]
+mod plain {
+    use std::io::{self, Write};
+
+    #[derive(Debug, Clone, Copy)]
+    pub(crate) enum ColorChoice {
+        Never,
+        Auto,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    #[allow(dead_code)]
+    pub(crate) enum Color {
+        Black,
+        Red,
+        Green,
+        Yellow,
+        Blue,
+        Magenta,
+        Cyan,
+        White,
+        Ansi256(u8),
+        Rgb(u8, u8, u8),
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub(crate) struct ColorSpec;
+
+    impl ColorSpec {
+        pub(crate) fn new() -> Self {
+            ColorSpec
+        }
+
+        pub(crate) fn set_fg(&mut self, _: Option<Color>) -> &mut Self {
+            self
+        }
+
+        pub(crate) fn set_bg(&mut self, _: Option<Color>) -> &mut Self {
+            self
+        }
+
+        pub(crate) fn set_bold(&mut self, _: bool) -> &mut Self {
+            self
+        }
+    }
+
+    pub(crate) trait WriteColor: Write {
+        fn set_color(&mut self, _: &ColorSpec) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn reset(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    pub(crate) struct Buffer(Vec<u8>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl WriteColor for Buffer {}
+
+    pub(crate) struct BufferWriter;
+
+    impl BufferWriter {
+        pub(crate) fn stderr(_: ColorChoice) -> Self {
+            BufferWriter
+        }
+
+        pub(crate) fn buffer(&self) -> Buffer {
+            Buffer(Vec::new())
+        }
+
+        pub(crate) fn print(&self, buffer: &Buffer) -> io::Result<()> {
+            io::stderr().lock().write_all(&buffer.0)
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Compressed report files
+//!
+//! Reports with long backtraces and large state snapshots add up. With the
+//! `gzip` or `zstd` feature, [`Compressed`] wraps any
+//! [`ReportSerializer`] to compress what it writes.
+
+use crate::{Report, ReportSerializer};
+use std::io;
+
+/// A compression format for report files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// gzip, which every platform can open, with the `gzip` feature
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Zstandard, which compresses better and faster, with the `zstd`
+    /// feature
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// The file extension this compression adds, without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => "gz",
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => "zst",
+        }
+    }
+
+    /// The compression a file with the extension `extension` was written
+    /// with, if it's one we can read
+    #[cfg(feature = "serde")]
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            #[cfg(feature = "gzip")]
+            "gz" => Some(Compression::Gzip),
+            #[cfg(feature = "zstd")]
+            "zst" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn decompress(self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        use std::io::Read;
+
+        let mut out = Vec::new();
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                flate2::read::GzDecoder::new(bytes).read_to_end(&mut out)?;
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                zstd::Decoder::new(bytes)?.read_to_end(&mut out)?;
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Decompress the contents of the file at `path`, if its extension is that
+/// of a [`Compression`]
+#[cfg(feature = "serde")]
+pub(crate) fn decompress_file(
+    path: &std::path::Path,
+    bytes: Vec<u8>,
+) -> io::Result<Vec<u8>> {
+    let compression = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(Compression::from_extension);
+    match compression {
+        Some(compression) => compression.decompress(&bytes),
+        None => Ok(bytes),
+    }
+}
+
+/// Compresses the output of another serializer
+///
+/// The extension of the files is the inner serializer's followed by the
+/// compression's, e.g. `toml.gz`.
+///
+/// ```
+/// # #[cfg(feature = "gzip")] {
+/// use human_panic_logger::{
+///     metadata, Builder, Compressed, Compression, ReportFormat,
+/// };
+///
+/// let gzip = Compressed::new(ReportFormat::Toml, Compression::Gzip);
+/// let builder = Builder::new(metadata!(), "app.log").report_serializer(gzip);
+/// # drop(builder);
+/// # }
+/// ```
+pub struct Compressed<S> {
+    inner: S,
+    compression: Compression,
+    level: Option<u32>,
+    extension: String,
+}
+
+impl<S: ReportSerializer> Compressed<S> {
+    /// Compress what `inner` writes with `compression`
+    pub fn new(inner: S, compression: Compression) -> Self {
+        let extension =
+            format!("{}.{}", inner.extension(), compression.extension());
+        Compressed {
+            inner,
+            compression,
+            level: None,
+            extension,
+        }
+    }
+
+    /// Compress at `level` instead of the compression's default
+    ///
+    /// gzip takes levels from 0, which stores reports uncompressed, to 9,
+    /// and Zstandard from 1 to 22; levels out of range are clamped to them.
+    /// Higher levels shrink large reports, e.g. with long breadcrumbs or
+    /// state snapshots, further, but take longer while the process crashes.
+    ///
+    /// ```
+    /// # #[cfg(feature = "zstd")] {
+    /// use human_panic_logger::{
+    ///     metadata, Builder, Compressed, Compression, ReportFormat,
+    /// };
+    ///
+    /// let zstd =
+    ///     Compressed::new(ReportFormat::Toml, Compression::Zstd).level(19);
+    /// let builder =
+    ///     Builder::new(metadata!(), "app.log").report_serializer(zstd);
+    /// # drop(builder);
+    /// # }
+    /// ```
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = Some(level);
+        self
+    }
+}
+
+impl<S: ReportSerializer> ReportSerializer for Compressed<S> {
+    fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    fn write(
+        &self,
+        report: &Report,
+        out: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        match self.compression {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                let level = match self.level {
+                    Some(level) => flate2::Compression::new(level.min(9)),
+                    None => flate2::Compression::default(),
+                };
+                let mut encoder = flate2::write::GzEncoder::new(out, level);
+                self.inner.write(report, &mut encoder)?;
+                encoder.finish()?;
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                // 0 is the library's default
+                let level = self.level.map_or(0, |level| level.clamp(1, 22));
+                let mut encoder = zstd::Encoder::new(out, level as i32)?;
+                self.inner.write(report, &mut encoder)?;
+                encoder.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
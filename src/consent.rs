@@ -0,0 +1,257 @@
+//! The user's choice about sending crash reports over the network
+//!
+//! We don't collect crashes behind users' backs: before a sink that
+//! uploads reports gets one, the hook checks what the user chose, and asks
+//! on the terminal unless they answered for good. The answer is kept in a
+//! small file in the platform's config directory.
+
+use crate::Report;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long users have to answer before the report stays on their machine,
+/// so a crashed process nobody is watching still exits
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Whether crash reports may be sent over the network
+///
+/// Applications can offer the choice in their settings and record it with
+/// [`UploadConsent::write`]:
+///
+/// ```no_run
+/// use human_panic_logger::{platform_consent_file, UploadConsent};
+///
+/// if let Some(file) = platform_consent_file("app") {
+///     UploadConsent::Always.write(&file)?;
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UploadConsent {
+    /// Send every report without asking
+    Always,
+    /// Never send reports
+    Never,
+    /// Ask on the terminal every time, and don't send reports if there is
+    /// no terminal to ask on or nobody answers within 30 seconds
+    #[default]
+    Ask,
+}
+
+impl UploadConsent {
+    /// The choice recorded in `file`, or [`Ask`](UploadConsent::Ask) if
+    /// there is none
+    pub fn read(file: &Path) -> Self {
+        let recorded = std::fs::read_to_string(file).unwrap_or_default();
+        for line in recorded.lines() {
+            let value = match line.split_once('=') {
+                Some((key, value)) if key.trim() == "upload" => value,
+                // the bare answers of earlier versions
+                _ => line,
+            };
+            match value.trim().trim_matches('"') {
+                "always" | "granted" => return UploadConsent::Always,
+                "never" | "denied" => return UploadConsent::Never,
+                "ask" => return UploadConsent::Ask,
+                _ => {}
+            }
+        }
+        UploadConsent::Ask
+    }
+
+    /// Record the choice in `file`, creating its directory if needed
+    pub fn write(self, file: &Path) -> io::Result<()> {
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(
+            file,
+            format!(
+                "# Whether crash reports may be sent: always, never or ask\n\
+                 upload = \"{}\"\n",
+                self.as_str()
+            ),
+        )
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            UploadConsent::Always => "always",
+            UploadConsent::Never => "never",
+            UploadConsent::Ask => "ask",
+        }
+    }
+}
+
+/// The conventional place for the consent file of `app` on this platform,
+/// if it has one
+///
+/// - Linux and other Unixes: `$XDG_CONFIG_HOME/<app>/crash-reports.toml`,
+///   falling back to `~/.config/<app>/crash-reports.toml`
+/// - macOS: `~/Library/Application Support/<app>/crash-reports.toml`
+/// - Windows: `%APPDATA%\<app>\crash-reports.toml`
+///
+/// The hook uses it unless set otherwise with
+/// [`Builder::consent_file`](crate::Builder::consent_file).
+pub fn platform_consent_file(app: &str) -> Option<PathBuf> {
+    fn var(name: &str) -> Option<PathBuf> {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    }
+
+    let dir = if cfg!(target_os = "macos") {
+        var("HOME")?.join("Library").join("Application Support")
+    } else if cfg!(windows) {
+        var("APPDATA")?
+    } else if cfg!(unix) {
+        var("XDG_CONFIG_HOME").or_else(|| Some(var("HOME")?.join(".config")))?
+    } else {
+        return None;
+    };
+    Some(dir.join(app).join("crash-reports.toml"))
+}
+
+/// Whether the user agrees to sending `report` over the network, going by
+/// the choice recorded in `file` or asking
+pub(crate) fn allowed(file: Option<&Path>, report: &Report) -> bool {
+    let recorded = file.map(UploadConsent::read).unwrap_or_default();
+    match recorded {
+        UploadConsent::Always => return true,
+        UploadConsent::Never => return false,
+        UploadConsent::Ask => {}
+    }
+
+    let choices = match file {
+        Some(_) => "[y]es, [n]o, [a]lways, ne[v]er",
+        None => "[y]es, [n]o",
+    };
+    let prompt = format!(
+        "\n{} crashed. Send a crash report to its developers to help fix \
+         it? It holds the details of the report file. {}: ",
+        report.name, choices
+    );
+    let answer = match ask(&prompt) {
+        Some(answer) => answer,
+        None => return false,
+    };
+    if let (Some(file), UploadConsent::Always | UploadConsent::Never) =
+        (file, answer.remember)
+    {
+        if let Err(e) = answer.remember.write(file) {
+            log::warn!("Could not record the consent to uploads: {}", e);
+        }
+    }
+    answer.send
+}
+
+/// What users answered when asked to send a report
+struct Answer {
+    /// Whether to send this report
+    send: bool,
+    /// What to do from now on
+    remember: UploadConsent,
+}
+
+/// Ask the user on the terminal, if there is one
+fn ask(prompt: &str) -> Option<Answer> {
+    let line = prompt_line(prompt)?;
+    let (send, remember) = match line.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => (true, UploadConsent::Ask),
+        "a" | "always" => (true, UploadConsent::Always),
+        "v" | "never" => (false, UploadConsent::Never),
+        _ => (false, UploadConsent::Ask),
+    };
+    Some(Answer { send, remember })
+}
+
+/// Whether the user answers yes to `prompt` on the terminal
+#[cfg(feature = "screenshot")]
+pub(crate) fn confirm(prompt: &str) -> bool {
+    prompt_line(prompt).is_some_and(|line| {
+        matches!(line.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+    })
+}
+
+/// Show `prompt` and read the line users answer with, if there is a
+/// terminal and they answer in time
+fn prompt_line(prompt: &str) -> Option<String> {
+    if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        return None;
+    }
+    let mut stderr = io::stderr();
+    stderr.write_all(prompt.as_bytes()).ok()?;
+    stderr.flush().ok()?;
+
+    let line = answer_within(ANSWER_TIMEOUT);
+    if line.is_none() {
+        // end the prompt's line, as users didn't
+        let _ = writeln!(stderr);
+    }
+    line
+}
+
+/// A line read from the terminal within `timeout`, if users typed one
+///
+/// The read blocks, so it runs on a thread of its own, which is left behind
+/// if nobody answers; the process exits soon after anyway.
+fn answer_within(timeout: Duration) -> Option<String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::Builder::new()
+        .name("human-panic-consent".into())
+        .spawn(move || {
+            let _ = tx.send(read_line());
+        })
+        .ok()?;
+    rx.recv_timeout(timeout).ok().flatten()
+}
+
+/// Read a line from the terminal, `None` at the end of input
+///
+/// This reads from a handle of its own rather than through
+/// [`io::stdin`]'s lock, which the panicking thread or one blocked reading
+/// may hold.
+#[cfg(any(unix, windows))]
+fn read_line() -> Option<String> {
+    use std::io::Read;
+
+    // long enough for any answer
+    const MAX_LINE: usize = 64;
+
+    #[cfg(unix)]
+    let handle = {
+        use std::os::fd::AsFd;
+        io::stdin().as_fd().try_clone_to_owned().ok()?
+    };
+    #[cfg(windows)]
+    let handle = {
+        use std::os::windows::io::AsHandle;
+        io::stdin().as_handle().try_clone_to_owned().ok()?
+    };
+    let mut terminal = std::fs::File::from(handle);
+
+    let mut line = Vec::new();
+    let mut byte = [0];
+    let mut ended = false;
+    while line.len() < MAX_LINE {
+        match terminal.read(&mut byte) {
+            Ok(1) if byte[0] == b'\n' => {
+                ended = true;
+                break;
+            }
+            Ok(1) => line.push(byte[0]),
+            _ => break,
+        }
+    }
+    // input that ends before the line does isn't an answer
+    if !ended && line.is_empty() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&line).into_owned())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn read_line() -> Option<String> {
+    None
+}
@@ -0,0 +1,89 @@
+//! Detection of an attached debugger
+//!
+//! Developers stepping through a crash want the standard panic output, not
+//! a crash message and a report, see
+//! [`Builder::defer_to_debugger`](crate::Builder::defer_to_debugger).
+
+/// Whether a debugger is attached to the process
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn attached() -> bool {
+    let status = match std::fs::read_to_string("/proc/self/status") {
+        Ok(status) => status,
+        Err(_) => return false,
+    };
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("TracerPid:"))
+        .is_some_and(|pid| pid.trim() != "0")
+}
+
+/// Whether a debugger is attached to the process
+#[cfg(target_os = "macos")]
+pub(crate) fn attached() -> bool {
+    use std::os::raw::{c_int, c_uint, c_void};
+
+    const CTL_KERN: c_int = 1;
+    const KERN_PROC: c_int = 14;
+    const KERN_PROC_PID: c_int = 1;
+    const P_TRACED: c_int = 0x800;
+    // `struct kinfo_proc`, of which we only need `kp_proc.p_flag`
+    const KINFO_PROC_SIZE: usize = 648;
+    const P_FLAG_OFFSET: usize = 32;
+
+    extern "C" {
+        fn getpid() -> c_int;
+        fn sysctl(
+            name: *mut c_int,
+            namelen: c_uint,
+            oldp: *mut c_void,
+            oldlenp: *mut usize,
+            newp: *mut c_void,
+            newlen: usize,
+        ) -> c_int;
+    }
+
+    let mut info = [0u8; KINFO_PROC_SIZE];
+    let mut len = info.len();
+    // SAFETY: `sysctl` writes at most `len` bytes into `info`
+    unsafe {
+        let mut name = [CTL_KERN, KERN_PROC, KERN_PROC_PID, getpid()];
+        if sysctl(
+            name.as_mut_ptr(),
+            name.len() as c_uint,
+            info.as_mut_ptr().cast(),
+            &mut len,
+            std::ptr::null_mut(),
+            0,
+        ) != 0
+            || len < P_FLAG_OFFSET + 4
+        {
+            return false;
+        }
+    }
+    let mut flag = [0u8; 4];
+    flag.copy_from_slice(&info[P_FLAG_OFFSET..P_FLAG_OFFSET + 4]);
+    c_int::from_ne_bytes(flag) & P_TRACED != 0
+}
+
+/// Whether a debugger is attached to the process
+#[cfg(windows)]
+pub(crate) fn attached() -> bool {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn IsDebuggerPresent() -> i32;
+    }
+
+    // SAFETY: `IsDebuggerPresent` takes no arguments and cannot fail
+    unsafe { IsDebuggerPresent() != 0 }
+}
+
+/// Whether a debugger is attached to the process
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    windows
+)))]
+pub(crate) fn attached() -> bool {
+    false
+}
@@ -0,0 +1,136 @@
+//! Integration with Linux distribution crash tooling
+//!
+//! Packaged CLIs are expected to show up in the system's own crash workflow.
+//! With the `distro-report` feature enabled, every report is additionally
+//! written as an apport crash file to `/var/crash` (Ubuntu, Debian) and
+//! submitted to the ABRT daemon (Fedora, RHEL) if it is running.
+
+use crate::clock::DateTime;
+use crate::Report;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The directory apport picks up crash files from
+pub const APPORT_CRASH_DIR: &str = "/var/crash";
+
+/// The socket the ABRT daemon accepts problem data on
+pub const ABRT_SOCKET: &str = "/var/run/abrt/abrt.socket";
+
+/// Hand the report to every crash tool that is available, ignoring
+/// failures since the regular report has already been written
+pub fn submit(report: &Report) {
+    if Path::new(APPORT_CRASH_DIR).is_dir() {
+        let _ = write_apport_report(report, APPORT_CRASH_DIR);
+    }
+    if Path::new(ABRT_SOCKET).exists() {
+        let _ = submit_abrt_report(report, ABRT_SOCKET);
+    }
+}
+
+/// Write the report as an apport crash file into `dir`
+///
+/// The file is named like the ones apport creates itself
+/// (`_usr_bin_app.1000.crash`) and is never overwritten, matching how
+/// apport treats crashes that have not been processed yet.
+pub fn write_apport_report<P: AsRef<Path>>(
+    report: &Report,
+    dir: P,
+) -> io::Result<PathBuf> {
+    let executable = env::current_exe()?;
+    let uid = fs::metadata("/proc/self")?.uid();
+    let file_name = format!(
+        "{}.{}.crash",
+        executable.display().to_string().replace('/', "_"),
+        uid
+    );
+    let path = dir.as_ref().join(file_name);
+
+    // `env::args` panics on arguments that aren't UTF-8, which are legal
+    // on Unix, and this runs in the panic hook
+    let cmdline = env::args_os()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut fields = vec![
+        ("ProblemType", "Crash".to_string()),
+        (
+            "Date",
+            DateTime::now().format("%a %b %e %H:%M:%S %Y"),
+        ),
+        ("ExecutablePath", executable.display().to_string()),
+        ("ProcCmdline", cmdline),
+        ("Package", format!("{} {}", report.name, report.version)),
+        ("Title", report.summary()),
+        ("Traceback", report.to_string()),
+    ];
+    if let Some(permalink) = &report.permalink {
+        fields.push(("SourceLink", permalink.clone()));
+    }
+
+    let mut contents = String::new();
+    for (key, value) in fields {
+        if value.contains('\n') {
+            // apport continues multi-line values on lines indented by a space
+            contents.push_str(&format!("{}:\n", key));
+            for line in value.lines() {
+                contents.push_str(&format!(" {}\n", line));
+            }
+        } else {
+            contents.push_str(&format!("{}: {}\n", key, value));
+        }
+    }
+
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o640)
+        .open(&path)?
+        .write_all(contents.as_bytes())?;
+
+    Ok(path)
+}
+
+/// Submit the report to the ABRT daemon listening on `socket`
+pub fn submit_abrt_report<P: AsRef<Path>>(
+    report: &Report,
+    socket: P,
+) -> io::Result<()> {
+    const TIMEOUT: Duration = Duration::from_secs(2);
+
+    let executable = env::current_exe()?;
+    let items = [
+        ("type", "Rust".to_string()),
+        ("analyzer", "human-panic-logger".to_string()),
+        ("executable", executable.display().to_string()),
+        ("pid", std::process::id().to_string()),
+        ("reason", report.summary()),
+        ("backtrace", report.to_string()),
+    ];
+
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    stream.write_all(b"POST / HTTP/1.1\r\n\r\n")?;
+    for (key, value) in &items {
+        // items are NUL separated, so values must not contain NUL bytes
+        write!(stream, "{}={}\0", key, value.replace('\0', ""))?;
+    }
+    stream.shutdown(std::net::Shutdown::Write)?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    if response.starts_with("HTTP/1.1 201") {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "ABRT rejected the report: {}",
+            response.trim()
+        )))
+    }
+}
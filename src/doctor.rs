@@ -0,0 +1,189 @@
+//! Checking that crash reporting is set up correctly
+//!
+//! Applications can call [`verify_installation`] from a `--doctor` command,
+//! so users (and support staff) can find out why crashes aren't reported
+//! before one happens.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// What the [`Builder`](crate::Builder) set up
+struct Installation {
+    log_file: PathBuf,
+    report_dir: Option<PathBuf>,
+    hook_installed: bool,
+}
+
+static INSTALLATION: OnceLock<Installation> = OnceLock::new();
+
+/// Remember the setup for [`verify_installation`]
+pub(crate) fn register_installation<P: AsRef<Path>>(
+    log_file: P,
+    report_dir: Option<PathBuf>,
+    hook_installed: bool,
+) {
+    let _ = INSTALLATION.set(Installation {
+        log_file: log_file.as_ref().to_path_buf(),
+        report_dir,
+        hook_installed,
+    });
+}
+
+/// Details about a working setup
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Diagnostics {
+    /// The log file crashes are written to
+    pub log_file: PathBuf,
+    /// The directory report files are written to
+    pub report_dir: PathBuf,
+    /// The system crash tools reports are also handed to
+    pub system_integrations: Vec<&'static str>,
+}
+
+/// A reason crashes wouldn't be reported
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SetupIssue {
+    /// `setup_panic_logger!` was never called
+    NotInstalled,
+    /// The hook wasn't installed because `RUST_BACKTRACE` was set
+    DisabledByRustBacktrace,
+    /// Another logger was installed, so crashes don't reach the log file
+    LoggerMissing,
+    /// The log file can't be written
+    LogFileNotWritable {
+        /// The configured log file
+        path: PathBuf,
+        /// Why opening it failed
+        error: io::Error,
+    },
+    /// No report files can be created in the report directory
+    ReportDirNotWritable {
+        /// The report directory, see
+        /// [`Builder::report_dir`](crate::Builder::report_dir)
+        path: PathBuf,
+        /// Why creating a file failed
+        error: io::Error,
+    },
+    /// No report directory is set, which WASI needs as it has no
+    /// temporary directory to fall back to
+    NoReportDir,
+}
+
+impl fmt::Display for SetupIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupIssue::NotInstalled => {
+                write!(f, "the panic handler was never set up")
+            }
+            SetupIssue::DisabledByRustBacktrace => write!(
+                f,
+                "the panic handler is disabled because RUST_BACKTRACE is set"
+            ),
+            SetupIssue::LoggerMissing => {
+                write!(f, "no logger is active to write crashes to the log")
+            }
+            SetupIssue::LogFileNotWritable { path, error } => write!(
+                f,
+                "the log file \"{}\" can't be written: {}",
+                crate::long_path::for_display(path).display(),
+                error
+            ),
+            SetupIssue::ReportDirNotWritable { path, error } => write!(
+                f,
+                "no report files can be created in \"{}\": {}",
+                crate::long_path::for_display(path).display(),
+                error
+            ),
+            SetupIssue::NoReportDir => write!(
+                f,
+                "no report directory is set, and there is no temporary \
+                 directory to write reports to"
+            ),
+        }
+    }
+}
+
+impl Error for SetupIssue {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SetupIssue::LogFileNotWritable { error, .. }
+            | SetupIssue::ReportDirNotWritable { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Check that crashes will be reported, e.g. for a `--doctor` command
+///
+/// ```
+/// use human_panic_logger::{verify_installation, SetupIssue};
+///
+/// // without `setup_panic_logger!`, nothing gets reported
+/// assert!(matches!(verify_installation(), Err(SetupIssue::NotInstalled)));
+/// ```
+pub fn verify_installation() -> Result<Diagnostics, SetupIssue> {
+    let installation = INSTALLATION.get().ok_or(SetupIssue::NotInstalled)?;
+    if !installation.hook_installed {
+        return Err(SetupIssue::DisabledByRustBacktrace);
+    }
+    if log::max_level() == log::LevelFilter::Off {
+        return Err(SetupIssue::LoggerMissing);
+    }
+    if let Err(error) = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(crate::long_path::for_io(&installation.log_file))
+    {
+        return Err(SetupIssue::LogFileNotWritable {
+            path: installation.log_file.clone(),
+            error,
+        });
+    }
+
+    let report_dir =
+        crate::format::report_dir(installation.report_dir.as_deref())
+            .ok_or(SetupIssue::NoReportDir)?;
+    if let Err(error) = probe_dir(&report_dir) {
+        return Err(SetupIssue::ReportDirNotWritable {
+            path: report_dir,
+            error,
+        });
+    }
+
+    Ok(Diagnostics {
+        log_file: installation.log_file.clone(),
+        report_dir,
+        system_integrations: system_integrations(),
+    })
+}
+
+/// Check that files can be created in `dir` by creating one
+pub(crate) fn probe_dir(dir: &Path) -> io::Result<()> {
+    let probe = dir.join(format!(".probe-{}", crate::id::random()));
+    let probe = crate::long_path::for_io(&probe);
+    OpenOptions::new().write(true).create_new(true).open(&probe)?;
+    std::fs::remove_file(probe)
+}
+
+fn system_integrations() -> Vec<&'static str> {
+    #[allow(unused_mut)]
+    let mut integrations = Vec::new();
+    #[cfg(all(feature = "distro-report", target_os = "linux"))]
+    {
+        if Path::new(crate::distro::APPORT_CRASH_DIR).is_dir() {
+            integrations.push("apport");
+        }
+        if Path::new(crate::distro::ABRT_SOCKET).exists() {
+            integrations.push("abrt");
+        }
+    }
+    #[cfg(all(feature = "macos-crash-report", target_os = "macos"))]
+    integrations.push("diagnostic reports");
+    integrations
+}
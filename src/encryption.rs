@@ -0,0 +1,71 @@
+//! Encrypted report files
+//!
+//! With the `encryption` feature, builds can embed the public key of the
+//! vendor's support team and encrypt every report file to it with
+//! [age](https://age-encryption.org). Users can then send reports through
+//! channels that must not carry plaintext crash data, and only the support
+//! team can read them, e.g. with `age --decrypt -i key.txt`.
+//!
+//! ```no_run
+//! use human_panic_logger::encryption::{Encrypted, Recipient};
+//! use human_panic_logger::{metadata, Builder, ReportFormat};
+//!
+//! // the public key of the support team, embedded in the binary
+//! let recipient: Recipient =
+//!     "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p"
+//!         .parse()
+//!         .expect("valid key");
+//!
+//! Builder::new(metadata!(), "app.log")
+//!     .report_serializer(Encrypted::new(ReportFormat::Toml, recipient))
+//!     .install()
+//!     .expect("crash reporting could not be set up");
+//! ```
+
+use crate::{Report, ReportSerializer};
+use std::io;
+
+pub use age::x25519::{Identity, Recipient};
+
+/// Encrypts the output of another serializer
+///
+/// The extension of the files is the inner serializer's followed by `age`,
+/// e.g. `toml.age`.
+pub struct Encrypted<S> {
+    inner: S,
+    recipient: Recipient,
+    extension: String,
+}
+
+impl<S: ReportSerializer> Encrypted<S> {
+    /// Encrypt what `inner` writes to `recipient`
+    pub fn new(inner: S, recipient: Recipient) -> Self {
+        let extension = format!("{}.age", inner.extension());
+        Encrypted {
+            inner,
+            recipient,
+            extension,
+        }
+    }
+}
+
+impl<S: ReportSerializer> ReportSerializer for Encrypted<S> {
+    fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    fn write(
+        &self,
+        report: &Report,
+        out: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        let recipient: &dyn age::Recipient = &self.recipient;
+        let recipients = std::iter::once(recipient);
+        let encryptor = age::Encryptor::with_recipients(recipients)
+            .map_err(io::Error::other)?;
+        let mut writer = encryptor.wrap_output(out)?;
+        self.inner.write(report, &mut writer)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,407 @@
+//! Details about the environment the crash happened in
+//!
+//! Everything here is cheap to capture and often decides whether a crash
+//! can be reproduced at all.
+
+use std::env;
+use std::fmt;
+use std::io::IsTerminal;
+
+/// A description of the operating system, such as
+/// `Ubuntu 24.04 LTS (linux x86_64)`
+///
+/// On Linux the distribution's name is read from `/etc/os-release`;
+/// elsewhere only the platform and architecture are known.
+pub fn operating_system() -> String {
+    let platform = format!("{} {}", env::consts::OS, env::consts::ARCH);
+    match distribution() {
+        Some(name) => format!("{} ({})", name, platform),
+        None => platform,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn distribution() -> Option<String> {
+    let release = std::fs::read_to_string("/etc/os-release").ok()?;
+    release.lines().find_map(|line| {
+        let name = line.strip_prefix("PRETTY_NAME=")?;
+        Some(name.trim_matches('"').to_string())
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn distribution() -> Option<String> {
+    None
+}
+
+/// The terminal the application was running in
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Terminal {
+    /// The `TERM` environment variable
+    pub term: Option<String>,
+    /// The `COLORTERM` environment variable
+    pub colorterm: Option<String>,
+    /// Columns and rows of the terminal, if attached to one
+    pub size: Option<(u16, u16)>,
+    /// Whether stdin is a terminal
+    pub stdin_tty: bool,
+    /// Whether stdout is a terminal
+    pub stdout_tty: bool,
+    /// Whether stderr is a terminal
+    pub stderr_tty: bool,
+}
+
+impl Terminal {
+    /// Capture the details of the current terminal
+    pub fn capture() -> Self {
+        Terminal {
+            term: env::var("TERM").ok(),
+            colorterm: env::var("COLORTERM").ok(),
+            size: terminal_size(),
+            stdin_tty: std::io::stdin().is_terminal(),
+            stdout_tty: std::io::stdout().is_terminal(),
+            stderr_tty: std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+/// The locale settings the application ran with
+///
+/// Many path and encoding bugs only reproduce under specific locales.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Locale {
+    /// `LANG`, `LANGUAGE` and every `LC_*` variable that is set, sorted by
+    /// name
+    pub variables: Vec<(String, String)>,
+    /// The active ANSI codepage, on Windows
+    pub codepage: Option<u32>,
+}
+
+impl Locale {
+    /// Capture the locale of the current process
+    pub fn capture() -> Self {
+        // `env::vars` panics on names or values that aren't UTF-8, which
+        // are legal on Unix, and this runs in the panic hook
+        let mut variables: Vec<(String, String)> = env::vars_os()
+            .filter_map(|(name, value)| {
+                let name = name.to_str()?;
+                let locale = name == "LANG"
+                    || name == "LANGUAGE"
+                    || name.starts_with("LC_");
+                locale.then(|| {
+                    (name.to_string(), value.to_string_lossy().into_owned())
+                })
+            })
+            .collect();
+        variables.sort();
+
+        Locale {
+            variables,
+            codepage: codepage(),
+        }
+    }
+}
+
+#[cfg(windows)]
+fn codepage() -> Option<u32> {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetACP() -> u32;
+    }
+
+    // SAFETY: `GetACP` takes no arguments and cannot fail
+    Some(unsafe { GetACP() })
+}
+
+#[cfg(not(windows))]
+fn codepage() -> Option<u32> {
+    None
+}
+
+/// A graphics adapter and the driver running it
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gpu {
+    /// The PCI vendor id, such as `0x10de`
+    pub vendor_id: String,
+    /// The PCI device id
+    pub device_id: Option<String>,
+    /// The kernel driver bound to the device
+    pub driver: Option<String>,
+    /// The version of that driver, if it reports one
+    pub driver_version: Option<String>,
+}
+
+impl Gpu {
+    /// Capture the graphics adapters of the machine
+    ///
+    /// Requires the `gpu-info` feature, and is only implemented for Linux,
+    /// where the adapters are read from sysfs. Everywhere else the list is
+    /// empty.
+    pub fn capture() -> Vec<Self> {
+        capture_gpus()
+    }
+
+    /// The name of the vendor, for the vendors most crashes are seen with
+    pub fn vendor(&self) -> &str {
+        match self.vendor_id.as_str() {
+            "0x10de" => "NVIDIA",
+            "0x1002" => "AMD",
+            "0x8086" => "Intel",
+            "0x1af4" => "Virtio",
+            "0x15ad" => "VMware",
+            other => other,
+        }
+    }
+}
+
+#[cfg(all(feature = "gpu-info", target_os = "linux"))]
+fn capture_gpus() -> Vec<Gpu> {
+    use std::fs;
+    use std::path::Path;
+
+    fn read(path: &Path) -> Option<String> {
+        fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+    }
+
+    let mut cards: Vec<_> = match fs::read_dir("/sys/class/drm") {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            // `card0-HDMI-A-1` and friends are connectors, not adapters
+            .filter(|name| name.starts_with("card") && !name.contains('-'))
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    cards.sort();
+
+    cards
+        .iter()
+        .filter_map(|card| {
+            let device = Path::new("/sys/class/drm").join(card).join("device");
+            let vendor_id = read(&device.join("vendor"))?;
+            let driver = fs::read_link(device.join("driver"))
+                .ok()
+                .and_then(|link| {
+                    link.file_name().map(|n| n.to_string_lossy().into_owned())
+                });
+            let driver_version = driver.as_ref().and_then(|driver| {
+                // the proprietary NVIDIA driver doesn't fill in sysfs
+                read(&Path::new("/sys/module").join(driver).join("version"))
+                    .or_else(|| {
+                        read(Path::new("/proc/driver/nvidia/version"))
+                            .filter(|_| driver == "nvidia")
+                            .and_then(|v| v.lines().next().map(String::from))
+                    })
+            });
+
+            Some(Gpu {
+                vendor_id,
+                device_id: read(&device.join("device")),
+                driver,
+                driver_version,
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(all(feature = "gpu-info", target_os = "linux")))]
+fn capture_gpus() -> Vec<Gpu> {
+    Vec::new()
+}
+
+/// Power source and thermal state of the machine
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Power {
+    /// Whether the machine ran on battery, if it has one
+    pub on_battery: Option<bool>,
+    /// Charge of the battery in percent
+    pub battery_percent: Option<u8>,
+    /// The hottest thermal zone, in millidegrees Celsius
+    pub max_temperature: Option<i32>,
+    /// How often the CPUs were throttled for heat since boot
+    pub throttle_count: Option<u64>,
+}
+
+impl Power {
+    /// Capture the power and thermal state of the machine
+    ///
+    /// Requires the `power-info` feature, and is only implemented for Linux,
+    /// where the state is read from sysfs. Everywhere else nothing is known.
+    pub fn capture() -> Self {
+        capture_power()
+    }
+
+    /// Whether anything is known at all
+    pub fn is_empty(&self) -> bool {
+        *self == Power::default()
+    }
+}
+
+#[cfg(all(feature = "power-info", target_os = "linux"))]
+fn capture_power() -> Power {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn read(path: &Path) -> Option<String> {
+        fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+    }
+
+    fn entries(dir: &str, prefix: &str) -> Vec<PathBuf> {
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| {
+                        entry.file_name().to_string_lossy().starts_with(prefix)
+                    })
+                    .map(|entry| entry.path())
+                    .collect()
+            })
+            .unwrap_or_default();
+        paths.sort();
+        paths
+    }
+
+    let mut power = Power::default();
+    for supply in entries("/sys/class/power_supply", "") {
+        match read(&supply.join("type")).as_deref() {
+            Some("Mains") => {
+                if let Some(online) = read(&supply.join("online")) {
+                    power.on_battery = Some(online == "0");
+                }
+            }
+            Some("Battery") if power.battery_percent.is_none() => {
+                power.battery_percent = read(&supply.join("capacity"))
+                    .and_then(|capacity| capacity.parse().ok());
+            }
+            _ => {}
+        }
+    }
+
+    power.max_temperature = entries("/sys/class/thermal", "thermal_zone")
+        .iter()
+        .filter_map(|zone| read(&zone.join("temp"))?.parse().ok())
+        .max();
+
+    let throttled: Vec<u64> = entries("/sys/devices/system/cpu", "cpu")
+        .iter()
+        .filter_map(|cpu| {
+            read(&cpu.join("thermal_throttle/core_throttle_count"))?
+                .parse()
+                .ok()
+        })
+        .collect();
+    if !throttled.is_empty() {
+        power.throttle_count = Some(throttled.iter().sum());
+    }
+
+    power
+}
+
+#[cfg(not(all(feature = "power-info", target_os = "linux")))]
+fn capture_power() -> Power {
+    Power::default()
+}
+
+#[cfg(all(feature = "terminal-size", any(unix, windows)))]
+fn terminal_size() -> Option<(u16, u16)> {
+    use terminal_size::{terminal_size_of, Height, Width};
+
+    terminal_size::terminal_size()
+        .or_else(|| terminal_size_of(std::io::stderr()))
+        .map(|(Width(w), Height(h))| (w, h))
+}
+
+#[cfg(not(all(feature = "terminal-size", any(unix, windows))))]
+fn terminal_size() -> Option<(u16, u16)> {
+    None
+}
+
+impl fmt::Display for Terminal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn yes_no(tty: bool) -> &'static str {
+            if tty {
+                "yes"
+            } else {
+                "no"
+            }
+        }
+
+        write!(
+            f,
+            "TERM={} COLORTERM={}",
+            self.term.as_deref().unwrap_or("<unset>"),
+            self.colorterm.as_deref().unwrap_or("<unset>")
+        )?;
+        match self.size {
+            Some((columns, rows)) => write!(f, " size={}x{}", columns, rows)?,
+            None => write!(f, " size=<unknown>")?,
+        }
+        write!(
+            f,
+            " tty: stdin={} stdout={} stderr={}",
+            yes_no(self.stdin_tty),
+            yes_no(self.stdout_tty),
+            yes_no(self.stderr_tty)
+        )
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.variables.is_empty() && self.codepage.is_none() {
+            return f.write_str("<unset>");
+        }
+
+        let mut separator = "";
+        for (name, value) in &self.variables {
+            write!(f, "{}{}={}", separator, name, value)?;
+            separator = " ";
+        }
+        if let Some(codepage) = self.codepage {
+            write!(f, "{}codepage={}", separator, codepage)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Gpu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.vendor())?;
+        if let Some(device_id) = &self.device_id {
+            write!(f, " ({}:{})", self.vendor_id, device_id)?;
+        }
+        match (&self.driver, &self.driver_version) {
+            (Some(driver), Some(version)) => {
+                write!(f, ", driver {} {}", driver, version)
+            }
+            (Some(driver), None) => write!(f, ", driver {}", driver),
+            (None, _) => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Power {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.on_battery {
+            Some(true) => write!(f, "on battery")?,
+            Some(false) => write!(f, "on mains")?,
+            None => write!(f, "power source unknown")?,
+        }
+        if let Some(percent) = self.battery_percent {
+            write!(f, " ({}% charged)", percent)?;
+        }
+        if let Some(temperature) = self.max_temperature {
+            let celsius = f64::from(temperature) / 1000.0;
+            write!(f, ", hottest zone {:.1}°C", celsius)?;
+        }
+        if let Some(count) = self.throttle_count {
+            write!(f, ", throttled {} times since boot", count)?;
+        }
+        Ok(())
+    }
+}
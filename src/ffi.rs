@@ -0,0 +1,62 @@
+//! Panics at FFI boundaries
+//!
+//! A panic must not unwind out of an `extern "C"` function: the runtime
+//! aborts the process, and the panic payload means little to whoever called
+//! into the library from C. Wrapping the body of exported functions in
+//! [`ffi_boundary`] stops the unwind there and marks the report with the
+//! boundary it would have crossed.
+
+use std::cell::Cell;
+use std::panic::{self, UnwindSafe};
+
+thread_local! {
+    /// The innermost boundary the current thread is inside of
+    static BOUNDARY: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+/// Restores the enclosing boundary, also while unwinding
+struct Restore(Option<&'static str>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        BOUNDARY.with(|boundary| boundary.set(self.0));
+    }
+}
+
+/// Run the body of an exported function, catching panics before they
+/// unwind into foreign code
+///
+/// `name` should identify the exported function; reports of panics inside
+/// `f` say that the panic was stopped at that boundary. Returns `None` if
+/// `f` panicked, so the function can return an error code instead.
+///
+/// ```
+/// use human_panic_logger::ffi_boundary;
+///
+/// #[no_mangle]
+/// pub extern "C" fn app_checksum(len: usize) -> i32 {
+///     ffi_boundary("app_checksum", || {
+///         assert!(len > 0, "empty input");
+///         len as i32 % 256
+///     })
+///     .unwrap_or(-1)
+/// }
+///
+/// assert_eq!(app_checksum(300), 44);
+/// ```
+///
+/// Foreign exceptions (e.g. from C++) can't be caught this way; the runtime
+/// aborts when one reaches Rust code.
+pub fn ffi_boundary<F, R>(name: &'static str, f: F) -> Option<R>
+where
+    F: FnOnce() -> R + UnwindSafe,
+{
+    let enclosing = BOUNDARY.with(|boundary| boundary.replace(Some(name)));
+    let _restore = Restore(enclosing);
+    panic::catch_unwind(f).ok()
+}
+
+/// The boundary the current thread is inside of, if any
+pub(crate) fn current_boundary() -> Option<&'static str> {
+    BOUNDARY.with(Cell::get)
+}
@@ -0,0 +1,867 @@
+//! Report files
+//!
+//! Besides the entry in the log, every crash is written to a report file of
+//! its own, in TOML like upstream `human-panic` does, or in Markdown that
+//! users can paste into an issue. Both are written by hand so the crate
+//! doesn't need serde for them; only JSON, with the `json` feature, goes
+//! through serde.
+
+use crate::clock::DateTime;
+use crate::report::Frame;
+use crate::{Metadata, Report};
+use std::fmt::{self, Write};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Overrides the directory report files are written to
+const REPORT_DIR_VAR: &str = "HUMAN_PANIC_REPORT_DIR";
+
+/// The conventional place for the crash reports of `app` on this platform,
+/// if it has one
+///
+/// - Linux and other Unixes: `$XDG_STATE_HOME/<app>`, falling back to
+///   `~/.local/state/<app>`
+/// - macOS: `~/Library/Logs/<app>`
+/// - Windows: `%LOCALAPPDATA%\<app>\Crashes`
+///
+/// See [`Builder::platform_report_dir`](crate::Builder::platform_report_dir).
+pub fn platform_report_dir(app: &str) -> Option<PathBuf> {
+    fn var(name: &str) -> Option<PathBuf> {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    }
+
+    if cfg!(target_os = "macos") {
+        Some(var("HOME")?.join("Library").join("Logs").join(app))
+    } else if cfg!(windows) {
+        Some(var("LOCALAPPDATA")?.join(app).join("Crashes"))
+    } else if cfg!(unix) {
+        let state = var("XDG_STATE_HOME")
+            .or_else(|| Some(var("HOME")?.join(".local").join("state")))?;
+        Some(state.join(app))
+    } else {
+        None
+    }
+}
+
+/// The directory report files are written to: `HUMAN_PANIC_REPORT_DIR` if
+/// set, else the `configured` one, else the user's own directory in the
+/// temporary directory, see [`user_temp_dir`](crate::shared::user_temp_dir)
+///
+/// None on WASI without a directory set, as there is no temporary
+/// directory to fall back to.
+pub(crate) fn report_dir(configured: Option<&Path>) -> Option<PathBuf> {
+    match std::env::var_os(REPORT_DIR_VAR) {
+        Some(dir) if !dir.is_empty() => Some(dir.into()),
+        _ => configured
+            .map(Path::to_path_buf)
+            .or_else(crate::shared::user_temp_dir),
+    }
+}
+
+/// How report files are named
+///
+/// ```
+/// use human_panic_logger::{metadata, Builder, ReportNaming};
+///
+/// let builder = Builder::new(metadata!(), "app.log").report_naming(
+///     ReportNaming::custom(|report| {
+///         format!("{}-{}", report.name, report.fingerprint())
+///     }),
+/// );
+/// # drop(builder);
+/// ```
+#[derive(Default)]
+#[non_exhaustive]
+pub enum ReportNaming {
+    /// `report-<uuid>.<extension>`, which never collide
+    #[default]
+    Uuid,
+    /// `crash-<time>.<extension>` in local time, e.g.
+    /// `crash-2024-05-01T12-00-00.toml`, which sort by when the crash
+    /// happened
+    Timestamp,
+    /// The name the closure returns for a report, followed by the
+    /// extension
+    ///
+    /// The name must be a plain file name, without directories. Report
+    /// files named this way are left alone by
+    /// [`Builder::max_reports`](crate::Builder::max_reports) and
+    /// [`Builder::max_age`](crate::Builder::max_age), as they can't be
+    /// told apart from other files.
+    Custom(Box<dyn Fn(&Report) -> String + Send + Sync>),
+}
+
+impl ReportNaming {
+    /// Name report files with `name`, see [`ReportNaming::Custom`]
+    pub fn custom<F>(name: F) -> Self
+    where
+        F: Fn(&Report) -> String + Send + Sync + 'static,
+    {
+        ReportNaming::Custom(Box::new(name))
+    }
+
+    /// The name of the file for `report`, without the extension
+    pub(crate) fn stem(&self, report: &Report) -> String {
+        match self {
+            ReportNaming::Uuid => format!("report-{}", crate::id::random()),
+            ReportNaming::Timestamp => {
+                let time = DateTime::parse(&report.timestamp)
+                    .unwrap_or_else(DateTime::now);
+                format!("crash-{}", time.format("%Y-%m-%dT%H-%M-%S"))
+            }
+            ReportNaming::Custom(name) => name(report),
+        }
+    }
+
+    /// Whether `name` is that of a report file named this way, with
+    /// `extension`
+    ///
+    /// Names of custom naming are never recognized, as they can't be told
+    /// apart from other files.
+    fn recognizes(&self, name: &str, extension: &str) -> bool {
+        // `x` stands for a hex digit and `d` for a decimal one
+        let (prefix, pattern) = match self {
+            ReportNaming::Uuid => {
+                ("report-", "xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx")
+            }
+            ReportNaming::Timestamp => ("crash-", "dddd-dd-ddTdd-dd-dd"),
+            ReportNaming::Custom(_) => return false,
+        };
+        let stem = name
+            .strip_suffix(extension)
+            .and_then(|name| name.strip_suffix('.'))
+            .and_then(|name| name.strip_prefix(prefix));
+        let stem = match stem {
+            Some(stem) if stem.len() >= pattern.len() => stem,
+            _ => return false,
+        };
+        let (head, count) = stem.split_at(pattern.len());
+        let fits = head.bytes().zip(pattern.bytes()).all(|(b, p)| match p {
+            b'x' => b.is_ascii_hexdigit(),
+            b'd' => b.is_ascii_digit(),
+            p => b == p,
+        });
+        // names taken already get a count, see `Report::write_file_with`
+        let counted = match count.strip_prefix('-') {
+            Some(count) => {
+                !count.is_empty() && count.bytes().all(|b| b.is_ascii_digit())
+            }
+            None => count.is_empty(),
+        };
+        fits && counted
+    }
+}
+
+impl fmt::Debug for ReportNaming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportNaming::Uuid => f.write_str("Uuid"),
+            ReportNaming::Timestamp => f.write_str("Timestamp"),
+            ReportNaming::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// How many report files to keep in the report directory
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Retention {
+    pub(crate) max_reports: Option<usize>,
+    pub(crate) max_age: Option<Duration>,
+}
+
+impl Retention {
+    /// Delete the report files in `dir` beyond the newest `max_reports`
+    /// and those older than `max_age`
+    ///
+    /// Only files named by `naming` with `extension`, and their signatures,
+    /// are touched, and the newest report, which the crash message points
+    /// to, is always kept. Anything that can't be read or deleted is left
+    /// alone.
+    pub(crate) fn prune(
+        &self,
+        dir: &Path,
+        naming: &ReportNaming,
+        extension: &str,
+    ) {
+        if self.max_reports.is_none() && self.max_age.is_none() {
+            return;
+        }
+        let entries = match std::fs::read_dir(crate::long_path::for_io(dir)) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        let mut reports: Vec<(SystemTime, PathBuf)> = entries
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                let name = entry.file_name();
+                naming.recognizes(&name.to_string_lossy(), extension)
+            })
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok().filter(|m| m.is_file())?;
+                Some((meta.modified().ok()?, entry.path()))
+            })
+            .collect();
+        // newest first
+        reports.sort_by_key(|&(modified, _)| std::cmp::Reverse(modified));
+
+        let now = SystemTime::now();
+        let keep = self.max_reports.unwrap_or(usize::MAX);
+        for (idx, (modified, path)) in reports.into_iter().enumerate() {
+            let expired = self.max_age.is_some_and(|max_age| {
+                now.duration_since(modified).unwrap_or_default() > max_age
+            });
+            if idx > 0 && (idx >= keep || expired) {
+                let _ = std::fs::remove_file(&path);
+                // signatures and screenshots go together with their report
+                for companion in [".sig", ".png"] {
+                    let mut file = path.clone().into_os_string();
+                    file.push(companion);
+                    let _ = std::fs::remove_file(file);
+                }
+            }
+        }
+    }
+}
+
+/// Flush the file at `path` to disk
+#[cfg(feature = "signing")]
+pub(crate) fn sync_file(path: &Path) -> io::Result<()> {
+    std::fs::File::open(crate::long_path::for_io(path))?.sync_all()
+}
+
+/// Flush the entries of `dir` to disk, so files just created or renamed in
+/// it survive a power loss
+#[cfg(unix)]
+pub(crate) fn sync_dir(dir: &Path) -> io::Result<()> {
+    std::fs::File::open(dir)?.sync_all()
+}
+
+/// Flush the entries of `dir` to disk, so files just created or renamed in
+/// it survive a power loss
+///
+/// Directories can't be flushed on their own here, their changes are
+/// committed along with the file system's journal.
+#[cfg(not(unix))]
+pub(crate) fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// The format report files are written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ReportFormat {
+    /// A TOML document, for tools to read
+    #[default]
+    Toml,
+    /// A Markdown document, ready to paste into a GitHub issue
+    Markdown,
+    /// A JSON document, for tools that don't read TOML, with the `json`
+    /// feature
+    #[cfg(feature = "json")]
+    Json,
+}
+
+/// Writes reports in a file format
+///
+/// Applications can register their own with
+/// [`Builder::report_serializer`](crate::Builder::report_serializer), e.g.
+/// for a schema their tooling already reads. The built-in formats of
+/// [`ReportFormat`] implement it too.
+///
+/// ```
+/// use human_panic_logger::{Report, ReportSerializer};
+/// use std::io::{self, Write};
+///
+/// struct Xml;
+///
+/// impl ReportSerializer for Xml {
+///     fn extension(&self) -> &str {
+///         "xml"
+///     }
+///
+///     fn write(
+///         &self,
+///         report: &Report,
+///         out: &mut dyn Write,
+///     ) -> io::Result<()> {
+///         // a real implementation escapes the values
+///         writeln!(
+///             out,
+///             "<crash name=\"{}\" version=\"{}\">{}</crash>",
+///             report.name, report.version, report.cause
+///         )
+///     }
+/// }
+/// ```
+///
+/// Like everything else in the hook, this runs while the application
+/// crashes, so it must be quick and must not panic.
+pub trait ReportSerializer: Send + Sync {
+    /// The extension of report files in this format, without the dot
+    fn extension(&self) -> &str;
+
+    /// Write `report` to `out`
+    fn write(
+        &self,
+        report: &Report,
+        out: &mut dyn io::Write,
+    ) -> io::Result<()>;
+}
+
+impl ReportFormat {
+    /// The file extension for this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Toml => "toml",
+            ReportFormat::Markdown => "md",
+            #[cfg(feature = "json")]
+            ReportFormat::Json => "json",
+        }
+    }
+
+    /// Render `report` in this format
+    pub fn render(self, report: &Report) -> String {
+        match self {
+            ReportFormat::Toml => to_toml(report),
+            ReportFormat::Markdown => to_markdown(report),
+            #[cfg(feature = "json")]
+            ReportFormat::Json => to_json(report),
+        }
+    }
+}
+
+impl ReportSerializer for ReportFormat {
+    fn extension(&self) -> &str {
+        ReportFormat::extension(*self)
+    }
+
+    fn write(
+        &self,
+        report: &Report,
+        out: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        out.write_all(self.render(report).as_bytes())
+    }
+}
+
+/// Render `report` as a JSON document
+#[cfg(feature = "json")]
+pub(crate) fn to_json(report: &Report) -> String {
+    // all maps of a report have string keys, so this can't fail
+    let mut out = serde_json::to_string_pretty(report).unwrap_or_default();
+    out.push('\n');
+    out
+}
+
+/// Render `report` as a TOML document
+pub(crate) fn to_toml(report: &Report) -> String {
+    fn key(out: &mut String, key: &str, value: &str) {
+        let _ = writeln!(out, "{} = {}", key, string(value));
+    }
+
+    let mut out = String::new();
+
+    let _ = writeln!(out, "schema_version = {}", report.schema_version);
+    key(&mut out, "name", &report.name);
+    key(&mut out, "operating_system", &report.operating_system);
+    key(&mut out, "crate_version", &report.version);
+    key(&mut out, "channel", report.channel.as_str());
+    key(&mut out, "timestamp", &report.timestamp);
+    key(&mut out, "cause", &report.cause);
+    key(&mut out, "fingerprint", &report.fingerprint());
+    if let Some(permalink) = &report.permalink {
+        key(&mut out, "permalink", permalink);
+    }
+    if let Some(build_id) = &report.build_id {
+        key(&mut out, "build_id", build_id);
+    }
+    if let Some(session_id) = &report.session_id {
+        key(&mut out, "session_id", session_id);
+    }
+    if let Some(boundary) = &report.ffi_boundary {
+        key(&mut out, "ffi_boundary", boundary);
+    }
+    if let Some(idle_secs) = report.idle_secs {
+        let _ = writeln!(out, "idle_secs = {}", idle_secs);
+    }
+    let _ = writeln!(out, "symbols_timed_out = {}", report.symbols_timed_out);
+
+    if let Some(location) = &report.location {
+        let _ = writeln!(out, "\n[location]");
+        key(&mut out, "file", &location.file);
+        let _ = writeln!(out, "line = {}", location.line);
+    }
+
+    if let Some(nested) = &report.nested {
+        let _ = writeln!(out, "\n[nested]");
+        key(&mut out, "original", &nested.original);
+        key(&mut out, "in_drop", &nested.in_drop);
+    }
+
+    if let Some(plugin) = &report.plugin {
+        let _ = writeln!(out, "\n[plugin]");
+        key(&mut out, "name", &plugin.name);
+        key(&mut out, "version", &plugin.version);
+        key(&mut out, "path", &plugin.path.display().to_string());
+        if let Some(issues) = &plugin.issues {
+            key(&mut out, "issues", issues);
+        }
+    }
+
+    let terminal = &report.terminal;
+    let _ = writeln!(out, "\n[terminal]");
+    if let Some(term) = &terminal.term {
+        key(&mut out, "term", term);
+    }
+    if let Some(colorterm) = &terminal.colorterm {
+        key(&mut out, "colorterm", colorterm);
+    }
+    if let Some((columns, rows)) = terminal.size {
+        let _ = writeln!(out, "columns = {}\nrows = {}", columns, rows);
+    }
+    let _ = writeln!(
+        out,
+        "stdin_tty = {}\nstdout_tty = {}\nstderr_tty = {}",
+        terminal.stdin_tty, terminal.stdout_tty, terminal.stderr_tty
+    );
+
+    let _ = writeln!(out, "\n[locale]");
+    if let Some(codepage) = report.locale.codepage {
+        let _ = writeln!(out, "codepage = {}", codepage);
+    }
+    let _ = writeln!(out, "\n[locale.variables]");
+    for (name, value) in &report.locale.variables {
+        key(&mut out, &string(name), value);
+    }
+
+    if !report.power.is_empty() {
+        let power = &report.power;
+        let _ = writeln!(out, "\n[power]");
+        if let Some(on_battery) = power.on_battery {
+            let _ = writeln!(out, "on_battery = {}", on_battery);
+        }
+        if let Some(percent) = power.battery_percent {
+            let _ = writeln!(out, "battery_percent = {}", percent);
+        }
+        if let Some(temperature) = power.max_temperature {
+            let _ = writeln!(out, "max_temperature = {}", temperature);
+        }
+        if let Some(count) = power.throttle_count {
+            let _ = writeln!(out, "throttle_count = {}", count);
+        }
+    }
+
+    for gpu in &report.gpus {
+        let _ = writeln!(out, "\n[[gpus]]");
+        key(&mut out, "vendor_id", &gpu.vendor_id);
+        if let Some(device_id) = &gpu.device_id {
+            key(&mut out, "device_id", device_id);
+        }
+        if let Some(driver) = &gpu.driver {
+            key(&mut out, "driver", driver);
+        }
+        if let Some(version) = &gpu.driver_version {
+            key(&mut out, "driver_version", version);
+        }
+    }
+
+    #[cfg(feature = "state")]
+    if !report.state.is_empty() {
+        out.push('\n');
+        out.push_str(&crate::state::to_toml(&report.state));
+    }
+
+    for frame in &report.backtrace {
+        let _ = writeln!(out, "\n[[backtrace]]");
+        key(&mut out, "ip", &format!("{:#x}", frame.ip));
+        for symbol in &frame.symbols {
+            let _ = writeln!(out, "\n[[backtrace.symbols]]");
+            if let Some(name) = &symbol.name {
+                key(&mut out, "name", name);
+            }
+            if let Some(file) = &symbol.file {
+                key(&mut out, "file", &file.display().to_string());
+            }
+            if let Some(line) = symbol.line {
+                let _ = writeln!(out, "line = {}", line);
+            }
+        }
+    }
+
+    out
+}
+
+/// Render `report` as a Markdown document
+///
+/// The details go into a table, the panic message into a code block and
+/// the backtrace into a collapsed `<details>` section, so the report stays
+/// readable when pasted into an issue.
+pub(crate) fn to_markdown(report: &Report) -> String {
+    fn row(out: &mut String, key: &str, value: &str) {
+        let _ = writeln!(out, "| {} | {} |", key, cell(value));
+    }
+
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "## Crash report for {} {}\n",
+        report.name, report.version
+    );
+    let _ = writeln!(out, "| | |\n|---|---|");
+    row(&mut out, "Version", &report.version);
+    row(&mut out, "Release channel", report.channel.as_str());
+    row(&mut out, "Operating system", &report.operating_system);
+    row(&mut out, "Time", &report.timestamp);
+    match (&report.location, &report.permalink) {
+        (Some(location), Some(permalink)) => {
+            let _ = writeln!(
+                out,
+                "| Location | [{}:{}]({}) |",
+                cell(&location.file),
+                location.line,
+                permalink
+            );
+        }
+        (Some(location), None) => {
+            let location = format!("{}:{}", location.file, location.line);
+            row(&mut out, "Location", &location);
+        }
+        (None, _) => row(&mut out, "Location", "unknown"),
+    }
+    row(&mut out, "Fingerprint", &report.fingerprint());
+    if let Some(build_id) = &report.build_id {
+        row(&mut out, "Build ID", build_id);
+    }
+    if let Some(session_id) = &report.session_id {
+        row(&mut out, "Session", session_id);
+    }
+    if let Some(idle_secs) = report.idle_secs {
+        let idle = format!("{} seconds before the crash", idle_secs);
+        row(&mut out, "Last user action", &idle);
+    }
+    if let Some(boundary) = &report.ffi_boundary {
+        row(&mut out, "Crossed FFI boundary", boundary);
+    }
+    if let Some(plugin) = &report.plugin {
+        let name = format!("{} {}", plugin.name, plugin.version);
+        row(&mut out, "Plugin", &name);
+    }
+    row(&mut out, "Terminal", &report.terminal.to_string());
+    row(&mut out, "Locale", &report.locale.to_string());
+    for gpu in &report.gpus {
+        row(&mut out, "GPU", &gpu.to_string());
+    }
+    if !report.power.is_empty() {
+        row(&mut out, "Power", &report.power.to_string());
+    }
+
+    let _ = writeln!(out, "\n### Panic message\n");
+    code_block(&mut out, "text", &report.cause);
+
+    if let Some(nested) = &report.nested {
+        let _ = writeln!(
+            out,
+            "\nA destructor panicked during unwinding, so the process \
+             aborted.\n\n#### Original panic\n"
+        );
+        code_block(&mut out, "text", &nested.original);
+        let _ = writeln!(out, "\n#### Panic in destructor\n");
+        code_block(&mut out, "text", &nested.in_drop);
+    }
+
+    let _ = writeln!(out, "\n<details>\n<summary>Backtrace</summary>\n");
+    code_block(&mut out, "text", report.format_backtrace().trim_start());
+    let _ = writeln!(out, "\n</details>");
+
+    #[cfg(feature = "state")]
+    if !report.state.is_empty() {
+        let (rendered, rest) = crate::state::to_markdown(&report.state);
+        let _ = writeln!(
+            out,
+            "\n<details>\n<summary>Application state</summary>\n"
+        );
+        if !rest.is_empty() {
+            code_block(&mut out, "toml", &crate::state::to_toml(&rest));
+        }
+        for (name, markdown) in rendered {
+            let _ = writeln!(out, "\n#### {}\n\n{}", name, markdown.trim_end());
+        }
+        let _ = writeln!(out, "\n</details>");
+    }
+
+    out
+}
+
+/// The placeholders of [`Settings::issue_template`](crate::Settings)
+pub(crate) const ISSUE_PLACEHOLDERS: [&str; 5] =
+    ["title", "name", "version", "cause", "crash_report"];
+
+/// Writes Markdown reports into the sections of a project's bug report
+/// template, see [`Settings::issue_template`](crate::Settings)
+pub(crate) struct IssueTemplate(pub(crate) String);
+
+impl ReportSerializer for IssueTemplate {
+    fn extension(&self) -> &str {
+        ReportFormat::Markdown.extension()
+    }
+
+    fn write(
+        &self,
+        report: &Report,
+        out: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        let markdown = to_markdown(report);
+        let filled =
+            fill_issue_template(&self.0, report, &report.cause, &markdown);
+        out.write_all(filled.as_bytes())
+    }
+}
+
+/// `template` filled in for `report`, with `cause` for the panic message
+/// and `crash_report` in the place of the report
+///
+/// Front matter, which tells GitHub what the template is for, is left out.
+fn fill_issue_template(
+    template: &str,
+    report: &Report,
+    cause: &str,
+    crash_report: &str,
+) -> String {
+    let template = template
+        .strip_prefix("---\n")
+        .and_then(|rest| rest.find("\n---\n").map(|end| &rest[end + 5..]))
+        .unwrap_or(template);
+    let value = |name: &str| match name {
+        "title" => Some(report.summary()),
+        "name" => Some(report.name.clone()),
+        "version" => Some(report.version.clone()),
+        "cause" => Some(cause.to_string()),
+        "crash_report" => Some(crash_report.trim_end().to_string()),
+        _ => None,
+    };
+    // the template was checked when the hook was installed
+    crate::template::render(template, value)
+        .unwrap_or_else(|_| crash_report.to_string())
+}
+
+/// The longest link to a new issue we show: GitHub turns longer ones down,
+/// and so do some browsers and proxies
+const MAX_ISSUE_URL_LEN: usize = 8000;
+
+/// A link to a new issue about `report` in the tracker of `meta`, with as
+/// much of the report filled in as fits into [`MAX_ISSUE_URL_LEN`]
+///
+/// The details and the summary always stay. If everything doesn't fit,
+/// frames in the middle of the backtrace go first, keeping the top ones
+/// and the outermost few, then the end of the panic message. The body asks
+/// users to attach `report_file`, which has it all, and follows `template`
+/// if the project has one.
+pub(crate) fn issue_url(
+    meta: &Metadata,
+    report: &Report,
+    report_file: Option<&Path>,
+    template: Option<&str>,
+) -> Option<String> {
+    /// More would make the URL too long even without a backtrace
+    const MAX_CAUSE_CHARS: usize = 1000;
+    /// Fewer doesn't say anything useful about most panics
+    const MIN_CAUSE_CHARS: usize = 60;
+
+    let title = report.summary();
+    let body = IssueBody::new(report, report_file, template);
+    let url = |frames, cause| {
+        meta.issue_url(&title, &body.render(frames, cause))
+    };
+    let fits = |url: &String| url.len() <= MAX_ISSUE_URL_LEN;
+
+    // the URL is longest with every frame shown, and most of the time fits
+    let full = url(body.frames.len(), MAX_CAUSE_CHARS)?;
+    if fits(&full) {
+        return Some(full);
+    }
+    let fewer_frames = (0..body.frames.len())
+        .rev()
+        .find_map(|frames| url(frames, MAX_CAUSE_CHARS).filter(fits));
+    let shorter_cause = || {
+        std::iter::successors(Some(MAX_CAUSE_CHARS / 2), |&cause| {
+            Some(cause / 2).filter(|&cause| cause >= MIN_CAUSE_CHARS)
+        })
+        .find_map(|cause| url(0, cause).filter(fits))
+    };
+    // a link that is too long still beats none for trackers that take it
+    fewer_frames
+        .or_else(shorter_cause)
+        .or_else(|| url(0, MIN_CAUSE_CHARS))
+}
+
+/// The body of a new issue about a report: the key details, the panic
+/// message and the backtrace, of which only part may fit into a URL
+struct IssueBody<'a> {
+    report: &'a Report,
+    /// The frames of the backtrace, one entry of one or more lines each
+    frames: Vec<String>,
+    report_file: Option<&'a Path>,
+    /// The project's bug report template
+    template: Option<&'a str>,
+}
+
+impl<'a> IssueBody<'a> {
+    fn new(
+        report: &'a Report,
+        report_file: Option<&'a Path>,
+        template: Option<&'a str>,
+    ) -> Self {
+        let frames = report
+            .backtrace
+            .iter()
+            .enumerate()
+            .map(|(idx, frame)| issue_frame(idx, frame))
+            .collect();
+        IssueBody {
+            report,
+            frames,
+            report_file,
+            template,
+        }
+    }
+
+    /// The body with `frames` of the backtrace and at most `cause`
+    /// characters of the panic message
+    fn render(&self, frames: usize, cause: usize) -> String {
+        fn row(out: &mut String, key: &str, value: &str) {
+            let _ = writeln!(out, "| {} | {} |", key, cell(value));
+        }
+
+        let report = self.report;
+        let mut out = String::new();
+        let _ = writeln!(out, "| | |\n|---|---|");
+        let version =
+            format!("{} ({})", report.version, report.channel.as_str());
+        row(&mut out, "Version", &version);
+        row(&mut out, "Operating system", &report.operating_system);
+        if let Some(location) = &report.location {
+            let location = format!("{}:{}", location.file, location.line);
+            row(&mut out, "Location", &location);
+        }
+        row(&mut out, "Fingerprint", &report.fingerprint());
+
+        let _ = writeln!(out, "\n### Panic message\n");
+        let cause = crate::link::truncate(&report.cause, cause);
+        code_block(&mut out, "text", &cause);
+
+        if !self.frames.is_empty() {
+            // the outermost frames tell how the program got there, e.g.
+            // from which thread
+            let bottom = (frames / 4).min(3);
+            let top = frames - bottom;
+            let omitted = self.frames.len() - frames;
+            let mut backtrace = self.frames[..top].join("\n");
+            if omitted > 0 {
+                let _ = write!(
+                    backtrace,
+                    "\n      ... {} frames left out, see the report file",
+                    omitted
+                );
+            }
+            for frame in &self.frames[self.frames.len() - bottom..] {
+                let _ = write!(backtrace, "\n{}", frame);
+            }
+            let _ = writeln!(out, "\n### Backtrace\n");
+            code_block(&mut out, "text", backtrace.trim_start_matches('\n'));
+        }
+
+        let name = self.report_file.and_then(Path::file_name);
+        match name {
+            Some(name) => {
+                let _ = writeln!(
+                    out,
+                    "\nThe whole report is in the file `{}`, which the crash \
+                     message pointed to. Please attach it by dragging it \
+                     into this box.",
+                    name.to_string_lossy()
+                );
+            }
+            None => {
+                let _ = writeln!(
+                    out,
+                    "\nPlease attach the report file the crash message \
+                     pointed to."
+                );
+            }
+        }
+        match self.template {
+            Some(template) => {
+                fill_issue_template(template, report, &cause, &out)
+            }
+            None => out,
+        }
+    }
+}
+
+/// Frame `idx` of a backtrace for an issue: its functions and where they
+/// are, without the addresses that differ from run to run
+fn issue_frame(idx: usize, frame: &Frame) -> String {
+    if frame.symbols.is_empty() {
+        return format!("{:4}: <unresolved>", idx);
+    }
+    let mut out = String::new();
+    for (nth, symbol) in frame.symbols.iter().enumerate() {
+        match nth {
+            0 => {
+                let _ = write!(out, "{:4}: ", idx);
+            }
+            _ => out.push_str("\n      "),
+        }
+        out.push_str(symbol.name.as_deref().unwrap_or("<unknown>"));
+        if let (Some(file), Some(line)) = (&symbol.file, symbol.line) {
+            let _ = write!(out, "\n        at {}:{}", file.display(), line);
+        }
+    }
+    out
+}
+
+/// Make `value` safe to put into a Markdown table cell
+fn cell(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\r', '\n'], " ")
+}
+
+/// Write `text` as a fenced code block, with a fence longer than any run of
+/// backticks inside it
+fn code_block(out: &mut String, lang: &str, text: &str) {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat((longest + 1).max(3));
+    let _ = writeln!(out, "{}{}\n{}\n{}", fence, lang, text.trim_end(), fence);
+}
+
+/// Quote `value` as a TOML basic string, which is a valid JSON string too
+pub(crate) fn string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04X}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,465 @@
+//! Uploading crash reports over HTTP, with the user's consent
+//!
+//! Like every sink that uploads reports, an [`HttpSink`] only gets those
+//! the user agreed to send, see [`UploadConsent`](crate::UploadConsent).
+//! Reports that couldn't be sent, e.g. without a network connection, can
+//! be queued in a spool directory and are sent on the next run.
+//!
+//! Requests go through the proxy set in the standard `HTTPS_PROXY`,
+//! `HTTP_PROXY` or `ALL_PROXY` environment variables, except for hosts
+//! listed in `NO_PROXY`.
+
+use crate::sink::ReportSink;
+use crate::{Report, ReportFormat, ReportSerializer};
+use std::error::Error;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use ureq::tls::{Certificate, PemItem, RootCerts, TlsConfig};
+
+/// POSTs every report to an HTTP endpoint, if the user agrees
+///
+/// Before it gets a report, the hook makes sure the user agrees, see
+/// [`UploadConsent`](crate::UploadConsent).
+///
+/// Uploads run after the report file is written, and give up after
+/// [2 seconds](HttpSink::timeout), so a slow endpoint can't hold up the
+/// crashing process for long.
+///
+/// With a [`spool_dir`](HttpSink::spool_dir), reports the user agreed to
+/// are queued there before they are sent, and stay queued if they couldn't
+/// be sent or the process was killed while sending. They are sent in the
+/// background on the next run or on
+/// [`flush_pending_reports`](crate::flush_pending_reports). After a failed
+/// attempt, a report waits a minute before it is tried again, and twice as
+/// long after every further one, up to a day. It is dropped after 12
+/// attempts.
+///
+/// ```no_run
+/// use human_panic_logger::{metadata, Builder, HttpSink};
+///
+/// let upload = HttpSink::new("https://crashes.example.com/report")
+///     .header("Authorization", "Bearer 7f3a")
+///     .spool_dir("/home/user/.cache/app/pending-reports")
+///     .root_certificate_file("/etc/ssl/certs/company-root.pem")?;
+/// Builder::new(metadata!(), "app.log")
+///     .sink(upload)
+///     .install()
+///     .expect("crash reporting could not be set up");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct HttpSink {
+    url: String,
+    serializer: Box<dyn ReportSerializer>,
+    headers: Vec<(String, String)>,
+    spool_dir: Option<PathBuf>,
+    proxy: Option<String>,
+    root_certs: Vec<Certificate<'static>>,
+    timeout: Duration,
+}
+
+impl HttpSink {
+    /// POST TOML reports to `url`
+    pub fn new(url: impl Into<String>) -> Self {
+        HttpSink {
+            url: url.into(),
+            serializer: Box::new(ReportFormat::Toml),
+            headers: Vec::new(),
+            spool_dir: None,
+            proxy: None,
+            root_certs: Vec::new(),
+            timeout: Duration::from_secs(2),
+        }
+    }
+
+    /// Send reports serialized with `serializer` instead
+    pub fn serializer(
+        mut self,
+        serializer: impl ReportSerializer + 'static,
+    ) -> Self {
+        self.serializer = Box::new(serializer);
+        self
+    }
+
+    /// Send a header with every request, e.g. for authentication
+    pub fn header(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Queue reports that couldn't be sent in `dir`, to send them later
+    pub fn spool_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.spool_dir = Some(dir.into());
+        self
+    }
+
+    /// Send requests through the proxy at `proxy`, e.g.
+    /// `http://proxy.example.com:3128`, instead of the one set in the
+    /// environment
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Trust the certificates in the PEM bundle `pem`, e.g. a company's
+    /// root certificate, instead of the usual roots
+    ///
+    /// This can be called several times to trust more certificates. Fails
+    /// if `pem` holds no certificate or one that can't be read.
+    pub fn root_certificates(mut self, pem: &[u8]) -> io::Result<Self> {
+        let invalid = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+        let mut found = false;
+        for item in ureq::tls::parse_pem(pem) {
+            if let PemItem::Certificate(cert) = item.map_err(invalid)? {
+                self.root_certs.push(cert);
+                found = true;
+            }
+        }
+        match found {
+            true => Ok(self),
+            false => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no certificate found",
+            )),
+        }
+    }
+
+    /// Trust the certificates in the PEM file at `path`, see
+    /// [`root_certificates`](HttpSink::root_certificates)
+    pub fn root_certificate_file(
+        self,
+        path: impl AsRef<Path>,
+    ) -> io::Result<Self> {
+        self.root_certificates(&std::fs::read(path)?)
+    }
+
+    /// Give up on a request after `timeout`, 2 seconds by default
+    ///
+    /// Reports are sent while the crashed process waits to exit, so this
+    /// should stay short.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// POST `body`, the report serialized to a file with `extension`
+    fn post(&self, body: &[u8], extension: &str) -> Result<(), ureq::Error> {
+        let mut config =
+            ureq::Agent::config_builder().timeout_global(Some(self.timeout));
+        if let Some(proxy) = &self.proxy {
+            config = config.proxy(Some(ureq::Proxy::new(proxy)?));
+        }
+        if !self.root_certs.is_empty() {
+            let roots = RootCerts::Specific(Arc::new(self.root_certs.clone()));
+            config = config
+                .tls_config(TlsConfig::builder().root_certs(roots).build());
+        }
+        let agent: ureq::Agent = config.build().into();
+        let mut request = agent
+            .post(&self.url)
+            .header("Content-Type", content_type(extension));
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        request.send(body)?;
+        Ok(())
+    }
+
+    /// Queue `body`, the report serialized to a file with `extension`, in
+    /// the spool directory, returning the queued file
+    fn queue(&self, body: &[u8], extension: &str) -> io::Result<PathBuf> {
+        // the queue fills up only while offline, so keep what came first
+        const MAX_QUEUED: usize = 20;
+
+        let dir = self.spool_dir.as_ref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "no spool directory is set")
+        })?;
+        std::fs::create_dir_all(dir)?;
+        let queued = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| is_queued(&entry.path()))
+            .count();
+        if queued >= MAX_QUEUED {
+            return Err(io::Error::other("the queue is full"));
+        }
+
+        // written under another name first, so it's never sent half done
+        let file = dir.join(format!("{}.{}", crate::id::random(), extension));
+        let tmp = with_suffix(&file, TMP_SUFFIX);
+        std::fs::write(&tmp, body)?;
+        std::fs::rename(&tmp, &file)?;
+        Ok(file)
+    }
+}
+
+impl ReportSink for HttpSink {
+    fn submit(
+        &self,
+        report: &Report,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut body = Vec::new();
+        self.serializer.write(report, &mut body)?;
+        let extension = self.serializer.extension();
+        // queued first, so the report isn't lost if the process is killed
+        // while it waits for the endpoint
+        let queued = self
+            .spool_dir
+            .as_ref()
+            .map(|_| self.queue(&body, extension));
+        let result = self.post(&body, extension);
+        let error = |e: &ureq::Error, message: String| {
+            match unreachable(e) {
+                true => io::Error::new(io::ErrorKind::NotConnected, message),
+                false => io::Error::other(message),
+            }
+        };
+        match (result, queued) {
+            (Err(e), Some(Ok(file))) if retryable(&e) => {
+                let _ = Schedule::default().failed().write(&file);
+                let message = format!("{}, queued to send later", e);
+                Err(error(&e, message).into())
+            }
+            (Err(e), Some(Err(queue))) if retryable(&e) => {
+                let message = format!("{}, and not queued: {}", e, queue);
+                Err(error(&e, message).into())
+            }
+            (Err(e), None) if unreachable(&e) => {
+                Err(error(&e, e.to_string()).into())
+            }
+            (result, queued) => {
+                if let Some(Ok(file)) = queued {
+                    let _ = std::fs::remove_file(file);
+                }
+                Ok(result?)
+            }
+        }
+    }
+
+    fn check(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let url: ureq::http::Uri = self.url.parse()?;
+        if !matches!(url.scheme_str(), Some("http" | "https")) {
+            return Err(format!("not an HTTP URL: {}", self.url).into());
+        }
+        if let Some(proxy) = &self.proxy {
+            ureq::Proxy::new(proxy)?;
+        }
+        Ok(())
+    }
+
+    fn uploads(&self) -> bool {
+        true
+    }
+
+    fn flush_pending(&self) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let dir = match &self.spool_dir {
+            Some(dir) => dir,
+            None => return Ok(0),
+        };
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut sent = 0;
+        for file in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+            if !is_queued(&file) {
+                continue;
+            }
+            let schedule = Schedule::read(&file);
+            if !schedule.due() {
+                continue;
+            }
+            // claim the report, so other instances starting at the same
+            // time don't send it too
+            let claimed = with_suffix(&file, SENDING_SUFFIX);
+            if std::fs::rename(&file, &claimed).is_err() {
+                continue;
+            }
+            let body = std::fs::read(&claimed)?;
+            let extension = file.extension().unwrap_or_default();
+            match self.post(&body, &extension.to_string_lossy()) {
+                Ok(()) => {
+                    remove_queued(&claimed, &file);
+                    sent += 1;
+                }
+                Err(e) if retryable(&e) => {
+                    let schedule = schedule.failed();
+                    if schedule.attempts >= Schedule::MAX_ATTEMPTS {
+                        log::warn!(
+                            "Dropping a queued crash report after {} \
+                             attempts: {}",
+                            schedule.attempts,
+                            e
+                        );
+                        remove_queued(&claimed, &file);
+                        continue;
+                    }
+                    let _ = std::fs::rename(&claimed, &file);
+                    let _ = schedule.write(&file);
+                    // still offline, so the rest would fail as well
+                    return Err(e.into());
+                }
+                Err(e) => {
+                    log::warn!("Dropping a queued crash report: {}", e);
+                    remove_queued(&claimed, &file);
+                }
+            }
+        }
+        Ok(sent)
+    }
+}
+
+/// Appended to queued reports while they are being written
+const TMP_SUFFIX: &str = ".tmp";
+/// Appended to queued reports while they are being sent
+const SENDING_SUFFIX: &str = ".sending";
+/// Appended to queued reports for the file with their retry schedule
+const RETRY_SUFFIX: &str = ".retry";
+
+/// Whether `path` is a queued report, ready to be sent
+fn is_queued(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.is_file()
+        && !name.ends_with(TMP_SUFFIX)
+        && !name.ends_with(SENDING_SUFFIX)
+        && !name.ends_with(RETRY_SUFFIX)
+}
+
+/// Remove the queued report `file`, claimed as `claimed`, and its schedule
+fn remove_queued(claimed: &Path, file: &Path) {
+    let _ = std::fs::remove_file(claimed);
+    let _ = std::fs::remove_file(with_suffix(file, RETRY_SUFFIX));
+}
+
+/// How often sending a queued report failed, and when to try again
+///
+/// It's kept next to the report in a file like
+///
+/// ```toml
+/// attempts = 3
+/// next_attempt = 1714557600
+/// ```
+///
+/// where `next_attempt` is in seconds since the Unix epoch.
+#[derive(Debug, Clone, Copy, Default)]
+struct Schedule {
+    attempts: u32,
+    next_attempt: u64,
+}
+
+impl Schedule {
+    /// The wait after the first failed attempt, doubling with every further
+    /// one
+    const FIRST_BACKOFF: u64 = 60;
+    /// The longest wait between attempts
+    const MAX_BACKOFF: u64 = 24 * 60 * 60;
+    /// Reports that failed this often are dropped
+    const MAX_ATTEMPTS: u32 = 12;
+
+    /// The schedule of the queued report `file`, which has none until it
+    /// failed
+    fn read(file: &Path) -> Self {
+        let text = std::fs::read_to_string(with_suffix(file, RETRY_SUFFIX));
+        let mut schedule = Schedule::default();
+        for line in text.unwrap_or_default().lines() {
+            match line.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+                Some(("attempts", value)) => {
+                    schedule.attempts = value.parse().unwrap_or_default();
+                }
+                Some(("next_attempt", value)) => {
+                    schedule.next_attempt = value.parse().unwrap_or_default();
+                }
+                _ => {}
+            }
+        }
+        schedule
+    }
+
+    /// Whether it's time to try again
+    fn due(self) -> bool {
+        now() >= self.next_attempt
+    }
+
+    /// The schedule after another failed attempt
+    fn failed(self) -> Self {
+        let attempts = self.attempts.saturating_add(1);
+        let factor = 1u64 << (attempts - 1).min(32);
+        let backoff = Self::FIRST_BACKOFF
+            .saturating_mul(factor)
+            .min(Self::MAX_BACKOFF);
+        Schedule {
+            attempts,
+            next_attempt: now() + backoff,
+        }
+    }
+
+    /// Keep the schedule next to the queued report `file`
+    fn write(self, file: &Path) -> io::Result<()> {
+        std::fs::write(
+            with_suffix(file, RETRY_SUFFIX),
+            format!(
+                "attempts = {}\nnext_attempt = {}\n",
+                self.attempts, self.next_attempt
+            ),
+        )
+    }
+}
+
+/// The time in seconds since the Unix epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// `path` with `suffix` appended to the file name
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Whether sending again later might work, unlike when the endpoint
+/// rejected the report
+fn retryable(error: &ureq::Error) -> bool {
+    !matches!(
+        error,
+        ureq::Error::StatusCode(400..=499) | ureq::Error::InvalidProxyUrl
+    )
+}
+
+/// Whether the endpoint couldn't be reached at all, e.g. without a network
+/// connection, rather than answering with an error
+fn unreachable(error: &ureq::Error) -> bool {
+    match error {
+        ureq::Error::Timeout(_)
+        | ureq::Error::HostNotFound
+        | ureq::Error::ConnectionFailed
+        | ureq::Error::ConnectProxyFailed(_) => true,
+        ureq::Error::Io(e) => matches!(
+            e.kind(),
+            io::ErrorKind::ConnectionRefused
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::NotConnected
+                | io::ErrorKind::TimedOut
+        ),
+        _ => false,
+    }
+}
+
+/// The media type of reports in files with `extension`
+fn content_type(extension: &str) -> &'static str {
+    match extension {
+        "toml" => "application/toml",
+        "md" => "text/markdown",
+        "json" => "application/json",
+        _ => "application/octet-stream",
+    }
+}
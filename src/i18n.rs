@@ -0,0 +1,593 @@
+//! Translations of the crash message
+//!
+//! The crash message is shown in the user's language when there is a
+//! translation for it. German, Spanish, French, Italian and Portuguese come
+//! with the crate unless it is built with the `tiny` feature, and
+//! applications can add more with [`register_language`]. The language is
+//! the one set in
+//! [`Settings::language`](crate::Settings#structfield.language), or else
+//! the first one known from the `LANGUAGE`, `LC_ALL`, `LC_MESSAGES` and
+//! `LANG` environment variables, or on Windows from the user's display
+//! languages.
+//!
+//! In translations written right to left, the values filled in, like paths
+//! and URLs, are isolated from the text around them, see
+//! [`Translation`].
+//!
+//! Messages a translation lacks are shown in English, and so are the
+//! [accessible message](crate::Settings#structfield.accessible) and
+//! [explanations](crate::Settings#structfield.explain_panics) of panics.
+
+use crate::clock::DateTime;
+use crate::SetupError;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A message of the crash message
+struct Message {
+    id: &'static str,
+    /// The placeholders its text must contain
+    placeholders: &'static [&'static str],
+    english: &'static str,
+}
+
+/// Every message of the crash message
+const MESSAGES: [Message; 26] = [
+    Message {
+        id: "heading",
+        placeholders: &[],
+        english: "Well, this is embarrassing.",
+    },
+    Message {
+        id: "crashed",
+        placeholders: &["name"],
+        english: "{name} had a problem and crashed. To help us diagnose the \
+                  problem you can send us a crash report.",
+    },
+    Message {
+        id: "explanation",
+        placeholders: &["explanation"],
+        english: "What went wrong: {explanation}.",
+    },
+    Message {
+        id: "report-file",
+        placeholders: &["path"],
+        english: "We have generated a report file at \"{path}\".",
+    },
+    Message {
+        id: "report-details",
+        placeholders: &["size", "time"],
+        english: "It is {size} and was written on {time}.",
+    },
+    Message {
+        id: "log-file",
+        placeholders: &["path"],
+        english: "There is a log file of the crash at \"{path}\".",
+    },
+    Message {
+        id: "log-on-stdout",
+        placeholders: &["path"],
+        english: "The log file \"{path}\" could not be created, so the crash \
+                  details were written to standard output instead.",
+    },
+    Message {
+        id: "call-to-action",
+        placeholders: &["subject"],
+        english: "Please submit an issue or email with the subject of \
+                  \"{subject}\" and include the file as an attachment.",
+    },
+    Message {
+        id: "plugin-issues",
+        placeholders: &["name", "version", "url"],
+        english: "The crash happened in the plugin \"{name}\" {version}. \
+                  Please report it to its authors at {url} and include the \
+                  file as an attachment.",
+    },
+    Message {
+        id: "plugin-authors",
+        placeholders: &["name", "version"],
+        english: "The crash happened in the plugin \"{name}\" {version}. \
+                  Please report it to the plugin's authors and include the \
+                  file as an attachment.",
+    },
+    Message {
+        id: "shell-path",
+        placeholders: &["path"],
+        english: "To use it in a shell: {path}",
+    },
+    Message {
+        id: "issue-link",
+        placeholders: &["url"],
+        english: "To file an issue with the details filled in, open: {url}",
+    },
+    Message {
+        id: "mail-link",
+        placeholders: &["url"],
+        english: "To email us with the details filled in, open: {url}",
+    },
+    Message {
+        id: "homepage",
+        placeholders: &["url"],
+        english: "Homepage: {url}",
+    },
+    Message {
+        id: "repository",
+        placeholders: &["url"],
+        english: "Repository: {url}",
+    },
+    Message {
+        id: "authors",
+        placeholders: &["authors"],
+        english: "Authors: {authors}",
+    },
+    Message {
+        id: "author",
+        placeholders: &["author"],
+        english: "Author: {author}",
+    },
+    Message {
+        id: "contact",
+        placeholders: &["contact"],
+        english: "Contact: {contact}",
+    },
+    Message {
+        id: "source",
+        placeholders: &["url"],
+        english: "Source: {url}",
+    },
+    Message {
+        id: "privacy",
+        placeholders: &[],
+        english: "We take privacy seriously, and do not perform any \
+                  automated error collection. In order to improve the \
+                  software, we rely on people to submit reports.",
+    },
+    Message {
+        id: "thanks",
+        placeholders: &[],
+        english: "Thank you!",
+    },
+    Message {
+        id: "upload-unreachable",
+        placeholders: &["path"],
+        english: "We could not reach the crash server, so the report was not \
+                  sent. It was saved locally at \"{path}\".",
+    },
+    Message {
+        id: "upload-failed",
+        placeholders: &["path"],
+        english: "The crash report could not be sent. It was saved locally \
+                  at \"{path}\".",
+    },
+    Message {
+        id: "date-time",
+        placeholders: &[],
+        english: "%d %b %Y at %H:%M",
+    },
+    Message {
+        id: "decimal-separator",
+        placeholders: &[],
+        english: ".",
+    },
+    Message {
+        id: "size-units",
+        placeholders: &[],
+        english: "B KB MB GB",
+    },
+];
+
+/// The translations that come with the crate, by language tag
+#[cfg(not(feature = "tiny"))]
+const BUNDLED: [(&str, &str); 5] = [
+    ("de", include_str!("../translations/de.txt")),
+    ("es", include_str!("../translations/es.txt")),
+    ("fr", include_str!("../translations/fr.txt")),
+    ("it", include_str!("../translations/it.txt")),
+    ("pt", include_str!("../translations/pt.txt")),
+];
+
+/// `tiny` builds leave the translations out
+#[cfg(feature = "tiny")]
+const BUNDLED: [(&str, &str); 0] = [];
+
+/// The languages registered by the application, by lowercase tag
+static LANGUAGES: Mutex<BTreeMap<String, Arc<Translation>>> =
+    Mutex::new(BTreeMap::new());
+
+/// The crash message in another language
+///
+/// Translations can be built message by message, or read from text with
+/// one `id = text` line per message:
+///
+/// ```
+/// use human_panic_logger::{register_language, Translation};
+///
+/// let dutch = Translation::parse(
+///     "# Dutch\n\
+///      heading = Nou, dit is gênant.\n\
+///      thanks = Bedankt!\n",
+/// )?
+/// .message("author", "Auteur: {author}")?
+/// .message("date-time", "%d-%m-%Y om %H:%M")?
+/// .message("decimal-separator", ",")?;
+/// register_language("nl", dutch);
+/// # Ok::<(), human_panic_logger::SetupError>(())
+/// ```
+///
+/// In messages with Arabic, Hebrew or other right-to-left letters, the
+/// values are put between U+2068 and U+2069, so terminals lay out a path or
+/// URL on its own rather than mixing it up with the text around it.
+///
+/// Each message must keep the placeholders of the English one:
+///
+/// | Id | Placeholders | English |
+/// |---|---|---|
+/// | `heading` | | Well, this is embarrassing. |
+/// | `crashed` | `{name}` | {name} had a problem and crashed. ... |
+/// | `explanation` | `{explanation}` | What went wrong: {explanation}. |
+/// | `report-file` | `{path}` | We have generated a report file at ... |
+/// | `report-details` | `{size}`, `{time}` | It is {size} and was ... |
+/// | `log-file` | `{path}` | There is a log file of the crash at ... |
+/// | `log-on-stdout` | `{path}` | The log file "{path}" could not be ... |
+/// | `call-to-action` | `{subject}` | Please submit an issue or email ... |
+/// | `plugin-issues` | `{name}`, `{version}`, `{url}` | The crash ... |
+/// | `plugin-authors` | `{name}`, `{version}` | The crash happened ... |
+/// | `shell-path` | `{path}` | To use it in a shell: {path} |
+/// | `issue-link` | `{url}` | To file an issue with the details ... |
+/// | `mail-link` | `{url}` | To email us with the details filled ... |
+/// | `homepage` | `{url}` | Homepage: {url} |
+/// | `repository` | `{url}` | Repository: {url} |
+/// | `authors` | `{authors}` | Authors: {authors} |
+/// | `author` | `{author}` | Author: {author} |
+/// | `contact` | `{contact}` | Contact: {contact} |
+/// | `source` | `{url}` | Source: {url} |
+/// | `privacy` | | We take privacy seriously, and do not ... |
+/// | `thanks` | | Thank you! |
+/// | `upload-unreachable` | `{path}` | We could not reach the crash ... |
+/// | `upload-failed` | `{path}` | The crash report could not be sent. ... |
+///
+/// Dates, times and file sizes in the message are written like the
+/// language does, which these entries tell:
+///
+/// | Id | English | Meaning |
+/// |---|---|---|
+/// | `date-time` | %d %b %Y at %H:%M | `strftime`, `%b` only in English |
+/// | `decimal-separator` | . | Before the tenths of a size |
+/// | `size-units` | B KB MB GB | Bytes, then 1000, 1000², 1000³ bytes |
+///
+/// The bundled translations in the crate's `translations` directory have
+/// the whole texts.
+#[derive(Debug, Clone, Default)]
+pub struct Translation {
+    messages: BTreeMap<&'static str, String>,
+}
+
+impl Translation {
+    /// A translation without any messages yet
+    pub fn new() -> Self {
+        Translation::default()
+    }
+
+    /// Translate the message `id` with `text`
+    ///
+    /// Fails with [`SetupError::InvalidTemplate`] if there is no such
+    /// message, or `text` lacks one of its placeholders or has others.
+    pub fn message(
+        mut self,
+        id: &str,
+        text: impl Into<String>,
+    ) -> Result<Self, SetupError> {
+        let text = text.into();
+        let invalid = |reason: String| SetupError::InvalidTemplate {
+            template: text.clone(),
+            reason,
+        };
+        let message = MESSAGES
+            .iter()
+            .find(|message| message.id == id)
+            .ok_or_else(|| invalid(format!("unknown message id '{}'", id)))?;
+        crate::template::check(&text, message.placeholders).map_err(invalid)?;
+        for placeholder in message.placeholders {
+            if !text.contains(&format!("{{{}}}", placeholder)) {
+                return Err(invalid(format!(
+                    "'{}' must contain {{{}}}",
+                    id, placeholder
+                )));
+            }
+        }
+        self.messages.insert(message.id, text);
+        Ok(self)
+    }
+
+    /// Read a translation with one `id = text` line per message
+    ///
+    /// Blank lines and lines starting with `#` are skipped. Fails like
+    /// [`message`](Translation::message), or on a line of another kind.
+    pub fn parse(source: &str) -> Result<Self, SetupError> {
+        let mut translation = Translation::new();
+        let source = source.strip_prefix('\u{FEFF}').unwrap_or(source);
+        for line in source.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (id, text) = line.split_once('=').ok_or_else(|| {
+                SetupError::InvalidTemplate {
+                    template: line.to_string(),
+                    reason: "expected a line like 'id = text'".into(),
+                }
+            })?;
+            translation = translation.message(id.trim(), text.trim())?;
+        }
+        Ok(translation)
+    }
+}
+
+/// Show the crash message in `translation` to users of the language `tag`,
+/// e.g. `"nl"` or `"pt-BR"`
+///
+/// A translation registered for a language that comes with the crate
+/// replaces it, and one for `"pt-BR"` takes precedence over `"pt"` for
+/// users in Brazil.
+pub fn register_language(tag: &str, translation: Translation) {
+    languages().insert(normalize(tag), Arc::new(translation));
+}
+
+fn languages() -> MutexGuard<'static, BTreeMap<String, Arc<Translation>>> {
+    // a panic while registering leaves the map as it was
+    LANGUAGES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The messages of the crash message in one language
+pub(crate) struct Messages(Option<Arc<Translation>>);
+
+impl Messages {
+    /// The messages in English
+    pub(crate) fn english() -> Self {
+        Messages(None)
+    }
+
+    /// The messages in `language`, or else in the user's language
+    pub(crate) fn new(language: Option<&str>) -> Self {
+        let candidates = match language {
+            Some(language) => vec![language.to_string()],
+            None => user_languages(),
+        };
+        candidates
+            .iter()
+            .find_map(|tag| find(&normalize(tag)))
+            .map(Messages)
+            .unwrap_or_else(Messages::english)
+    }
+
+    /// The text of the message `id`, with the placeholders filled in from
+    /// `values`
+    pub(crate) fn text(&self, id: &str, values: &[(&str, &str)]) -> String {
+        let english = MESSAGES
+            .iter()
+            .find(|message| message.id == id)
+            .map_or("", |message| message.english);
+        let text = self
+            .0
+            .as_ref()
+            .and_then(|translation| translation.messages.get(id))
+            .map_or(english, String::as_str);
+        let value = |name: &str| {
+            values
+                .iter()
+                .find(|(placeholder, _)| *placeholder == name)
+                .map(|(_, value)| *value)
+        };
+        crate::template::render_isolated(text, value)
+            .or_else(|_| crate::template::render(english, value))
+            .unwrap_or_default()
+    }
+
+    /// `time` written like the language does, e.g. `16.10.2026 um 12:05`
+    pub(crate) fn date_time(&self, time: DateTime) -> String {
+        time.format(&self.text("date-time", &[]))
+    }
+
+    /// A size of `bytes` written like the language does, e.g. `5,4 KB`
+    ///
+    /// Sizes have one decimal below 10 of their unit, and none above.
+    pub(crate) fn size(&self, bytes: u64) -> String {
+        let units = self.text("size-units", &[]);
+        let mut units: Vec<&str> = units.split_whitespace().collect();
+        if units.len() != 4 {
+            units = vec!["B", "KB", "MB", "GB"];
+        }
+        let (mut unit, mut scale) = (0, 1u64);
+        // sizes that round up to 1000 of a unit are shown in the next one
+        while unit + 1 < units.len()
+            && bytes.saturating_add(scale / 2) >= scale * 1000
+        {
+            unit += 1;
+            scale *= 1000;
+        }
+        if unit == 0 {
+            return format!("{} {}", bytes, units[0]);
+        }
+        let tenths = bytes.saturating_add(scale / 20) / (scale / 10);
+        if tenths < 100 {
+            let separator = self.text("decimal-separator", &[]);
+            let (whole, tenth) = (tenths / 10, tenths % 10);
+            format!("{}{}{} {}", whole, separator, tenth, units[unit])
+        } else {
+            format!(
+                "{} {}",
+                bytes.saturating_add(scale / 2) / scale,
+                units[unit]
+            )
+        }
+    }
+
+    /// The text of the message `id` before and after its placeholder
+    /// `split`, e.g. to show a path in another color
+    pub(crate) fn split(
+        &self,
+        id: &str,
+        values: &[(&str, &str)],
+        split: &str,
+    ) -> (String, String) {
+        // a character no translation has
+        const MARK: &str = "\u{0}";
+
+        let mut values = values.to_vec();
+        values.push((split, MARK));
+        let text = self.text(id, &values);
+        match text.split_once(MARK) {
+            Some((before, after)) => (before.to_string(), after.to_string()),
+            None => (text, String::new()),
+        }
+    }
+}
+
+/// The translation for `tag` or its language without the region, `None`
+/// inside for English, or `None` if there is none
+fn find(tag: &str) -> Option<Option<Arc<Translation>>> {
+    let language = tag.split('-').next().unwrap_or_default();
+    for tag in [tag, language] {
+        if tag == "en" {
+            return Some(None);
+        }
+        if let Some(translation) = languages().get(tag) {
+            return Some(Some(translation.clone()));
+        }
+        let bundled = BUNDLED.iter().find(|(bundled, _)| *bundled == tag);
+        if let Some((_, source)) = bundled {
+            return Some(Translation::parse(source).ok().map(Arc::new));
+        }
+    }
+    None
+}
+
+/// `tag` as a lowercase language tag, e.g. `pt-br` for `pt_BR.UTF-8`
+fn normalize(tag: &str) -> String {
+    let tag = tag.split(['.', '@']).next().unwrap_or_default();
+    tag.trim().replace('_', "-").to_ascii_lowercase()
+}
+
+/// The languages the user prefers, most preferred first
+fn user_languages() -> Vec<String> {
+    let mut languages = Vec::new();
+    for name in ["LANGUAGE", "LC_ALL", "LC_MESSAGES", "LANG"] {
+        let value = std::env::var(name).unwrap_or_default();
+        languages.extend(
+            value
+                .split(':')
+                .filter(|tag| !tag.is_empty() && *tag != "C")
+                .filter(|tag| *tag != "POSIX" && !tag.starts_with("C."))
+                .map(str::to_string),
+        );
+    }
+    languages.extend(ui_languages());
+    languages
+}
+
+/// The user's display languages, on Windows
+#[cfg(windows)]
+fn ui_languages() -> Vec<String> {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetUserPreferredUILanguages(
+            flags: u32,
+            count: *mut u32,
+            buffer: *mut u16,
+            size: *mut u32,
+        ) -> i32;
+    }
+    const MUI_LANGUAGE_NAME: u32 = 0x8;
+
+    let (mut count, mut size) = (0, 0);
+    // SAFETY: without a buffer, this only asks for the size needed
+    let sized = unsafe {
+        GetUserPreferredUILanguages(
+            MUI_LANGUAGE_NAME,
+            &mut count,
+            std::ptr::null_mut(),
+            &mut size,
+        )
+    };
+    if sized == 0 {
+        return Vec::new();
+    }
+    let mut buffer = vec![0u16; size as usize];
+    // SAFETY: the buffer holds the `size` characters asked for
+    let read = unsafe {
+        GetUserPreferredUILanguages(
+            MUI_LANGUAGE_NAME,
+            &mut count,
+            buffer.as_mut_ptr(),
+            &mut size,
+        )
+    };
+    if read == 0 {
+        return Vec::new();
+    }
+    // a list of names, each ending in a null character
+    String::from_utf16_lossy(&buffer)
+        .split('\0')
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn ui_languages() -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_switch_units_where_they_round_to_a_thousand() {
+        let english = Messages::english();
+        let sizes = [
+            (0, "0 B"),
+            (999, "999 B"),
+            (1000, "1.0 KB"),
+            (9_949, "9.9 KB"),
+            (9_950, "10 KB"),
+            (999_499, "999 KB"),
+            (999_500, "1.0 MB"),
+            (5_400_000, "5.4 MB"),
+            (2_500_000_000_000, "2500 GB"),
+            (u64::MAX, "18446744073 GB"),
+        ];
+        for (bytes, text) in sizes {
+            assert_eq!(english.size(bytes), text, "{} bytes", bytes);
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "tiny"))]
+    fn sizes_and_times_are_written_like_the_language_does() {
+        let time = DateTime::parse("2026-01-02T09:05:00+01:00").unwrap();
+        let expected = [
+            ("en", "5.4 KB", "02 Jan 2026 at 09:05"),
+            ("de", "5,4 KB", "02.01.2026 um 09:05"),
+            ("es", "5,4 KB", "02/01/2026 a las 09:05"),
+            ("fr", "5,4 Ko", "02/01/2026 à 09:05"),
+            ("it", "5,4 KB", "02/01/2026 alle 09:05"),
+            ("pt", "5,4 KB", "02/01/2026 às 09:05"),
+        ];
+        for (language, size, date_time) in expected {
+            let messages = Messages::new(Some(language));
+            assert_eq!(messages.size(5_400), size, "{}", language);
+            assert_eq!(messages.date_time(time), date_time, "{}", language);
+        }
+    }
+
+    #[test]
+    fn malformed_size_units_fall_back_to_english() {
+        let translation = Translation::new()
+            .message("size-units", "B KB")
+            .unwrap()
+            .message("decimal-separator", ",")
+            .unwrap();
+        let messages = Messages(Some(Arc::new(translation)));
+        assert_eq!(messages.size(1_500), "1,5 KB");
+    }
+}
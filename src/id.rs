@@ -0,0 +1,47 @@
+//! Unique ids for report files, sessions and the like
+//!
+//! With the `uuid` feature, ids are random UUIDs. Without it they look the
+//! same but come from std's randomly seeded hasher, which keeps them apart
+//! without making them hard to guess.
+
+/// A new random id, like `4cc0fa49-9ff7-402a-84c2-4eb5acb3ba28`
+#[cfg(feature = "uuid")]
+pub(crate) fn random() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+#[cfg(not(feature = "uuid"))]
+pub(crate) fn random() -> String {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    static COUNT: AtomicU64 = AtomicU64::new(0);
+
+    let count = COUNT.fetch_add(1, Ordering::Relaxed);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos());
+    let half = |salt: u64| {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(salt);
+        hasher.write_u64(count);
+        hasher.write_u32(std::process::id());
+        hasher.write_u128(nanos);
+        u128::from(hasher.finish())
+    };
+    // the version and variant bits of a random UUID
+    let bits = (half(0) << 64 | half(1)) & !(0xf << 76) & !(0b11 << 62)
+        | 0x4 << 76
+        | 0b10 << 62;
+    let hex = format!("{:032x}", bits);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
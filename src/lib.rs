@@ -42,28 +42,625 @@
 
 #![cfg_attr(feature = "nightly", deny(missing_docs))]
 #![cfg_attr(feature = "nightly", feature(panic_info_message))]
+// with `disabled`, the hook isn't compiled, leaving what only it uses unused
+#![cfg_attr(feature = "disabled", allow(dead_code, unused_imports))]
 
 use std::borrow::Cow;
+use std::fmt;
 use std::io::{Result as IoResult, Write};
-use std::panic::PanicInfo;
+use std::panic::{Location, PanicHookInfo};
+use std::fs::OpenOptions;
 use std::path::Path;
-use termcolor::{BufferWriter, Color, ColorChoice, ColorSpec, WriteColor};
-use backtrace::Backtrace;
-use core::mem;
-use std::fmt::Write as WriteFmt;
+use crate::color::{Buffer, BufferWriter, ColorChoice, WriteColor};
+use crate::i18n::Messages;
 
+mod activity;
+mod assertion;
+mod authors;
+#[cfg(feature = "state")]
+mod breadcrumbs;
+mod browser;
+mod builder;
+#[cfg(feature = "clap")]
+mod cli;
+mod clock;
+mod color;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+mod compress;
+mod consent;
+mod debugger;
+mod doctor;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+pub mod environment;
+mod ffi;
+mod format;
+#[cfg(feature = "http")]
+mod http;
+mod i18n;
+mod id;
+mod link;
+#[cfg(feature = "serde")]
+mod load;
+mod long_path;
+mod nested;
+mod observe;
+mod origin;
+mod output;
+mod permissions;
+mod plain;
+mod plugin;
+mod profile;
+#[cfg(all(feature = "distro-report", target_os = "linux"))]
+pub mod distro;
+#[cfg(all(feature = "macos-crash-report", target_os = "macos"))]
+pub mod macos;
+#[cfg(feature = "prost")]
+pub mod proto;
+pub mod report;
+mod rollup;
+#[cfg(feature = "screenshot")]
+mod screenshot;
+#[cfg(feature = "sentry")]
+mod sentry_sink;
+mod session;
+mod shared;
+mod shell;
+mod sink;
+#[cfg(feature = "signing")]
+pub mod signing;
+#[cfg(feature = "state")]
+mod state;
+mod supervisor;
+#[cfg(feature = "backtrace")]
+mod symbol_cache;
+mod symbols;
+mod template;
+mod theme;
+
+pub use activity::note_activity;
+pub use authors::{join_authors, split_authors, AuthorsDisplay};
+#[cfg(feature = "state")]
+pub use breadcrumbs::Breadcrumbs;
+pub use builder::{Builder, SetupError};
+#[cfg(feature = "clap")]
+pub use cli::crash_report_args;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use compress::{Compressed, Compression};
+pub use consent::{platform_consent_file, UploadConsent};
+pub use doctor::{verify_installation, Diagnostics, SetupIssue};
+pub use format::{
+    platform_report_dir, ReportFormat, ReportNaming, ReportSerializer,
+};
+pub use ffi::ffi_boundary;
+#[cfg(feature = "http")]
+pub use http::HttpSink;
+pub use i18n::{register_language, Translation};
+pub use link::{normalize_url, IssueTracker};
+#[cfg(feature = "serde")]
+pub use load::LoadError;
+pub use nested::NestedPanic;
+pub use observe::{HookEvent, HookStage};
+pub use origin::ForeignPanics;
+pub use output::MessageOutput;
+pub use plugin::{register_plugin, unregister_plugin, Plugin};
+pub use profile::{profile, set_profile};
+pub use report::Report;
+pub use rollup::{read_rollup, Occurrences};
+#[cfg(feature = "screenshot")]
+pub use screenshot::ask_screenshot_consent;
+#[cfg(feature = "sentry")]
+pub use sentry_sink::SentrySink;
+pub use session::session_id;
+pub use shell::shell_quote;
+pub use sink::{flush_pending_reports, FileSink, ReportSink, WriterSink};
+#[cfg(feature = "state")]
+pub use state::{
+    register_section_serializer, SectionSerializer, StateProvider,
+};
+pub use symbols::SymbolMap;
+pub use theme::{Color, Style, Theme};
+
+pub use log;
 pub use simplelog;
 
+/// The message of the panic `cargo human-panic test` injects right after
+/// the hook is installed
+#[doc(hidden)]
+pub const INJECTED_PANIC: &str =
+    "panic injected by `cargo human-panic test` to check the crash path";
+
 /// A convenient metadata struct that describes a crate
+///
+/// Build it with [`metadata!`] for the calling crate, or with
+/// [`Metadata::new`] and the setters. More fields are added over time, so
+/// it can't be built with a struct literal.
+#[derive(Clone)]
+#[non_exhaustive]
 pub struct Metadata {
     /// The crate version
     pub version: Cow<'static, str>,
     /// The crate name
     pub name: Cow<'static, str>,
-    /// The list of authors of the crate
+    /// The authors of the crate, separated by `:` like in
+    /// `CARGO_PKG_AUTHORS`, see [`split_authors`]
     pub authors: Cow<'static, str>,
     /// The URL of the crate's website
     pub homepage: Cow<'static, str>,
+    /// The URL of the crate's source repository
+    pub repository: Cow<'static, str>,
+    /// The commit the crate was built from
+    pub commit: Cow<'static, str>,
+    /// The release channel the crate was built for
+    pub channel: Channel,
+    /// Identifies the build, so the symbol mapping of obfuscated builds can
+    /// be found again
+    pub build_id: Cow<'static, str>,
+    /// The address users can email crash reports to
+    pub support_email: Cow<'static, str>,
+    /// Where the crate's issues are filed
+    pub issue_tracker: IssueTracker,
+}
+
+/// Options for how the crash message is presented
+///
+/// These are set at setup by appending `; field = value` pairs to
+/// [`setup_panic_logger!`].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Settings {
+    /// The look of the crash message
+    pub theme: Theme,
+    /// Print the message as short, plain sentences with the key line
+    /// prefixed by `ERROR:`, for screen readers and braille terminals
+    ///
+    /// Users can also turn this on by setting the `HUMAN_PANIC_ACCESSIBLE`
+    /// environment variable.
+    pub accessible: bool,
+    /// Replaces the sentence telling users where to send the crash log,
+    /// e.g. `"Please email the log to support@example.com with the subject
+    /// \"{subject}\"."`
+    ///
+    /// `{subject}` is replaced with [`Metadata::report_subject`].
+    pub call_to_action: Option<Cow<'static, str>>,
+    /// How the authors are listed in the crash message
+    pub authors: AuthorsDisplay,
+    /// Also print the path of the report file quoted for the shell, see
+    /// [`shell_quote`], so it can be pasted into commands as is
+    pub shell_path: bool,
+    /// The format report files are written in
+    pub report_format: ReportFormat,
+    /// Where the crash message goes, e.g. nowhere but stdout in a language
+    /// server
+    pub output: MessageOutput,
+    /// Explain well-known panic messages of the standard library in plain
+    /// words in the crash message, e.g. "tried to access item 10 of a list
+    /// with 3 items"
+    ///
+    /// Reports and the log keep the panic message as it was.
+    pub explain_panics: bool,
+    /// Also print the whole report after the message, for users who can't
+    /// get at files, e.g. on kiosks or in remote shells
+    ///
+    /// Users can also turn this on by setting the `HUMAN_PANIC_SHOW_REPORT`
+    /// environment variable.
+    pub show_report: bool,
+    /// Replaces the whole crash message, to give it the application's own
+    /// voice, e.g. `"{name} {version} crashed. Please send \"{report_path}\"
+    /// to {support_email}."`
+    ///
+    /// Placeholders are replaced with the [`Metadata`] fields `{name}`,
+    /// `{version}`, `{channel}`, `{authors}`, `{homepage}`, `{repository}` and
+    /// `{support_email}`, the `{report_path}` (or the log file's, if no
+    /// report was written), the `{log_path}`, the `{subject}` and
+    /// `{call_to_action}`, and the `{explanation}`, `{issue_url}` and
+    /// `{mail_url}`, which are empty if there is none. So are the report's
+    /// `{report_size}` and `{report_time}`, which are written like the
+    /// language of the message does. `{{` and `}}` stand for braces.
+    ///
+    /// [`Builder::install`] fails with
+    /// [`SetupError::InvalidTemplate`](crate::SetupError::InvalidTemplate)
+    /// if the template is malformed. The accessible message is kept, as
+    /// users who turned it on need its plain sentences.
+    ///
+    /// To change the template without a rebuild, see
+    /// [`Builder::message_template_file`].
+    pub message_template: Option<Cow<'static, str>>,
+    /// The language of the crash message, e.g. `"de"` or `"pt-BR"`, rather
+    /// than the user's, see [`Translation`]
+    ///
+    /// Set it to `"en"` to keep the message in English.
+    pub language: Option<Cow<'static, str>>,
+    /// The project's bug report template, e.g. its
+    /// `.github/ISSUE_TEMPLATE/bug_report.md`, which the body of the
+    /// [issue link](Metadata::issue_url) and Markdown report files follow
+    ///
+    /// `{crash_report}`, which the template must name, is replaced with
+    /// what would be there without a template. `{title}` is the summary of
+    /// the crash, `{cause}` the panic message, and `{name}` and
+    /// `{version}` are those of [`Metadata`]. Sections for users to fill
+    /// in, like the steps to reproduce, are kept as they are. So is front
+    /// matter, which is left out. `{{` and `}}` stand for braces.
+    ///
+    /// [`Builder::install`] fails with
+    /// [`SetupError::InvalidTemplate`](crate::SetupError::InvalidTemplate)
+    /// if the template is malformed.
+    pub issue_template: Option<Cow<'static, str>>,
+}
+
+/// The placeholders of [`Settings::message_template`]
+const MESSAGE_PLACEHOLDERS: [&str; 16] = [
+    "name",
+    "version",
+    "channel",
+    "authors",
+    "homepage",
+    "repository",
+    "support_email",
+    "report_path",
+    "report_size",
+    "report_time",
+    "log_path",
+    "subject",
+    "call_to_action",
+    "explanation",
+    "issue_url",
+    "mail_url",
+];
+
+impl Settings {
+    /// Check that the message and issue templates are well-formed
+    pub(crate) fn check(&self) -> Result<(), SetupError> {
+        let invalid = |template: &str, reason| SetupError::InvalidTemplate {
+            template: template.to_string(),
+            reason,
+        };
+        if let Some(template) = &self.message_template {
+            template::check(template, &MESSAGE_PLACEHOLDERS)
+                .map_err(|reason| invalid(template, reason))?;
+        }
+        if let Some(template) = &self.issue_template {
+            template::check(template, &format::ISSUE_PLACEHOLDERS)
+                .map_err(|reason| invalid(template, reason))?;
+            if !template::uses(template, "crash_report") {
+                let reason = "the crash report has no place in it, add \
+                              {crash_report}";
+                return Err(invalid(template, reason.into()));
+            }
+        }
+        Ok(())
+    }
+
+    /// The serializer for report files of [`report_format`], following
+    /// the [`issue_template`] for Markdown
+    ///
+    /// [`report_format`]: Settings#structfield.report_format
+    /// [`issue_template`]: Settings#structfield.issue_template
+    pub(crate) fn report_serializer(&self) -> Box<dyn ReportSerializer> {
+        match (self.report_format, &self.issue_template) {
+            (ReportFormat::Markdown, Some(template)) => {
+                Box::new(format::IssueTemplate(template.to_string()))
+            }
+            (format, _) => Box::new(format),
+        }
+    }
+
+    /// Use the message template in `file`, keeping the one set if it can't
+    /// be read or is malformed
+    pub(crate) fn load_message_template(
+        &mut self,
+        file: &Path,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let template = std::fs::read_to_string(long_path::for_io(file))?;
+        // editors on Windows like to start UTF-8 files with a byte order mark
+        let template = template.strip_prefix('\u{FEFF}').unwrap_or(&template);
+        template::check(template, &MESSAGE_PLACEHOLDERS)?;
+        self.message_template = Some(template.to_string().into());
+        Ok(())
+    }
+
+    /// The call to action sentence with its placeholders filled in, in the
+    /// language of `text` unless the application set its own
+    fn call_to_action(&self, meta: &Metadata, text: &Messages) -> String {
+        let subject = meta.report_subject();
+        match &self.call_to_action {
+            Some(call_to_action) => {
+                let subject = match template::is_rtl(call_to_action) {
+                    true => template::isolate(&subject),
+                    false => subject,
+                };
+                call_to_action.replace("{subject}", &subject)
+            }
+            None => text.text("call-to-action", &[("subject", &subject)]),
+        }
+    }
+
+    /// These settings with the call to action sending users to the
+    /// authors of `plugin` instead
+    fn for_plugin(&self, plugin: &Plugin) -> Settings {
+        let text = Messages::new(self.language.as_deref());
+        let name = ("name", plugin.name.as_str());
+        let version = ("version", plugin.version.as_str());
+        let call_to_action = match &plugin.issues {
+            Some(issues) => text.text(
+                "plugin-issues",
+                &[name, version, ("url", issues.as_str())],
+            ),
+            None => text.text("plugin-authors", &[name, version]),
+        };
+        Settings {
+            call_to_action: Some(call_to_action.into()),
+            ..self.clone()
+        }
+    }
+
+    fn accessible(&self) -> bool {
+        self.accessible
+            || std::env::var_os("HUMAN_PANIC_ACCESSIBLE")
+                .is_some_and(|v| !v.is_empty() && v != "0")
+    }
+
+    /// Whether to print the whole report after the message
+    pub(crate) fn show_report(&self) -> bool {
+        self.show_report
+            || std::env::var_os("HUMAN_PANIC_SHOW_REPORT")
+                .is_some_and(|v| !v.is_empty() && v != "0")
+    }
+}
+
+/// The release channel a build was made for
+///
+/// Crash reports are tagged with the channel so stable-channel crashes can
+/// be told apart from those of beta, nightly or internal builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Channel {
+    /// A regular release
+    #[default]
+    Stable,
+    /// A pre-release build
+    Beta,
+    /// A nightly build
+    Nightly,
+    /// An internal or dogfood build
+    Internal,
+}
+
+impl Channel {
+    /// The lowercase name of the channel
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Channel::Stable => "stable",
+            Channel::Beta => "beta",
+            Channel::Nightly => "nightly",
+            Channel::Internal => "internal",
+        }
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Channel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "stable" => Ok(Channel::Stable),
+            "beta" => Ok(Channel::Beta),
+            "nightly" => Ok(Channel::Nightly),
+            "internal" => Ok(Channel::Internal),
+            other => Err(format!("unknown release channel '{}'", other)),
+        }
+    }
+}
+
+impl Metadata {
+    /// Metadata for the crate `name` at `version`, with everything else
+    /// left empty
+    ///
+    /// Both take anything that converts into a `Cow<'static, str>`, so
+    /// values computed at runtime work as well as literals:
+    ///
+    /// ```
+    /// use human_panic_logger::Metadata;
+    ///
+    /// let version = format!("{}+{}", "1.4.0", "deb12");
+    /// let meta = Metadata::new("app", version)
+    ///     .authors("Jane Doe <jane@example.com>")
+    ///     .repository(String::from("https://github.com/org/app"));
+    ///
+    /// assert_eq!(meta.version, "1.4.0+deb12");
+    /// ```
+    pub fn new(
+        name: impl Into<Cow<'static, str>>,
+        version: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        Metadata {
+            version: version.into(),
+            name: name.into(),
+            authors: "".into(),
+            homepage: "".into(),
+            repository: "".into(),
+            commit: "".into(),
+            channel: Channel::default(),
+            build_id: "".into(),
+            support_email: "".into(),
+            issue_tracker: IssueTracker::default(),
+        }
+    }
+
+    /// Set the authors, separated by `:`, see [`Metadata::authors`]
+    pub fn authors(mut self, authors: impl Into<Cow<'static, str>>) -> Self {
+        self.authors = authors.into();
+        self
+    }
+
+    /// Set the URL of the crate's website
+    pub fn homepage(mut self, homepage: impl Into<Cow<'static, str>>) -> Self {
+        self.homepage = homepage.into();
+        self
+    }
+
+    /// Set the URL of the crate's source repository
+    pub fn repository(
+        mut self,
+        repository: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.repository = repository.into();
+        self
+    }
+
+    /// Set the commit the crate was built from
+    pub fn commit(mut self, commit: impl Into<Cow<'static, str>>) -> Self {
+        self.commit = commit.into();
+        self
+    }
+
+    /// Set the release channel the crate was built for
+    pub fn channel(mut self, channel: Channel) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    /// Set the id identifying the build
+    pub fn build_id(mut self, build_id: impl Into<Cow<'static, str>>) -> Self {
+        self.build_id = build_id.into();
+        self
+    }
+
+    /// Set the address users can email crash reports to
+    pub fn support_email(
+        mut self,
+        support_email: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.support_email = support_email.into();
+        self
+    }
+
+    /// Set where the crate's issues are filed
+    pub fn issue_tracker(mut self, issue_tracker: IssueTracker) -> Self {
+        self.issue_tracker = issue_tracker;
+        self
+    }
+
+    /// Link to a line of source code at the commit the crate was built from
+    ///
+    /// Returns `None` if either `repository` or `commit` is unknown, or if
+    /// `file` lies outside of the repository (e.g. a dependency).
+    ///
+    /// ```
+    /// use human_panic_logger::Metadata;
+    ///
+    /// let meta = Metadata::new("app", "1.0.0")
+    ///     .repository("https://github.com/org/app")
+    ///     .commit("8f3e2c1");
+    ///
+    /// assert_eq!(
+    ///     meta.permalink("src/main.rs", 12).unwrap(),
+    ///     "https://github.com/org/app/blob/8f3e2c1/src/main.rs#L12"
+    /// );
+    /// ```
+    pub fn permalink(&self, file: &str, line: u32) -> Option<String> {
+        if self.repository.is_empty()
+            || self.commit.is_empty()
+            || is_absolute_path(file)
+        {
+            return None;
+        }
+
+        let repository = self.repository.trim_end_matches('/');
+        let repository = repository.trim_end_matches(".git");
+        Some(format!(
+            "{}/blob/{}/{}#L{}",
+            repository,
+            self.commit,
+            file.replace('\\', "/"),
+            line
+        ))
+    }
+
+    /// The URL of a new issue in the crate's
+    /// [issue tracker](Metadata::issue_tracker), with `title` and `body`
+    /// filled in
+    ///
+    /// Returns `None` if the tracker isn't known, e.g. if the repository
+    /// isn't on GitHub, GitLab or Bitbucket and no tracker is set.
+    ///
+    /// ```
+    /// use human_panic_logger::{IssueTracker, Metadata};
+    ///
+    /// let meta = Metadata::new("app", "1.0.0")
+    ///     .repository("https://github.com/org/app.git");
+    ///
+    /// assert_eq!(
+    ///     meta.issue_url("app crashed", "see *report*").unwrap(),
+    ///     "https://github.com/org/app/issues/new\
+    ///      ?title=app%20crashed&body=see%20%2Areport%2A"
+    /// );
+    ///
+    /// let gitlab = Metadata::new("app", "1.0.0")
+    ///     .repository("https://git.example.com/group/tools/app")
+    ///     .issue_tracker(IssueTracker::GitLab);
+    /// assert_eq!(
+    ///     gitlab.issue_url("app crashed", "").unwrap(),
+    ///     "https://git.example.com/group/tools/app/-/issues/new\
+    ///      ?issue%5Btitle%5D=app%20crashed&issue%5Bdescription%5D="
+    /// );
+    /// ```
+    pub fn issue_url(&self, title: &str, body: &str) -> Option<String> {
+        self.issue_tracker.new_issue(&self.repository, title, body)
+    }
+
+    /// A `mailto:` URL for an email to the support address with the
+    /// [subject](Metadata::report_subject) and `body` filled in
+    ///
+    /// Returns `None` without a plausible support address.
+    ///
+    /// ```
+    /// use human_panic_logger::Metadata;
+    ///
+    /// let meta = Metadata::new("app", "1.0.0")
+    ///     .support_email("support@example.com");
+    ///
+    /// assert_eq!(
+    ///     meta.mailto("app crashed").unwrap(),
+    ///     "mailto:support@example.com\
+    ///      ?subject=app%20%28stable%29%20Crash%20Report&body=app%20crashed"
+    /// );
+    /// ```
+    pub fn mailto(&self, body: &str) -> Option<String> {
+        link::mailto(&self.support_email, &self.report_subject(), body)
+    }
+
+    /// The subject line users are asked to file crash reports under
+    ///
+    /// It carries the release channel so that triage can tell stable
+    /// crashes apart from those of pre-release builds.
+    pub fn report_subject(&self) -> String {
+        format!("{} ({}) Crash Report", self.name, self.channel)
+    }
+}
+
+/// Whether `file` is an absolute path on any platform
+///
+/// Source paths are recorded on the machine that compiled the crate, which
+/// isn't necessarily the one running it, so `Path::is_absolute` alone
+/// doesn't do when cross compiling.
+fn is_absolute_path(file: &str) -> bool {
+    let bytes = file.as_bytes();
+    let has_drive = bytes.len() >= 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':';
+
+    Path::new(file).is_absolute()
+        || file.starts_with('/')
+        || file.starts_with('\\')
+        || has_drive
 }
 
 /// If in debug mode, sends first param to function. If in release mode, sends 2nd param to function
@@ -78,208 +675,640 @@ macro_rules! debug_param {
     };
 }
 
+/// [`Metadata`] of the calling crate, read from Cargo's environment at
+/// compile time
+///
+/// The values are taken where the macro is expanded, so this describes the
+/// crate calling it rather than `human-panic-logger`. See
+/// [`setup_panic_logger!`] for the `HUMAN_PANIC_*` variables that fill in the
+/// commit, release channel and build id.
+///
+/// ```
+/// let meta = human_panic_logger::metadata!();
+///
+/// assert_eq!(meta.name, env!("CARGO_PKG_NAME"));
+/// ```
+#[macro_export]
+macro_rules! metadata {
+    () => {
+        $crate::Metadata::new(
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+        )
+        .authors(env!("CARGO_PKG_AUTHORS"))
+        .homepage(env!("CARGO_PKG_HOMEPAGE"))
+        .repository(env!("CARGO_PKG_REPOSITORY"))
+        .commit(option_env!("HUMAN_PANIC_COMMIT").unwrap_or(""))
+        .channel(
+            option_env!("HUMAN_PANIC_CHANNEL")
+                .and_then(|c| c.parse().ok())
+                .unwrap_or_default(),
+        )
+        .build_id(option_env!("HUMAN_PANIC_BUILD_ID").unwrap_or(""))
+        .support_email(option_env!("HUMAN_PANIC_SUPPORT_EMAIL").unwrap_or(""))
+        .issue_tracker(
+            option_env!("HUMAN_PANIC_ISSUE_TRACKER")
+                .and_then(|t| t.parse().ok())
+                .unwrap_or_default(),
+        )
+    };
+}
+
 /// `human-panic-logger` initialisation macro
 ///
 /// Call this with your log file location
 ///
+/// The repository link in crash messages uses `CARGO_PKG_REPOSITORY` together
+/// with the commit hash found in the `HUMAN_PANIC_COMMIT` environment variable
+/// at compile time, which a build script can set with
+/// `cargo:rustc-env=HUMAN_PANIC_COMMIT=<sha>`. Likewise, the release channel
+/// is read from `HUMAN_PANIC_CHANNEL` (`stable`, `beta`, `nightly` or
+/// `internal`) and defaults to `stable`. Builds with obfuscated symbols
+/// should set `HUMAN_PANIC_BUILD_ID`, which is recorded in every report so
+/// the matching [`SymbolMap`] can be applied later. If
+/// `HUMAN_PANIC_SUPPORT_EMAIL` is set, the crash message offers a prefilled
+/// email to that address, and `HUMAN_PANIC_ISSUE_TRACKER` picks where the
+/// link to a new issue goes, see [`IssueTracker`].
+///
 /// ```
 /// use human_panic_logger::setup_panic_logger;
 ///
+/// # // keep the log and session files out of the working tree
+/// # std::env::set_current_dir(std::env::temp_dir()).unwrap();
 /// setup_panic_logger!("myloglocation.log");
 /// ```
+///
+/// To configure the hook at runtime instead, use [`Builder`].
+///
+/// Any [`Settings`] can be changed by appending `; field = value` pairs:
+///
+/// ```no_run
+/// use human_panic_logger::{setup_panic_logger, Theme};
+///
+/// setup_panic_logger!("myloglocation.log"; theme = Theme::HIGH_CONTRAST);
+/// ```
+#[cfg(not(feature = "disabled"))]
 #[macro_export]
 macro_rules! setup_panic_logger {
-    ($log_file:expr $(, $opt:expr)*) => {
-        use std::panic::{self, PanicInfo};
-        use std::fs::OpenOptions;
-        use $crate::{format_panic, print_msg, Metadata, debug_param};
+    ($log_file:expr $(, $opt:expr)* $(; $key:ident = $value:expr)* $(;)?) => {{
+        #[allow(unused_imports)]
         use $crate::simplelog::*;
 
-        CombinedLogger::init(
-            vec![
-                WriteLogger::new(
-                    debug_param!(LevelFilter::Debug, LevelFilter::Info),
-                    Config::default(),
-                    OpenOptions::new()
-                        .read(true)
-                        .append(true)
-                        .create(true)
-                        .open(&$log_file).unwrap()),
-                $($opt),*
-            ]
-        ).unwrap();
-
-        let meta = $crate::Metadata {
-            version: env!("CARGO_PKG_VERSION").into(),
-            name: env!("CARGO_PKG_NAME").into(),
-            authors: env!("CARGO_PKG_AUTHORS").replace(":", ", ").into(),
-            homepage: env!("CARGO_PKG_HOMEPAGE").into(),
-        };
+        #[allow(unused_mut)]
+        let mut settings = $crate::Settings::default();
+        $(settings.$key = $value;)*
 
-        let default_hook = panic::take_hook();
+        $crate::Builder::new($crate::metadata!(), &$log_file)
+            $(.logger($opt))*
+            .settings(settings)
+            .debug(cfg!(debug_assertions))
+            .install()
+            .unwrap();
+    }};
+}
 
-        if let Err(_) = ::std::env::var("RUST_BACKTRACE") {
-            panic::set_hook(Box::new(move |info: &panic::PanicInfo| {
-                // call standard hook in debug mode
-                #[cfg(debug_assertions)]
-                default_hook(info);
+/// `human-panic-logger` initialisation macro, which expands to nothing
+/// with the `disabled` feature
+#[cfg(feature = "disabled")]
+#[macro_export]
+macro_rules! setup_panic_logger {
+    ($log_file:expr $(, $opt:expr)* $(; $key:ident = $value:expr)* $(;)?) => {{
+        // never called, so no code is generated, but the arguments still
+        // count as used
+        let _ = || (&$log_file, $($opt,)* $($value,)*);
+    }};
+}
 
-                // output panic to logfile
-                error!("Panic! :: {}", format_panic(info));
+/// Hand a report over to the operating system's crash tooling
+///
+/// This does nothing unless the `distro-report` (Linux) or
+/// `macos-crash-report` (macOS) feature is enabled, see the `distro` and
+/// `macos` modules.
+#[cfg_attr(
+    not(any(
+        all(feature = "distro-report", target_os = "linux"),
+        all(feature = "macos-crash-report", target_os = "macos")
+    )),
+    allow(unused_variables)
+)]
+pub fn notify_system(report: &Report) {
+    #[cfg(all(feature = "distro-report", target_os = "linux"))]
+    distro::submit(report);
+    #[cfg(all(feature = "macos-crash-report", target_os = "macos"))]
+    macos::submit(report);
+}
 
-                // do human error message in release mode
-                #[cfg(not(debug_assertions))]
-                $crate::print_msg(&$log_file, &meta)
-                    .expect("human-panic-logger: printing error message to console failed");
-            }));
-        }
-    };
+/// Open the log file for appending, creating it if needed
+///
+/// On WASI the file can only be opened inside a directory the runtime
+/// preopened for us. If it isn't, the log goes to stdout instead so the
+/// crash details aren't lost.
+pub fn open_log_file<P: AsRef<Path>>(
+    path: P,
+) -> IoResult<Box<dyn Write + Send>> {
+    open_log_file_with(path.as_ref(), permissions::Permissions::default())
+}
+
+/// Open the log file like [`open_log_file`], creating it with
+/// `permissions`
+pub(crate) fn open_log_file_with(
+    path: &Path,
+    permissions: permissions::Permissions,
+) -> IoResult<Box<dyn Write + Send>> {
+    let file = permissions
+        .apply(OpenOptions::new().read(true).append(true).create(true))
+        .open(long_path::for_io(path));
+
+    match file {
+        Ok(file) => Ok(Box::new(file)),
+        #[cfg(target_os = "wasi")]
+        Err(_) => Ok(Box::new(std::io::stdout())),
+        #[cfg(not(target_os = "wasi"))]
+        Err(e) => Err(e),
+    }
 }
 
 /// Utility function that prints a message to our human users
+///
+/// `file_path` is the log file. If the crash was also written to a report
+/// file of its own, users are pointed to `report_file` instead.
 pub fn print_msg<P: AsRef<Path>>(
     file_path: P,
+    report_file: Option<&Path>,
+    meta: &Metadata,
+    location: Option<&Location<'_>>,
+    settings: &Settings,
+) -> IoResult<()> {
+    let links = MessageDetails::default();
+    print_msg_with(file_path, report_file, meta, location, settings, &links)
+}
+
+/// Details of the crash offered in the crash message
+#[derive(Debug, Default)]
+pub(crate) struct MessageDetails {
+    /// The panic message in plain words, see [`Settings::explain_panics`]
+    pub(crate) explanation: Option<String>,
+    /// A new issue, see [`Metadata::issue_url`]
+    pub(crate) issue: Option<String>,
+    /// An email to the support address, see [`Metadata::mailto`]
+    pub(crate) mail: Option<String>,
+    /// The whole report, see [`Settings::show_report`]
+    pub(crate) report: Option<String>,
+}
+
+/// Print the message like [`print_msg`], with `links` for reporting the
+/// crash and what went wrong in plain words
+pub(crate) fn print_msg_with<P: AsRef<Path>>(
+    file_path: P,
+    report_file: Option<&Path>,
     meta: &Metadata,
+    location: Option<&Location<'_>>,
+    settings: &Settings,
+    links: &MessageDetails,
 ) -> IoResult<()> {
+    match settings.output {
+        MessageOutput::Stderr => {}
+        MessageOutput::LspNotification => {
+            let file = report_file.unwrap_or(file_path.as_ref());
+            let mut message =
+                format!("{} had a problem and crashed.", meta.name);
+            if let Some(explanation) = &links.explanation {
+                message
+                    .push_str(&format!(" What went wrong: {}.", explanation));
+            }
+            message.push_str(&format!(
+                " The details are in \"{}\".",
+                long_path::for_display(file).display()
+            ));
+            if let Some(issue_url) = &links.issue {
+                message.push_str(&format!(" File an issue at {}", issue_url));
+            }
+            if let Some(mailto) = &links.mail {
+                message.push_str(&format!(" Email us at {}", mailto));
+            }
+            return output::show_message(&message);
+        }
+        MessageOutput::Silent => return Ok(()),
+    }
+
     let (_version, name, authors, homepage) =
         (&meta.version, &meta.name, &meta.authors, &meta.homepage);
+    let theme = settings.theme;
+    let br = theme.paragraph_break();
 
-    let stderr = BufferWriter::stderr(ColorChoice::Auto);
+    if !stderr_usable(file_path.as_ref()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotConnected,
+            "stderr is closed",
+        ));
+    }
+
+    // WASI runtimes pass output through without a terminal to talk to
+    let color = if cfg!(target_os = "wasi") {
+        ColorChoice::Never
+    } else {
+        ColorChoice::Auto
+    };
+    let stderr = BufferWriter::stderr(color);
     let mut buffer = stderr.buffer();
-    buffer.set_color(ColorSpec::new().set_fg(Some(Color::White)))?;
-
-    writeln!(&mut buffer, "Well, this is embarrassing.\n")?;
-    writeln!(
-        &mut buffer,
-        "{} had a problem and crashed. To help us diagnose the \
-     problem you can send us a crash report.\n",
-        name
-    )?;
-    writeln!(
-        &mut buffer,
-        "There is a log file of the crash at \"{}\". Please submit an \
-     issue or email with the subject of \"{} Crash Report\" and include the \
-     log as an attachment.\n",
-        file_path.as_ref().display(),
-        name
-    )?;
-
-    if !homepage.is_empty() {
-        writeln!(&mut buffer, "- Homepage: {}", homepage)?;
-    }
-    if !authors.is_empty() {
-        writeln!(&mut buffer, "- Authors: {}", authors)?;
-    }
-    writeln!(
-        &mut buffer,
-        "\nWe take privacy seriously, and do not perform any \
-     automated error collection. In order to improve the software, we rely on \
-     people to submit reports.\n"
-    )?;
-    writeln!(&mut buffer, "Thank you!")?;
+
+    if settings.accessible() {
+        write_accessible_msg(
+            &mut buffer,
+            file_path.as_ref(),
+            report_file,
+            meta,
+            location,
+            settings,
+            links,
+        )?;
+        buffer.reset()?;
+        if let Some(report) = &links.report {
+            write_report(&mut buffer, report)?;
+        }
+        return print_buffer(&stderr, &buffer);
+    }
+
+    if let Some(template) = &settings.message_template {
+        let message = render_message(
+            template,
+            file_path.as_ref(),
+            report_file,
+            meta,
+            settings,
+            links,
+        );
+        // a malformed template was reported at setup, so just fall back
+        if let Ok(message) = message {
+            buffer.set_color(&theme.body.spec())?;
+            writeln!(&mut buffer, "{}", message.trim_end())?;
+            buffer.reset()?;
+            if let Some(report) = &links.report {
+                write_report(&mut buffer, report)?;
+            }
+            return print_buffer(&stderr, &buffer);
+        }
+    }
+
+    let text = Messages::new(settings.language.as_deref());
+    let call_to_action = settings.call_to_action(meta, &text);
+    buffer.set_color(&theme.heading.spec())?;
+    writeln!(&mut buffer, "{}{}", text.text("heading", &[]), br)?;
+    buffer.set_color(&theme.error.spec())?;
+    writeln!(&mut buffer, "{}{}", text.text("crashed", &[("name", name)]), br)?;
+    buffer.set_color(&theme.body.spec())?;
+    if let Some(explanation) = &links.explanation {
+        let explanation =
+            text.text("explanation", &[("explanation", explanation)]);
+        writeln!(&mut buffer, "{}{}", explanation, br)?;
+    }
+    if let Some(report_file) = report_file {
+        let shown = long_path::for_display(report_file);
+        write_highlighted(
+            &mut buffer,
+            theme,
+            text.split("report-file", &[], "path"),
+            &shown.display().to_string(),
+        )?;
+        if let Some((size, time)) = size_and_time(report_file, &text) {
+            let values = [("size", size.as_str()), ("time", time.as_str())];
+            let details = text.text("report-details", &values);
+            write!(&mut buffer, " {}", details)?;
+        }
+        writeln!(&mut buffer, " {}{}", call_to_action, br)?;
+        if settings.shell_path {
+            write_highlighted(
+                &mut buffer,
+                theme,
+                text.split("shell-path", &[], "path"),
+                &shell_quote(report_file),
+            )?;
+            writeln!(&mut buffer, "{}", br)?;
+        }
+    } else if !cfg!(target_os = "wasi") || file_path.as_ref().exists() {
+        let shown = long_path::for_display(file_path.as_ref());
+        write_highlighted(
+            &mut buffer,
+            theme,
+            text.split("log-file", &[], "path"),
+            &shown.display().to_string(),
+        )?;
+        writeln!(&mut buffer, " {}{}", call_to_action, br)?;
+    } else {
+        let shown = long_path::for_display(file_path.as_ref());
+        let path = shown.display().to_string();
+        let on_stdout = text.text("log-on-stdout", &[("path", &path)]);
+        writeln!(&mut buffer, "{} {}{}", on_stdout, call_to_action, br)?;
+    }
+
+    if let Some(issue_url) = &links.issue {
+        let split = text.split("issue-link", &[], "url");
+        write_highlighted(&mut buffer, theme, split, issue_url)?;
+        writeln!(&mut buffer, "{}", br)?;
+    }
+    if let Some(mailto) = &links.mail {
+        let split = text.split("mail-link", &[], "url");
+        write_highlighted(&mut buffer, theme, split, mailto)?;
+        writeln!(&mut buffer, "{}", br)?;
+    }
+
+    if let Some(homepage) = normalize_url(homepage) {
+        let homepage = text.text("homepage", &[("url", &homepage)]);
+        writeln!(&mut buffer, "- {}", homepage)?;
+    }
+    if let Some(repository) = repository_link(meta) {
+        let repository = text.text("repository", &[("url", &repository)]);
+        writeln!(&mut buffer, "- {}", repository)?;
+    }
+    write_authors(&mut buffer, "- ", authors, &settings.authors, &text)?;
+    if let Some(permalink) =
+        location.and_then(|l| meta.permalink(l.file(), l.line()))
+    {
+        let source = text.text("source", &[("url", &permalink)]);
+        writeln!(&mut buffer, "- {}", source)?;
+    }
+    writeln!(&mut buffer, "{}{}{}", br, text.text("privacy", &[]), br)?;
+    writeln!(&mut buffer, "{}", text.text("thanks", &[]))?;
 
     buffer.reset()?;
+    if let Some(report) = &links.report {
+        write_report(&mut buffer, report)?;
+    }
 
-    stderr.print(&buffer).unwrap();
-    Ok(())
+    print_buffer(&stderr, &buffer)
 }
 
-/// Format the panic message for printing to log
-pub fn format_panic(panic_info: &PanicInfo) -> String {
-    let mut expl = String::new();
-
-    #[cfg(feature = "nightly")]
-        let message = panic_info.message().map(|m| format!("{}", m));
-
-    #[cfg(not(feature = "nightly"))]
-        let message = match (
-        panic_info.payload().downcast_ref::<&str>(),
-        panic_info.payload().downcast_ref::<String>(),
-    ) {
-        (Some(s), _) => Some(s.to_string()),
-        (_, Some(s)) => Some(s.to_string()),
-        (None, None) => None,
+/// The size of `file` and when it was written, in the language of `text`
+fn size_and_time(file: &Path, text: &Messages) -> Option<(String, String)> {
+    let meta = std::fs::metadata(long_path::for_io(file)).ok()?;
+    let time = clock::DateTime::from(meta.modified().ok()?);
+    Some((text.size(meta.len()), text.date_time(time)))
+}
+
+/// The crash message of `template`, see [`Settings::message_template`]
+fn render_message(
+    template: &str,
+    file_path: &Path,
+    report_file: Option<&Path>,
+    meta: &Metadata,
+    settings: &Settings,
+    links: &MessageDetails,
+) -> Result<String, String> {
+    let text = Messages::new(settings.language.as_deref());
+    let path = |path: &Path| {
+        long_path::for_display(path).display().to_string()
+    };
+    let (size, time) = report_file
+        .and_then(|file| size_and_time(file, &text))
+        .unwrap_or_default();
+    template::render_isolated(template, |name| {
+        Some(match name {
+            "name" => meta.name.to_string(),
+            "version" => meta.version.to_string(),
+            "channel" => meta.channel.to_string(),
+            "authors" => join_authors(&meta.authors),
+            "homepage" => meta.homepage.to_string(),
+            "repository" => meta.repository.to_string(),
+            "support_email" => meta.support_email.to_string(),
+            "report_path" => path(report_file.unwrap_or(file_path)),
+            "report_size" => size.clone(),
+            "report_time" => time.clone(),
+            "log_path" => path(file_path),
+            "subject" => meta.report_subject(),
+            "call_to_action" => settings.call_to_action(meta, &text),
+            "explanation" => links.explanation.clone().unwrap_or_default(),
+            "issue_url" => links.issue.clone().unwrap_or_default(),
+            "mail_url" => links.mail.clone().unwrap_or_default(),
+            _ => return None,
+        })
+    })
+}
+
+/// Write `highlighted` between the two parts of a message split around it,
+/// in the highlight color of `theme`
+fn write_highlighted<W: WriteColor>(
+    out: &mut W,
+    theme: Theme,
+    (before, after): (String, String),
+    highlighted: &str,
+) -> IoResult<()> {
+    write!(out, "{}", before)?;
+    out.set_color(&theme.highlight.spec())?;
+    write!(out, "{}", highlighted)?;
+    out.set_color(&theme.body.spec())?;
+    write!(out, "{}", after)
+}
+
+/// The whole report, set apart from the message above it
+fn write_report<W: Write>(out: &mut W, report: &str) -> IoResult<()> {
+    writeln!(out)?;
+    writeln!(out, "---- Crash report ----")?;
+    writeln!(out)?;
+    out.write_all(report.as_bytes())?;
+    if !report.ends_with('\n') {
+        writeln!(out)?;
+    }
+    writeln!(out, "---- End of crash report ----")
+}
+
+/// Tell users that the report saved at `saved` wasn't uploaded, after the
+/// crash message
+pub(crate) fn print_upload_failure(
+    delivery: sink::Delivery,
+    saved: &Path,
+    settings: &Settings,
+) -> IoResult<()> {
+    let id = match delivery {
+        sink::Delivery::Sent => return Ok(()),
+        sink::Delivery::Failed => "upload-failed",
+        sink::Delivery::Unreachable => "upload-unreachable",
     };
+    if settings.output != MessageOutput::Stderr || !stderr_usable(saved) {
+        return Ok(());
+    }
+    let text = Messages::new(settings.language.as_deref());
+    let path = long_path::for_display(saved).display().to_string();
+    let color = if cfg!(target_os = "wasi") {
+        ColorChoice::Never
+    } else {
+        ColorChoice::Auto
+    };
+    let stderr = BufferWriter::stderr(color);
+    let mut buffer = stderr.buffer();
+    buffer.set_color(&settings.theme.body.spec())?;
+    write!(&mut buffer, "{}", settings.theme.paragraph_break())?;
+    writeln!(&mut buffer, "{}", text.text(id, &[("path", &path)]))?;
+    buffer.reset()?;
+    print_buffer(&stderr, &buffer)
+}
+
+/// Print the finished message, not minding if whoever reads our stderr has
+/// already gone away (e.g. `app | head`): the crash is in the log either way
+fn print_buffer(stderr: &BufferWriter, buffer: &Buffer) -> IoResult<()> {
+    match stderr.print(buffer) {
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+/// Whether stderr can take the crash message
+///
+/// If fd 2 was closed, writing to it either fails or, worse, ends up in
+/// whatever file reused the descriptor, which may well be our own log file.
+#[cfg(unix)]
+fn stderr_usable(log_file: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
 
-    let cause = match message {
-        Some(m) => m,
-        None => "Unknown".into(),
+    // without /dev/fd there's no telling, so just try
+    if !Path::new("/dev/fd").is_dir() {
+        return true;
+    }
+    let stderr = match std::fs::metadata("/dev/fd/2") {
+        Ok(stderr) => stderr,
+        Err(_) => return false,
     };
+    match std::fs::metadata(log_file) {
+        Ok(log) => (log.dev(), log.ino()) != (stderr.dev(), stderr.ino()),
+        Err(_) => true,
+    }
+}
 
-    match panic_info.location() {
-        Some(location) => expl.push_str(&format!(
-            "Panic occurred in file '{}' at line {}\n",
-            location.file(),
-            location.line()
-        )),
-        None => expl.push_str("Panic location unknown.\n"),
-    }
-
-    format!("{}\n   {}\n{}", expl, cause, format_backtrace())
-}
-
-fn format_backtrace() -> String {
-    //We skip 3 frames from backtrace library
-    //Then we skip 3 frames for our own library
-    //(including closure that we set as hook)
-    //Then we skip 2 functions from Rust's runtime
-    //that calls panic hook
-    const SKIP_FRAMES_NUM: usize = 4;
-    //We take padding for address and extra two letters
-    //to padd after index.
-    const HEX_WIDTH: usize = mem::size_of::<usize>() + 2;
-    //Padding for next lines after frame's address
-    const NEXT_SYMBOL_PADDING: usize = HEX_WIDTH + 6;
-
-    let mut backtrace = String::new();
-
-    //Here we iterate over backtrace frames
-    //(each corresponds to function's stack)
-    //We need to print its address
-    //and symbol(e.g. function name),
-    //if it is available
-    for (idx, frame) in Backtrace::new()
-        .frames()
-        .iter()
-        .skip(SKIP_FRAMES_NUM)
-        .enumerate()
-    {
-        let ip = frame.ip();
-        let _ = write!(backtrace, "\n{:4}: {:2$?}", idx, ip, HEX_WIDTH);
+#[cfg(not(unix))]
+fn stderr_usable(_log_file: &Path) -> bool {
+    true
+}
 
-        let symbols = frame.symbols();
-        if symbols.is_empty() {
-            let _ = write!(backtrace, " - <unresolved>");
-            continue;
-        }
+/// List the authors (or the contact replacing them), one line each
+fn write_authors<W: Write>(
+    out: &mut W,
+    bullet: &str,
+    authors: &str,
+    display: &AuthorsDisplay,
+    text: &Messages,
+) -> IoResult<()> {
+    // long enough for a few names, short enough to not flood the message
+    const MAX_LINE_CHARS: usize = 200;
 
-        for (idx, symbol) in symbols.iter().enumerate() {
-            //Print symbols from this address,
-            //if there are several addresses
-            //we need to put it on next line
-            if idx != 0 {
-                let _ = write!(backtrace, "\n{:1$}", "", NEXT_SYMBOL_PADDING);
+    match display {
+        AuthorsDisplay::Inline => {
+            let authors = join_authors(authors);
+            if !authors.is_empty() {
+                let authors = link::truncate(&authors, MAX_LINE_CHARS);
+                let line = text.text("authors", &[("authors", &authors)]);
+                writeln!(out, "{}{}", bullet, line)?;
             }
-
-            if let Some(name) = symbol.name() {
-                let _ = write!(backtrace, " - {}", name);
-            } else {
-                let _ = write!(backtrace, " - <unknown>");
+        }
+        AuthorsDisplay::List => {
+            for author in split_authors(authors) {
+                let author = link::truncate(author, MAX_LINE_CHARS);
+                let line = text.text("author", &[("author", &author)]);
+                writeln!(out, "{}{}", bullet, line)?;
             }
+        }
+        AuthorsDisplay::Contact(contact) => {
+            let contact = link::truncate(contact, MAX_LINE_CHARS);
+            let line = text.text("contact", &[("contact", &contact)]);
+            writeln!(out, "{}{}", bullet, line)?;
+        }
+    }
+    Ok(())
+}
 
-            //See if there is debug information with file name and line
-            if let (Some(file), Some(line)) = (symbol.filename(), symbol.lineno()) {
-                let _ = write!(
-                    backtrace,
-                    "\n{:3$}at {}:{}",
-                    "",
-                    file.display(),
-                    line,
-                    NEXT_SYMBOL_PADDING
-                );
-            }
+/// The crash message in short, plain sentences for screen readers
+fn write_accessible_msg<W: WriteColor>(
+    out: &mut W,
+    file_path: &Path,
+    report_file: Option<&Path>,
+    meta: &Metadata,
+    location: Option<&Location<'_>>,
+    settings: &Settings,
+    links: &MessageDetails,
+) -> IoResult<()> {
+    // short, plain sentences don't translate as they are
+    let english = Messages::english();
+    out.set_color(&settings.theme.error.spec())?;
+    writeln!(out, "ERROR: {} crashed.", meta.name)?;
+    out.set_color(&settings.theme.body.spec())?;
+    if let Some(explanation) = &links.explanation {
+        writeln!(out, "What went wrong: {}.", explanation)?;
+    }
+    if let Some(report_file) = report_file {
+        let path = long_path::for_display(report_file);
+        let path = path.display();
+        writeln!(out, "A report of the crash is in: {}", path)?;
+        if settings.shell_path {
+            writeln!(out, "Quoted for a shell: {}", shell_quote(report_file))?;
         }
+    } else if !cfg!(target_os = "wasi") || file_path.exists() {
+        let path = long_path::for_display(file_path);
+        writeln!(out, "A log of the crash is in: {}", path.display())?;
+    } else {
+        writeln!(out, "The crash log was written to standard output.")?;
+    }
+    if settings.call_to_action.is_some() {
+        writeln!(out, "{}", settings.call_to_action(meta, &english))?;
+    } else {
+        writeln!(out, "Please send us this file in an issue or email.")?;
+        writeln!(out, "Use the subject: {}", meta.report_subject())?;
+    }
+    if let Some(issue_url) = &links.issue {
+        writeln!(out, "File an issue at: {}", issue_url)?;
+    }
+    if let Some(mailto) = &links.mail {
+        writeln!(out, "Email us at: {}", mailto)?;
+    }
+    if let Some(homepage) = normalize_url(&meta.homepage) {
+        writeln!(out, "Homepage: {}", homepage)?;
+    }
+    if let Some(repository) = repository_link(meta) {
+        writeln!(out, "Repository: {}", repository)?;
+    }
+    write_authors(out, "", &meta.authors, &settings.authors, &english)?;
+    if let Some(permalink) =
+        location.and_then(|l| meta.permalink(l.file(), l.line()))
+    {
+        writeln!(out, "Source: {}", permalink)?;
+    }
+    writeln!(out, "We do not collect crash data automatically.")?;
+    writeln!(out, "Thank you.")
+}
+
+/// The repository link for the crash message, unless it is the same as the
+/// homepage, which many crates set to their repository
+fn repository_link(meta: &Metadata) -> Option<String> {
+    fn trimmed(url: &str) -> &str {
+        url.trim_end_matches('/').trim_end_matches(".git")
     }
 
-    format!("\nstack backtrace:{}", backtrace)
+    let repository = normalize_url(&meta.repository)?;
+    match normalize_url(&meta.homepage) {
+        Some(homepage) if trimmed(&homepage) == trimmed(&repository) => None,
+        _ => Some(repository),
+    }
+}
+
+/// Format the panic message for printing to log
+pub fn format_panic(panic_info: &PanicHookInfo, meta: &Metadata) -> String {
+    format_panic_with_limit(panic_info, meta, None)
+}
+
+/// Format the panic message for printing to log, keeping at most
+/// `max_lines` lines of the panic message
+///
+/// Any lines past the limit are replaced by a marker saying how many were
+/// left out, so that very long messages don't drown out the backtrace.
+pub fn format_panic_truncated(
+    panic_info: &PanicHookInfo,
+    meta: &Metadata,
+    max_lines: usize,
+) -> String {
+    format_panic_with_limit(panic_info, meta, Some(max_lines))
+}
+
+fn format_panic_with_limit(
+    panic_info: &PanicHookInfo,
+    meta: &Metadata,
+    max_lines: Option<usize>,
+) -> String {
+    Report::new(panic_info, meta).render(max_lines)
 }
@@ -0,0 +1,267 @@
+//! Checking of the links shown in the crash message
+
+use std::borrow::Cow;
+
+/// URLs longer than this are assumed to be garbage rather than a homepage
+const MAX_URL_LEN: usize = 200;
+
+/// Normalize a URL for display, or `None` if it is clearly not one
+///
+/// A missing scheme is filled in with `https://`. Values with whitespace or
+/// control characters, without a plausible host, or of absurd length are
+/// rejected, so the crash message never shows a broken link.
+///
+/// ```
+/// use human_panic_logger::normalize_url;
+///
+/// assert_eq!(
+///     normalize_url("example.com/app").as_deref(),
+///     Some("https://example.com/app")
+/// );
+/// assert_eq!(
+///     normalize_url(" http://localhost:8080 ").as_deref(),
+///     Some("http://localhost:8080")
+/// );
+/// assert_eq!(normalize_url("not a url"), None);
+/// assert_eq!(normalize_url("TODO"), None);
+/// assert_eq!(normalize_url(""), None);
+/// ```
+pub fn normalize_url(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    if raw.is_empty()
+        || raw.len() > MAX_URL_LEN
+        || raw.chars().any(|c| c.is_whitespace() || c.is_control())
+    {
+        return None;
+    }
+
+    let (scheme, rest) = match raw.find("://") {
+        Some(idx) => (&raw[..idx], &raw[idx + "://".len()..]),
+        None => ("https", raw),
+    };
+    if scheme.is_empty()
+        || !scheme.chars().all(|c| c.is_ascii_alphanumeric() || c == '+')
+    {
+        return None;
+    }
+
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = host.rsplit('@').next().unwrap_or_default();
+    let host = host.split(':').next().unwrap_or_default();
+    if host != "localhost" && !host.contains('.') {
+        return None;
+    }
+
+    Some(format!("{}://{}", scheme, rest))
+}
+
+/// Cut `text` down to `max` characters, marking the cut with an ellipsis
+pub(crate) fn truncate(text: &str, max: usize) -> Cow<'_, str> {
+    match text.char_indices().nth(max) {
+        Some((idx, _)) => Cow::Owned(format!("{}…", &text[..idx])),
+        None => Cow::Borrowed(text),
+    }
+}
+
+/// Where the crate's issues are filed, for the link to a new issue in the
+/// crash message
+///
+/// Set with [`Metadata::issue_tracker`](crate::Metadata::issue_tracker),
+/// or at build time with the `HUMAN_PANIC_ISSUE_TRACKER` environment
+/// variable, which takes `github`, `gitlab`, `bitbucket`, `none` or a
+/// template.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum IssueTracker {
+    /// Told from the host of the repository URL: `github.com`,
+    /// `gitlab.com` or `bitbucket.org`
+    #[default]
+    Auto,
+    /// GitHub, or GitHub Enterprise at the repository's host
+    GitHub,
+    /// GitLab, including self-hosted instances at the repository's host
+    GitLab,
+    /// Bitbucket Cloud
+    Bitbucket,
+    /// Any other tracker, as a URL template in which `{repository}`,
+    /// `{title}` and `{body}` are replaced, e.g.
+    /// `"https://tracker.example.com/new?summary={title}&text={body}"`
+    ///
+    /// Title and body are percent-encoded, the repository URL is not.
+    /// Braces of the URL itself are written `{{` and `}}`.
+    Custom(Cow<'static, str>),
+    /// No link to a new issue
+    None,
+}
+
+impl std::str::FromStr for IssueTracker {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(IssueTracker::Auto),
+            "github" => Ok(IssueTracker::GitHub),
+            "gitlab" => Ok(IssueTracker::GitLab),
+            "bitbucket" => Ok(IssueTracker::Bitbucket),
+            "none" => Ok(IssueTracker::None),
+            _ if s.contains("{title}") || s.contains("{body}") => {
+                Ok(IssueTracker::Custom(s.to_string().into()))
+            }
+            other => Err(format!("unknown issue tracker '{}'", other)),
+        }
+    }
+}
+
+impl IssueTracker {
+    /// Check that a custom template only has placeholders we fill in
+    pub(crate) fn check(&self) -> Result<(), crate::SetupError> {
+        match self {
+            IssueTracker::Custom(template) => {
+                crate::template::check(template, &PLACEHOLDERS).map_err(
+                    |reason| crate::SetupError::InvalidTemplate {
+                        template: template.to_string(),
+                        reason,
+                    },
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The URL of a new issue about the crate in `repository`, with `title`
+    /// and `body` filled in
+    pub(crate) fn new_issue(
+        &self,
+        repository: &str,
+        title: &str,
+        body: &str,
+    ) -> Option<String> {
+        let repository = normalize_url(repository);
+        let tracker = match self {
+            IssueTracker::Auto => match repository.as_deref().map(host) {
+                Some("github.com" | "www.github.com") => IssueTracker::GitHub,
+                Some("gitlab.com") => IssueTracker::GitLab,
+                Some("bitbucket.org") => IssueTracker::Bitbucket,
+                _ => return None,
+            },
+            IssueTracker::None => return None,
+            tracker => tracker.clone(),
+        };
+        let (title, body) = (percent_encode(title), percent_encode(body));
+        match tracker {
+            IssueTracker::GitHub => Some(format!(
+                "{}/issues/new?title={}&body={}",
+                project(&repository?, Some(2))?,
+                title,
+                body
+            )),
+            IssueTracker::GitLab => Some(format!(
+                "{}/-/issues/new?issue%5Btitle%5D={}\
+                 &issue%5Bdescription%5D={}",
+                project(&repository?, None)?,
+                title,
+                body
+            )),
+            IssueTracker::Bitbucket => Some(format!(
+                "{}/issues/new?title={}&content={}",
+                project(&repository?, Some(2))?,
+                title,
+                body
+            )),
+            IssueTracker::Custom(template) => {
+                let repository = repository
+                    .as_deref()
+                    .map(|repository| project(repository, None))
+                    .unwrap_or_default()
+                    .unwrap_or_default();
+                crate::template::render(&template, |name| match name {
+                    "repository" => Some(&*repository),
+                    "title" => Some(&*title),
+                    "body" => Some(&*body),
+                    _ => None,
+                })
+                .ok()
+            }
+            IssueTracker::Auto | IssueTracker::None => None,
+        }
+    }
+}
+
+/// What custom issue tracker templates can contain
+const PLACEHOLDERS: [&str; 3] = ["repository", "title", "body"];
+
+/// The host of the normalized `url`
+pub(crate) fn host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', '?', '#']).next().unwrap_or_default()
+}
+
+/// The URL of the project `url` points into, without a trailing slash or
+/// `.git`, keeping at most `depth` path segments
+///
+/// GitHub and Bitbucket projects are always `<owner>/<name>`, while GitLab
+/// nests them in groups and marks where the project ends with `/-/`.
+fn project(url: &str, depth: Option<usize>) -> Option<String> {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    let url = url.split("/-/").next().unwrap_or_default();
+    let (scheme, rest) = url.split_once("://")?;
+    let mut parts = rest.split('/').filter(|part| !part.is_empty());
+    let host = parts.next()?;
+    let path: Vec<&str> = match depth {
+        Some(depth) => parts.take(depth).collect(),
+        None => parts.collect(),
+    };
+    if path.is_empty() || depth.is_some_and(|depth| path.len() < depth) {
+        return None;
+    }
+    let path = path.join("/");
+    Some(format!(
+        "{}://{}/{}",
+        scheme,
+        host,
+        path.trim_end_matches(".git")
+    ))
+}
+
+/// A `mailto:` URL for an email to `address` with `subject` and `body`
+/// filled in, or `None` if `address` is clearly not an email address
+pub(crate) fn mailto(
+    address: &str,
+    subject: &str,
+    body: &str,
+) -> Option<String> {
+    let address = address.trim();
+    let (user, domain) = address.split_once('@')?;
+    if user.is_empty()
+        || !domain.contains('.')
+        || address.len() > MAX_URL_LEN
+        || address.chars().any(|c| {
+            c.is_whitespace() || c.is_control() || "?&#%<>\"".contains(c)
+        })
+    {
+        return None;
+    }
+    // line breaks in mail are CRLF, see RFC 6068
+    let body = body.replace("\r\n", "\n").replace('\n', "\r\n");
+    Some(format!(
+        "mailto:{}?subject={}&body={}",
+        address,
+        percent_encode(subject),
+        percent_encode(&body)
+    ))
+}
+
+/// Percent-encode everything in `value` but unreserved characters, for a
+/// query parameter
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_'
+            | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
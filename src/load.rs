@@ -0,0 +1,314 @@
+//! Reading report files back in
+//!
+//! With the `serde` feature, applications and companion viewers can read
+//! the TOML report files this crate writes back in, of every
+//! [schema version](crate::Report::SCHEMA_VERSION) it knows about, with
+//! [`Report::load`] and [`Report::parse`]. With the `json` feature, JSON
+//! report files are read as well.
+
+use crate::environment::{Gpu, Locale, Power, Terminal};
+use crate::nested::NestedPanic;
+use crate::plugin::Plugin;
+use crate::report::{Frame, Location, Symbol};
+use crate::{Channel, Report};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+use std::{fmt, fs, io};
+
+/// Why a report file couldn't be read
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LoadError {
+    /// The file couldn't be read
+    Io(io::Error),
+    /// The file is not a report in a layout we know
+    Invalid(toml::de::Error),
+    /// The JSON file is not a report in a layout we know
+    #[cfg(feature = "json")]
+    InvalidJson(serde_json::Error),
+    /// The file was written by a newer version of this crate
+    UnsupportedSchema(u32),
+    /// A backtrace address is not a hexadecimal number
+    InvalidAddress(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(error) => {
+                write!(f, "could not read the report: {}", error)
+            }
+            LoadError::Invalid(error) => {
+                write!(f, "not a valid report: {}", error)
+            }
+            #[cfg(feature = "json")]
+            LoadError::InvalidJson(error) => {
+                write!(f, "not a valid report: {}", error)
+            }
+            LoadError::UnsupportedSchema(version) => write!(
+                f,
+                "report schema version {} is newer than the supported {}",
+                version,
+                Report::SCHEMA_VERSION
+            ),
+            LoadError::InvalidAddress(address) => {
+                write!(f, "invalid backtrace address \"{}\"", address)
+            }
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(error) => Some(error),
+            LoadError::Invalid(error) => Some(error),
+            #[cfg(feature = "json")]
+            LoadError::InvalidJson(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
+
+impl From<toml::de::Error> for LoadError {
+    fn from(error: toml::de::Error) -> Self {
+        LoadError::Invalid(error)
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for LoadError {
+    fn from(error: serde_json::Error) -> Self {
+        LoadError::InvalidJson(error)
+    }
+}
+
+/// Only the version, to pick the layout for the rest
+#[derive(Deserialize)]
+struct Version {
+    #[serde(default)]
+    schema_version: u32,
+}
+
+/// Versions 0 and 1, which only differ in `schema_version` being there
+#[derive(Deserialize)]
+struct FileV1 {
+    name: String,
+    operating_system: String,
+    crate_version: String,
+    channel: Channel,
+    timestamp: String,
+    cause: String,
+    permalink: Option<String>,
+    build_id: Option<String>,
+    session_id: Option<String>,
+    ffi_boundary: Option<String>,
+    idle_secs: Option<u64>,
+    #[serde(default)]
+    symbols_timed_out: bool,
+    location: Option<Location>,
+    nested: Option<NestedPanic>,
+    plugin: Option<Plugin>,
+    #[serde(default)]
+    terminal: TerminalV1,
+    #[serde(default)]
+    locale: LocaleV1,
+    #[serde(default)]
+    power: Power,
+    #[serde(default)]
+    gpus: Vec<Gpu>,
+    #[cfg(feature = "state")]
+    #[serde(default)]
+    state: BTreeMap<String, toml::Value>,
+    #[serde(default)]
+    backtrace: Vec<FrameV1>,
+}
+
+#[derive(Deserialize, Default)]
+struct TerminalV1 {
+    term: Option<String>,
+    colorterm: Option<String>,
+    columns: Option<u16>,
+    rows: Option<u16>,
+    #[serde(default)]
+    stdin_tty: bool,
+    #[serde(default)]
+    stdout_tty: bool,
+    #[serde(default)]
+    stderr_tty: bool,
+}
+
+#[derive(Deserialize, Default)]
+struct LocaleV1 {
+    codepage: Option<u32>,
+    #[serde(default)]
+    variables: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct FrameV1 {
+    ip: String,
+    #[serde(default)]
+    symbols: Vec<Symbol>,
+}
+
+impl Report {
+    /// Read the report file at `path` back in
+    ///
+    /// Files compressed with the `gzip` or `zstd` feature enabled are
+    /// recognized by their extension and decompressed first, and so are
+    /// JSON files with the `json` feature.
+    ///
+    /// ```no_run
+    /// use human_panic_logger::Report;
+    ///
+    /// let report = Report::load("/tmp/report-0c1e.toml")?;
+    /// println!("{}", report.summary());
+    /// # Ok::<(), human_panic_logger::LoadError>(())
+    /// ```
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Report, LoadError> {
+        let path = path.as_ref();
+        let bytes = fs::read(crate::long_path::for_io(path))?;
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        let bytes = crate::compress::decompress_file(path, bytes)?;
+        let text = String::from_utf8(bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        #[cfg(feature = "json")]
+        if is_json(path) {
+            return Report::parse_json(&text);
+        }
+        Report::parse(&text)
+    }
+
+    /// Parse a report written by [`Report::to_json`], of any schema version
+    /// up to [`Report::SCHEMA_VERSION`]
+    ///
+    /// ```
+    /// use human_panic_logger::Report;
+    ///
+    /// let report = Report::parse(
+    ///     r#"
+    ///     schema_version = 1
+    ///     name = "app"
+    ///     operating_system = "Linux"
+    ///     crate_version = "1.2.3"
+    ///     channel = "stable"
+    ///     timestamp = "2024-05-01T12:00:00+00:00"
+    ///     cause = "index out of bounds"
+    ///     "#,
+    /// )?;
+    /// let json = report.to_json();
+    /// assert_eq!(Report::parse_json(&json)?.cause, "index out of bounds");
+    /// # Ok::<(), human_panic_logger::LoadError>(())
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn parse_json(json: &str) -> Result<Report, LoadError> {
+        let version: Version = serde_json::from_str(json)?;
+        if version.schema_version > Report::SCHEMA_VERSION {
+            return Err(LoadError::UnsupportedSchema(version.schema_version));
+        }
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Parse a report written by [`Report::to_toml`], of any schema
+    /// version up to [`Report::SCHEMA_VERSION`]
+    ///
+    /// The report keeps the version it was written with, in
+    /// [`schema_version`](Report::schema_version).
+    ///
+    /// ```
+    /// use human_panic_logger::Report;
+    ///
+    /// let report = Report::parse(
+    ///     r#"
+    ///     schema_version = 1
+    ///     name = "app"
+    ///     operating_system = "Linux"
+    ///     crate_version = "1.2.3"
+    ///     channel = "stable"
+    ///     timestamp = "2024-05-01T12:00:00+00:00"
+    ///     cause = "index out of bounds"
+    ///     "#,
+    /// )?;
+    /// assert_eq!(report.version, "1.2.3");
+    /// # Ok::<(), human_panic_logger::LoadError>(())
+    /// ```
+    pub fn parse(toml: &str) -> Result<Report, LoadError> {
+        let version: Version = toml::from_str(toml)?;
+        match version.schema_version {
+            schema_version @ (0 | 1) => {
+                toml::from_str::<FileV1>(toml)?.into_report(schema_version)
+            }
+            newer => Err(LoadError::UnsupportedSchema(newer)),
+        }
+    }
+}
+
+impl FileV1 {
+    fn into_report(self, schema_version: u32) -> Result<Report, LoadError> {
+        let backtrace = self
+            .backtrace
+            .into_iter()
+            .map(|frame| {
+                let hex = frame.ip.trim_start_matches("0x");
+                let ip = usize::from_str_radix(hex, 16)
+                    .map_err(|_| LoadError::InvalidAddress(frame.ip.clone()))?;
+                Ok(Frame {
+                    ip,
+                    symbols: frame.symbols,
+                })
+            })
+            .collect::<Result<_, LoadError>>()?;
+
+        Ok(Report {
+            schema_version,
+            name: self.name,
+            version: self.crate_version,
+            channel: self.channel,
+            operating_system: self.operating_system,
+            timestamp: self.timestamp,
+            build_id: self.build_id,
+            session_id: self.session_id,
+            cause: self.cause,
+            location: self.location,
+            permalink: self.permalink,
+            idle_secs: self.idle_secs,
+            ffi_boundary: self.ffi_boundary,
+            nested: self.nested,
+            plugin: self.plugin,
+            terminal: Terminal {
+                term: self.terminal.term,
+                colorterm: self.terminal.colorterm,
+                size: self.terminal.columns.zip(self.terminal.rows),
+                stdin_tty: self.terminal.stdin_tty,
+                stdout_tty: self.terminal.stdout_tty,
+                stderr_tty: self.terminal.stderr_tty,
+            },
+            locale: Locale {
+                variables: self.locale.variables.into_iter().collect(),
+                codepage: self.locale.codepage,
+            },
+            gpus: self.gpus,
+            power: self.power,
+            backtrace,
+            symbols_timed_out: self.symbols_timed_out,
+            #[cfg(feature = "state")]
+            state: self.state,
+        })
+    }
+}
+
+/// Whether the file at `path` is a JSON report, compressed or not
+#[cfg(feature = "json")]
+fn is_json(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.ends_with(".json") || name.contains(".json.")
+}
@@ -0,0 +1,69 @@
+//! Long and UNC paths on Windows
+//!
+//! Roaming profiles and network shares easily push paths past `MAX_PATH`,
+//! which the plain Windows file APIs refuse. Such paths are opened in their
+//! extended-length form (`\\?\C:\...` or `\\?\UNC\server\share\...`), but
+//! shown to users without the prefix, so they can be pasted into Explorer
+//! or a shell. Elsewhere, paths are used as they are.
+
+use std::borrow::Cow;
+use std::path::Path;
+
+/// The path to open `path` with
+#[cfg(windows)]
+pub(crate) fn for_io(path: &Path) -> Cow<'_, Path> {
+    // the longest directory path the plain APIs accept
+    const MAX_DIR_PATH: usize = 248;
+
+    let raw = match path.to_str() {
+        Some(raw) => raw,
+        None => return Cow::Borrowed(path),
+    };
+    if raw.starts_with(r"\\?\") || raw.starts_with(r"\\.\") {
+        return Cow::Borrowed(path);
+    }
+    // the extended-length form skips normalization, so do it ourselves
+    let absolute = match std::path::absolute(path) {
+        Ok(absolute) => absolute,
+        Err(_) => return Cow::Borrowed(path),
+    };
+    let absolute = match absolute.to_str() {
+        Some(absolute) if absolute.len() >= MAX_DIR_PATH => absolute,
+        _ => return Cow::Borrowed(path),
+    };
+    match absolute.strip_prefix(r"\\") {
+        Some(unc) => Cow::Owned(format!(r"\\?\UNC\{}", unc).into()),
+        None => Cow::Owned(format!(r"\\?\{}", absolute).into()),
+    }
+}
+
+/// The path to open `path` with
+#[cfg(not(windows))]
+pub(crate) fn for_io(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
+/// `path` as users should see it
+#[cfg(windows)]
+pub(crate) fn for_display(path: &Path) -> Cow<'_, Path> {
+    let raw = match path.to_str() {
+        Some(raw) => raw,
+        None => return Cow::Borrowed(path),
+    };
+    if let Some(unc) = raw.strip_prefix(r"\\?\UNC\") {
+        return Cow::Owned(format!(r"\\{}", unc).into());
+    }
+    match raw.strip_prefix(r"\\?\") {
+        // only drive paths; `\\?\Volume{...}` has no plain form
+        Some(plain) if plain.get(1..3) == Some(r":\") => {
+            Cow::Owned(plain.into())
+        }
+        _ => Cow::Borrowed(path),
+    }
+}
+
+/// `path` as users should see it
+#[cfg(not(windows))]
+pub(crate) fn for_display(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
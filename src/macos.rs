@@ -0,0 +1,215 @@
+//! Companion crash reports in the style of Apple's `.crash` files
+//!
+//! Support staff on macOS are used to reading crash reports in Console.app.
+//! With the `macos-crash-report` feature enabled, every report is
+//! additionally written in that layout (process header, crashed thread,
+//! binary images, no registers) to `~/Library/Logs/DiagnosticReports`,
+//! where Console.app lists it next to the system's own reports.
+
+use crate::clock::DateTime;
+use crate::report::Frame;
+use crate::Report;
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::CStr;
+use std::fmt::Write as FmtWrite;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::raw::{c_char, c_int, c_void};
+use std::path::{Path, PathBuf};
+
+#[repr(C)]
+struct DlInfo {
+    dli_fname: *const c_char,
+    dli_fbase: *mut c_void,
+    dli_sname: *const c_char,
+    dli_saddr: *mut c_void,
+}
+
+extern "C" {
+    fn dladdr(addr: *const c_void, info: *mut DlInfo) -> c_int;
+}
+
+/// The binary image an address belongs to
+struct Image {
+    path: String,
+    base: usize,
+    symbol_address: Option<usize>,
+}
+
+fn image_of(ip: usize) -> Option<Image> {
+    let mut info = DlInfo {
+        dli_fname: std::ptr::null(),
+        dli_fbase: std::ptr::null_mut(),
+        dli_sname: std::ptr::null(),
+        dli_saddr: std::ptr::null_mut(),
+    };
+    // SAFETY: `dladdr` only reads the address and fills in `info`
+    if unsafe { dladdr(ip as *const c_void, &mut info) } == 0
+        || info.dli_fname.is_null()
+    {
+        return None;
+    }
+
+    // SAFETY: `dli_fname` points to a NUL terminated string owned by dyld
+    let path = unsafe { CStr::from_ptr(info.dli_fname) };
+    Some(Image {
+        path: path.to_string_lossy().into_owned(),
+        base: info.dli_fbase as usize,
+        symbol_address: if info.dli_saddr.is_null() {
+            None
+        } else {
+            Some(info.dli_saddr as usize)
+        },
+    })
+}
+
+/// The directory Console.app lists user crash reports from
+pub fn diagnostic_reports_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| {
+        Path::new(&home).join("Library/Logs/DiagnosticReports")
+    })
+}
+
+/// Write the companion report to the diagnostic reports directory,
+/// ignoring failures since the regular report has already been written
+pub fn submit(report: &Report) {
+    if let Some(dir) = diagnostic_reports_dir() {
+        let _ = write_crash_report(report, dir);
+    }
+}
+
+/// Write the report in the `.crash` layout into `dir`
+pub fn write_crash_report<P: AsRef<Path>>(
+    report: &Report,
+    dir: P,
+) -> io::Result<PathBuf> {
+    let now = DateTime::now();
+    let path = dir.as_ref().join(format!(
+        "{}_{}.crash",
+        report.name,
+        now.format("%Y-%m-%d-%H%M%S")
+    ));
+
+    let executable = env::current_exe()?;
+    let code_type = if cfg!(target_arch = "aarch64") {
+        "ARM-64 (Native)"
+    } else {
+        "X86-64 (Native)"
+    };
+    let thread = std::thread::current();
+
+    let mut contents = String::new();
+    let _ = writeln!(
+        contents,
+        "Process:               {} [{}]",
+        report.name,
+        std::process::id()
+    );
+    let _ = writeln!(contents, "Path:                  {}", executable.display());
+    let _ = writeln!(contents, "Identifier:            {}", report.name);
+    let _ = writeln!(
+        contents,
+        "Version:               {} ({})",
+        report.version, report.channel
+    );
+    let _ = writeln!(contents, "Code Type:             {}", code_type);
+    let _ = writeln!(
+        contents,
+        "Parent Process:        [{}]",
+        std::os::unix::process::parent_id()
+    );
+    let _ = writeln!(
+        contents,
+        "Date/Time:             {}",
+        now.format("%Y-%m-%d %H:%M:%S%.3f %z")
+    );
+    let _ = writeln!(contents, "Report Version:        12");
+    let _ = writeln!(contents);
+    let _ = writeln!(contents, "Exception Type:        Rust panic");
+    let _ = writeln!(
+        contents,
+        "Termination Reason:    {}",
+        report.cause.lines().next().unwrap_or_default()
+    );
+    if let Some(location) = &report.location {
+        let _ = writeln!(
+            contents,
+            "Panic Location:        {}:{}",
+            location.file, location.line
+        );
+    }
+    let _ = writeln!(contents);
+    let _ = writeln!(contents, "Crashed Thread:        0");
+    let _ = writeln!(contents);
+    let _ = writeln!(
+        contents,
+        "Thread 0 Crashed:: {}",
+        thread.name().unwrap_or("")
+    );
+
+    let mut images = BTreeMap::new();
+    for (idx, frame) in report.backtrace.iter().enumerate() {
+        let image = image_of(frame.ip);
+        let image_name = image
+            .as_ref()
+            .map(|i| file_name(&i.path))
+            .unwrap_or("???");
+        let _ = writeln!(
+            contents,
+            "{:<4}{:<30}\t0x{:016x} {}",
+            idx,
+            image_name,
+            frame.ip,
+            describe(frame, image.as_ref())
+        );
+        if let Some(image) = image {
+            images.entry(image.base).or_insert(image.path);
+        }
+    }
+
+    let _ = writeln!(contents);
+    let _ = writeln!(contents, "Binary Images:");
+    for (base, path) in &images {
+        let _ = writeln!(
+            contents,
+            "{:>18} - {:>18} {} {}",
+            format!("0x{:x}", base),
+            "???",
+            file_name(path),
+            path
+        );
+    }
+
+    fs::create_dir_all(dir.as_ref())?;
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)?
+        .write_all(contents.as_bytes())?;
+
+    Ok(path)
+}
+
+/// Describe a frame like Apple does: `symbol + offset (file:line)`
+fn describe(frame: &Frame, image: Option<&Image>) -> String {
+    let symbol = match frame.symbols.last() {
+        Some(symbol) => symbol,
+        None => return "<unresolved>".into(),
+    };
+
+    let mut description =
+        symbol.name.clone().unwrap_or_else(|| "<unknown>".into());
+    if let Some(address) = image.and_then(|i| i.symbol_address) {
+        let _ = write!(description, " + {}", frame.ip.saturating_sub(address));
+    }
+    if let (Some(file), Some(line)) = (&symbol.file, symbol.line) {
+        let file = file.file_name().unwrap_or(file.as_os_str());
+        let _ = write!(description, " ({}:{})", file.to_string_lossy(), line);
+    }
+    description
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
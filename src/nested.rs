@@ -0,0 +1,46 @@
+//! Panics raised by destructors while unwinding from another panic
+//!
+//! When a `Drop` implementation panics during unwinding, the runtime calls
+//! the hook for that second panic and then once more with the fixed message
+//! `panic in a destructor during cleanup`, right before aborting. On its own
+//! that last report says nothing useful, so we remember the recent panics
+//! of each thread and describe both of them instead.
+
+use std::cell::RefCell;
+
+/// The message the runtime panics with before aborting
+const PANIC_IN_CLEANUP: &str = "panic in a destructor during cleanup";
+
+thread_local! {
+    /// Summaries of the last two panics on this thread, oldest first
+    static RECENT: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A destructor panicked while unwinding from an earlier panic
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NestedPanic {
+    /// Summary of the panic that started the unwinding
+    pub original: String,
+    /// Summary of the panic raised by a destructor during that unwinding
+    pub in_drop: String,
+}
+
+/// Remember a panic of the current thread, and return the nested panic it
+/// completes, if `cause` is the runtime's abort message
+pub(crate) fn record(cause: &str, summary: String) -> Option<NestedPanic> {
+    RECENT.with(|recent| {
+        let mut recent = recent.borrow_mut();
+        if cause == PANIC_IN_CLEANUP && recent.len() == 2 {
+            let in_drop = recent.pop()?;
+            let original = recent.pop()?;
+            return Some(NestedPanic { original, in_drop });
+        }
+
+        if recent.len() == 2 {
+            recent.remove(0);
+        }
+        recent.push(summary);
+        None
+    })
+}
@@ -0,0 +1,73 @@
+//! Timing of the panic hook's stages
+//!
+//! Applications that watch their crash path in production telemetry
+//! register observers with [`Builder::observe`](crate::Builder::observe).
+//! Each stage of the hook is then bracketed by a [`HookEvent::Begin`] and a
+//! [`HookEvent::End`] carrying how long it took.
+
+use std::time::{Duration, Instant};
+
+/// A stage of the panic hook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HookStage {
+    /// Building the [`Report`](crate::Report), including the backtrace,
+    /// state snapshots and the metadata resolved at crash time
+    Capture,
+    /// Rendering the report for the log
+    Format,
+    /// Writing the report to the log
+    Log,
+    /// Writing the report file
+    Write,
+    /// Printing the crash message
+    Print,
+    /// Handing the report to the system's crash tooling, the supervising
+    /// process and the registered [`ReportSink`](crate::ReportSink)s
+    Submit,
+}
+
+/// Something that happened in the panic hook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HookEvent {
+    /// A stage started
+    Begin(HookStage),
+    /// A stage finished, after the given time
+    End(HookStage, Duration),
+}
+
+/// The registered observers
+#[derive(Default)]
+pub(crate) struct Observers(Vec<Box<dyn Fn(HookEvent) + Send + Sync>>);
+
+impl Observers {
+    pub(crate) fn register(
+        &mut self,
+        observer: Box<dyn Fn(HookEvent) + Send + Sync>,
+    ) {
+        self.0.push(observer);
+    }
+
+    /// Run the `stage` done by `f`, telling the observers about it
+    pub(crate) fn stage<R>(
+        &self,
+        stage: HookStage,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        if self.0.is_empty() {
+            return f();
+        }
+        self.emit(HookEvent::Begin(stage));
+        let start = Instant::now();
+        let result = f();
+        self.emit(HookEvent::End(stage, start.elapsed()));
+        result
+    }
+
+    fn emit(&self, event: HookEvent) {
+        for observer in &self.0 {
+            observer(event);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Panics that originate outside the application's own crates
+//!
+//! Plugin hosts and other applications running third-party code don't want
+//! to collect reports under their name for bugs that aren't theirs. The
+//! innermost frame of the backtrace outside the standard library tells us
+//! which crate a panic came from, and [`ForeignPanics`] says what to do if
+//! that isn't one of the application's own.
+
+use crate::Report;
+
+/// Crates whose frames don't say where a panic came from
+const RUNTIME_CRATES: &[&str] = &[
+    "std",
+    "core",
+    "alloc",
+    "panic_unwind",
+    "__rust",
+    "backtrace",
+    "human_panic_logger",
+];
+
+/// What to do about a panic that originates outside the application's own
+/// crates, see [`Builder::own_crates`](crate::Builder::own_crates)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ForeignPanics {
+    /// Handle it like any other panic
+    #[default]
+    Report,
+    /// Log it and print the crash message, but don't write a report file
+    /// or hand it to the system's crash tooling
+    MessageOnly,
+    /// Leave it to the previously installed hook, as if this one wasn't
+    /// there
+    Passthrough,
+}
+
+/// The crates that make up the application and how to treat panics in
+/// others
+#[derive(Debug, Default)]
+pub(crate) struct OwnCrates {
+    pub(crate) prefixes: Vec<String>,
+    pub(crate) foreign: ForeignPanics,
+}
+
+impl OwnCrates {
+    /// How to respond to the panic described by `report`
+    ///
+    /// Panics whose origin can't be told, e.g. because symbols couldn't be
+    /// resolved, count as the application's own.
+    pub(crate) fn response(&self, report: &Report) -> ForeignPanics {
+        if self.foreign == ForeignPanics::Report || self.prefixes.is_empty() {
+            return ForeignPanics::Report;
+        }
+        match origin_crate(report) {
+            Some(name)
+                if !self
+                    .prefixes
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix.as_str())) =>
+            {
+                self.foreign
+            }
+            _ => ForeignPanics::Report,
+        }
+    }
+}
+
+/// The crate of the innermost frame outside the standard library
+fn origin_crate(report: &Report) -> Option<&str> {
+    report
+        .backtrace
+        .iter()
+        .flat_map(|frame| frame.symbols.iter())
+        .filter_map(|symbol| symbol.name.as_deref())
+        .filter_map(crate_name)
+        .find(|name| {
+            !RUNTIME_CRATES
+                .iter()
+                .any(|runtime| name.starts_with(runtime))
+        })
+}
+
+/// The crate a demangled symbol name belongs to, e.g. `myapp` for
+/// `<myapp::Parser as core::iter::Iterator>::next`
+///
+/// Symbols without a path, like the C `main`, belong to no crate.
+fn crate_name(symbol: &str) -> Option<&str> {
+    let path = symbol.trim_start_matches(['<', '&']);
+    let path = path.trim_start_matches("mut ").trim_start_matches("dyn ");
+    let end = path.find("::")?;
+    let name = &path[..end];
+    // v0 mangling adds a disambiguator, e.g. `std[e28293b1aa0f68bd]`
+    Some(name.split('[').next().unwrap_or(name))
+}
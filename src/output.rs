@@ -0,0 +1,47 @@
+//! Where the crash message goes
+//!
+//! Language servers and other tools that speak a protocol over stdio can't
+//! have prose in their output: stdout carries the protocol, and some
+//! clients mix stderr into their transcripts as well.
+
+use crate::format::string;
+use std::io::{self, Write};
+
+/// Where the crash message goes, see [`Settings::output`]
+///
+/// [`Settings::output`]: crate::Settings::output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum MessageOutput {
+    /// The crash message on stderr, and in debug builds the standard panic
+    /// output as well
+    #[default]
+    Stderr,
+    /// A `window/showMessage` notification on stdout, framed like every
+    /// message of the Language Server Protocol and other JSON-RPC tools,
+    /// and nothing on stderr
+    ///
+    /// The notification is written at once while holding the stdout lock,
+    /// so it can't end up inside a message written by the rest of the
+    /// program through [`std::io::stdout`].
+    LspNotification,
+    /// Nothing at all: the crash only goes to the log and the report file
+    Silent,
+}
+
+/// Send `message` to the client as an error notification on stdout
+pub(crate) fn show_message(message: &str) -> io::Result<()> {
+    /// `MessageType.Error`
+    const ERROR: u8 = 1;
+
+    let body = format!(
+        "{{\"jsonrpc\":\"2.0\",\"method\":\"window/showMessage\",\
+         \"params\":{{\"type\":{},\"message\":{}}}}}",
+        ERROR,
+        string(message)
+    );
+    let framed = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(framed.as_bytes())?;
+    stdout.flush()
+}
@@ -0,0 +1,45 @@
+//! Permissions of the files and directories we create
+//!
+//! Crash reports and logs can contain paths and other details that
+//! shouldn't be readable by everyone on shared machines, see
+//! [`Builder::file_mode`](crate::Builder::file_mode).
+
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+
+/// The Unix modes to create files and directories with, if not the defaults
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Permissions {
+    pub(crate) file: Option<u32>,
+    pub(crate) dir: Option<u32>,
+}
+
+impl Permissions {
+    /// Make `options` create files with the configured mode
+    pub(crate) fn apply<'a>(
+        &self,
+        options: &'a mut OpenOptions,
+    ) -> &'a mut OpenOptions {
+        #[cfg(unix)]
+        if let Some(mode) = self.file {
+            use std::os::unix::fs::OpenOptionsExt;
+
+            options.mode(mode);
+        }
+        options
+    }
+
+    /// Create `dir` and its missing parents with the configured mode
+    pub(crate) fn create_dir_all(&self, dir: &Path) -> io::Result<()> {
+        let mut builder = std::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        if let Some(mode) = self.dir {
+            use std::os::unix::fs::DirBuilderExt;
+
+            builder.mode(mode);
+        }
+        builder.create(crate::long_path::for_io(dir))
+    }
+}
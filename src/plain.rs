@@ -0,0 +1,73 @@
+//! Plain-language explanations of common panic messages
+//!
+//! The standard library's panic messages are written for developers. With
+//! [`Settings::explain_panics`](crate::Settings::explain_panics), the crash
+//! message explains the well-known ones to users, while the report keeps
+//! the message as it was.
+
+/// A plain explanation of the panic message `cause`, if it's one of the
+/// standard library's well-known ones
+pub(crate) fn explain(cause: &str) -> Option<String> {
+    let cause = cause.lines().next().unwrap_or_default();
+
+    if let Some(rest) = cause.strip_prefix("index out of bounds: the len is ")
+    {
+        let (len, index) = rest.split_once(" but the index is ")?;
+        return Some(format!(
+            "tried to access item {} of a list with {} items",
+            index, len
+        ));
+    }
+    if let Some(rest) = cause.strip_prefix("range end index ") {
+        let (end, len) =
+            rest.split_once(" out of range for slice of length ")?;
+        return Some(format!(
+            "tried to access items up to {} of a list with {} items",
+            end, len
+        ));
+    }
+    if let Some(rest) = cause.strip_prefix("range start index ") {
+        let (start, len) =
+            rest.split_once(" out of range for slice of length ")?;
+        return Some(format!(
+            "tried to access items from {} on of a list with {} items",
+            start, len
+        ));
+    }
+    if let Some(error) =
+        cause.strip_prefix("called `Result::unwrap()` on an `Err` value: ")
+    {
+        return Some(format!("an operation failed unexpectedly: {}", error));
+    }
+
+    let explanation = match cause {
+        "called `Option::unwrap()` on a `None` value" => {
+            "a value the program expected was missing"
+        }
+        "attempt to divide by zero"
+        | "attempt to calculate the remainder with a divisor of zero" => {
+            "tried to divide by zero"
+        }
+        "attempt to add with overflow"
+        | "attempt to multiply with overflow"
+        | "attempt to negate with overflow"
+        | "attempt to shift left with overflow" => {
+            "a number got too large to be stored"
+        }
+        "attempt to subtract with overflow" => {
+            "a number got too small to be stored"
+        }
+        "capacity overflow" => "tried to reserve more memory than possible",
+        "already borrowed: BorrowMutError" | "already mutably borrowed: \
+         BorrowError" => "tried to change data that was in use",
+        _ if cause.starts_with("byte index ") => {
+            "tried to cut text at an invalid position"
+        }
+        _ if cause.starts_with("failed printing to std") => {
+            "could not write to the terminal"
+        }
+        _ if cause.starts_with("assertion `") => "an internal check failed",
+        _ => return None,
+    };
+    Some(explanation.to_string())
+}
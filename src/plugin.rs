@@ -0,0 +1,200 @@
+//! Attribution of crashes to dynamically loaded plugins
+//!
+//! Applications that load plugins register each one with
+//! [`register_plugin`]. If a backtrace passes through a registered library,
+//! the report names the plugin, and the crash message sends users to the
+//! plugin's issue tracker rather than the application's.
+//!
+//! Libraries are told apart by the addresses of their code, so this works
+//! on Unix and Windows only. Note that a plugin built as a `cdylib` carries
+//! its own copy of the standard library, whose panics never reach this hook
+//! unless they unwind into the application.
+
+use crate::long_path::for_display;
+use crate::report::Frame;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// The plugins currently loaded
+static PLUGINS: Mutex<Vec<Plugin>> = Mutex::new(Vec::new());
+
+/// A dynamically loaded plugin
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Plugin {
+    /// The name of the plugin
+    pub name: String,
+    /// The version of the plugin
+    pub version: String,
+    /// The shared library the plugin was loaded from
+    pub path: PathBuf,
+    /// Where to report bugs in the plugin, if it has a tracker of its own
+    pub issues: Option<String>,
+}
+
+impl Plugin {
+    /// Describe the plugin `name` loaded from the library at `path`
+    pub fn new(
+        name: impl Into<String>,
+        version: impl Into<String>,
+        path: impl Into<PathBuf>,
+    ) -> Self {
+        Plugin {
+            name: name.into(),
+            version: version.into(),
+            path: path.into(),
+            issues: None,
+        }
+    }
+
+    /// Set where to report bugs in the plugin
+    pub fn issues(mut self, issues: impl Into<String>) -> Self {
+        self.issues = Some(issues.into());
+        self
+    }
+}
+
+/// Record that `plugin` was loaded, so crashes in its code are attributed
+/// to it
+///
+/// ```
+/// use human_panic_logger::{register_plugin, unregister_plugin, Plugin};
+///
+/// let plugin = Plugin::new("spell-check", "0.4.1", "plugins/libspell.so")
+///     .issues("https://github.com/example/spell-check/issues");
+/// register_plugin(plugin);
+/// // ...
+/// unregister_plugin("plugins/libspell.so");
+/// ```
+pub fn register_plugin(mut plugin: Plugin) {
+    // the loader may report the library under its resolved path
+    if let Ok(path) = plugin.path.canonicalize() {
+        plugin.path = for_display(&path).into_owned();
+    }
+    plugins().push(plugin);
+}
+
+/// Record that the plugin loaded from `path` was unloaded
+pub fn unregister_plugin<P: AsRef<Path>>(path: P) {
+    let path = path.as_ref();
+    let canonical = path
+        .canonicalize()
+        .ok()
+        .map(|path| for_display(&path).into_owned());
+    plugins().retain(|plugin| {
+        plugin.path != path && Some(&plugin.path) != canonical.as_ref()
+    });
+}
+
+fn plugins() -> MutexGuard<'static, Vec<Plugin>> {
+    PLUGINS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The registered plugin of the innermost frame in one, if any
+pub(crate) fn find(backtrace: &[Frame]) -> Option<Plugin> {
+    let plugins = plugins();
+    if plugins.is_empty() {
+        return None;
+    }
+    backtrace.iter().find_map(|frame| {
+        let image = image_path(frame.ip)?;
+        let canonical = image
+            .canonicalize()
+            .ok()
+            .map(|path| for_display(&path).into_owned());
+        plugins
+            .iter()
+            .find(|plugin| {
+                plugin.path == image || Some(&plugin.path) == canonical.as_ref()
+            })
+            .cloned()
+    })
+}
+
+/// The path of the executable or shared library containing `ip`
+#[cfg(unix)]
+fn image_path(ip: usize) -> Option<PathBuf> {
+    use std::ffi::CStr;
+    use std::os::raw::{c_char, c_int, c_void};
+    use std::os::unix::ffi::OsStrExt;
+
+    #[repr(C)]
+    struct DlInfo {
+        dli_fname: *const c_char,
+        dli_fbase: *mut c_void,
+        dli_sname: *const c_char,
+        dli_saddr: *mut c_void,
+    }
+
+    extern "C" {
+        fn dladdr(addr: *const c_void, info: *mut DlInfo) -> c_int;
+    }
+
+    let mut info = DlInfo {
+        dli_fname: std::ptr::null(),
+        dli_fbase: std::ptr::null_mut(),
+        dli_sname: std::ptr::null(),
+        dli_saddr: std::ptr::null_mut(),
+    };
+    // SAFETY: `dladdr` only fills in `info`, and the file name it points
+    // to stays valid while the library is loaded
+    unsafe {
+        if dladdr(ip as *const c_void, &mut info) == 0
+            || info.dli_fname.is_null()
+        {
+            return None;
+        }
+        let name = CStr::from_ptr(info.dli_fname).to_bytes();
+        Some(PathBuf::from(std::ffi::OsStr::from_bytes(name)))
+    }
+}
+
+/// The path of the executable or shared library containing `ip`
+#[cfg(windows)]
+fn image_path(ip: usize) -> Option<PathBuf> {
+    use std::ffi::OsString;
+    use std::os::raw::c_void;
+    use std::os::windows::ffi::OsStringExt;
+
+    const FROM_ADDRESS: u32 = 0x4;
+    const UNCHANGED_REFCOUNT: u32 = 0x2;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetModuleHandleExW(
+            flags: u32,
+            name: *const u16,
+            module: *mut *mut c_void,
+        ) -> i32;
+        fn GetModuleFileNameW(
+            module: *mut c_void,
+            name: *mut u16,
+            size: u32,
+        ) -> u32;
+    }
+
+    let mut module = std::ptr::null_mut();
+    let mut name = [0u16; 1024];
+    // SAFETY: with `FROM_ADDRESS`, the name argument is an address rather
+    // than a string, and both calls only write into the buffers passed
+    unsafe {
+        if GetModuleHandleExW(
+            FROM_ADDRESS | UNCHANGED_REFCOUNT,
+            ip as *const u16,
+            &mut module,
+        ) == 0
+        {
+            return None;
+        }
+        let len = GetModuleFileNameW(module, name.as_mut_ptr(), 1024);
+        if len == 0 {
+            return None;
+        }
+        Some(OsString::from_wide(&name[..len as usize]).into())
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn image_path(_ip: usize) -> Option<PathBuf> {
+    None
+}
@@ -0,0 +1,93 @@
+//! Keeping the crash reports of several profiles apart
+//!
+//! Deployments serving several environments or tenants from one build set
+//! the active profile with [`set_profile`], e.g. from a `--profile staging`
+//! argument. Report files then go into a subdirectory of the report
+//! directory named after the profile, and sinks registered with
+//! [`Builder::profile_sink`](crate::Builder::profile_sink) only get the
+//! reports of their profile.
+
+use crate::sink::ReportSink;
+use crate::Report;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// The profile crashes are reported under
+static PROFILE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Report crashes under `profile` from now on, or under none with `None`
+///
+/// ```
+/// use human_panic_logger::{profile, set_profile};
+///
+/// set_profile(Some("staging"));
+/// assert_eq!(profile().as_deref(), Some("staging"));
+/// ```
+pub fn set_profile(profile: Option<&str>) {
+    let profile = profile.filter(|profile| !profile.is_empty());
+    *PROFILE.lock().unwrap_or_else(|e| e.into_inner()) =
+        profile.map(str::to_string);
+}
+
+/// The profile crashes are reported under, see [`set_profile`]
+pub fn profile() -> Option<String> {
+    PROFILE.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Where the report files of the active profile go, within `dir`
+pub(crate) fn report_dir(dir: PathBuf) -> PathBuf {
+    match profile() {
+        Some(profile) => dir.join(dir_name(&profile)),
+        None => dir,
+    }
+}
+
+/// `profile` made safe as the name of a directory
+///
+/// Anything but ASCII letters, digits, `-` and `_` becomes `_`, so a
+/// profile can't point outside the report directory.
+fn dir_name(profile: &str) -> String {
+    profile
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// A sink that only gets the reports of one profile, see
+/// [`Builder::profile_sink`](crate::Builder::profile_sink)
+pub(crate) struct ProfileSink {
+    pub(crate) profile: String,
+    pub(crate) sink: Box<dyn ReportSink>,
+}
+
+impl ReportSink for ProfileSink {
+    fn submit(
+        &self,
+        report: &Report,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match profile() {
+            Some(profile) if profile == self.profile => {
+                self.sink.submit(report)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.sink.check()
+    }
+
+    fn uploads(&self) -> bool {
+        // only asks for consent when it would send the report
+        self.sink.uploads() && profile().as_ref() == Some(&self.profile)
+    }
+
+    fn flush_pending(&self) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        // reports were queued under this profile, whatever it is now
+        self.sink.flush_pending()
+    }
+}
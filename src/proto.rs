@@ -0,0 +1,320 @@
+//! Protobuf encoding of crash reports
+//!
+//! The message types mirror `proto/report.proto` in the repository, so
+//! ingestion pipelines can generate their decoders from that file.
+
+use crate::report;
+
+/// Protobuf form of a [`Report`](crate::Report)
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Report {
+    /// The name of the crate that panicked
+    #[prost(string, tag = "1")]
+    pub name: String,
+    /// The version of the crate that panicked
+    #[prost(string, tag = "2")]
+    pub version: String,
+    /// The release channel the crate was built for
+    #[prost(string, tag = "3")]
+    pub channel: String,
+    /// The panic message
+    #[prost(string, tag = "4")]
+    pub cause: String,
+    /// Where in the source code the panic happened, if known
+    #[prost(message, optional, tag = "5")]
+    pub location: Option<Location>,
+    /// Link to the panic location in the source repository, if known
+    #[prost(string, optional, tag = "6")]
+    pub permalink: Option<String>,
+    /// The stack frames leading up to the panic
+    #[prost(message, repeated, tag = "7")]
+    pub backtrace: Vec<Frame>,
+    /// Seconds between the last recorded user action and the panic
+    #[prost(uint64, optional, tag = "8")]
+    pub idle_secs: Option<u64>,
+    /// The terminal the application ran in
+    #[prost(message, optional, tag = "9")]
+    pub terminal: Option<Terminal>,
+    /// The locale the application ran with
+    #[prost(message, optional, tag = "10")]
+    pub locale: Option<Locale>,
+    /// The graphics adapters of the machine
+    #[prost(message, repeated, tag = "11")]
+    pub gpus: Vec<Gpu>,
+    /// Power and thermal state of the machine
+    #[prost(message, optional, tag = "12")]
+    pub power: Option<Power>,
+    /// The build id, if the build set one
+    #[prost(string, optional, tag = "13")]
+    pub build_id: Option<String>,
+    /// The FFI boundary the panic was stopped at
+    #[prost(string, optional, tag = "14")]
+    pub ffi_boundary: Option<String>,
+    /// The earlier panics, if a destructor panicked while unwinding
+    #[prost(message, optional, tag = "15")]
+    pub nested: Option<NestedPanic>,
+    /// Whether resolving symbols took too long, leaving some frames
+    /// unresolved
+    #[prost(bool, tag = "16")]
+    pub symbols_timed_out: bool,
+    /// Same for repeated crashes of the same bug, see
+    /// [`Report::fingerprint`](crate::Report::fingerprint)
+    #[prost(string, tag = "17")]
+    pub fingerprint: String,
+    /// The session the crash happened in
+    #[prost(string, optional, tag = "18")]
+    pub session_id: Option<String>,
+    /// The operating system
+    #[prost(string, tag = "19")]
+    pub operating_system: String,
+    /// When the panic happened, in RFC 3339 format
+    #[prost(string, tag = "20")]
+    pub timestamp: String,
+    /// Snapshots of the registered state providers, as TOML, by section
+    /// name
+    #[prost(map = "string, string", tag = "21")]
+    pub state: std::collections::HashMap<String, String>,
+    /// The plugin the panic passed through, if any
+    #[prost(message, optional, tag = "22")]
+    pub plugin: Option<Plugin>,
+    /// The version of the report layout, see
+    /// [`Report::SCHEMA_VERSION`](crate::Report::SCHEMA_VERSION)
+    #[prost(uint32, tag = "23")]
+    pub schema_version: u32,
+}
+
+/// Protobuf form of a [`NestedPanic`](crate::NestedPanic)
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct NestedPanic {
+    /// Summary of the panic that started the unwinding
+    #[prost(string, tag = "1")]
+    pub original: String,
+    /// Summary of the panic raised by a destructor during that unwinding
+    #[prost(string, tag = "2")]
+    pub in_drop: String,
+}
+
+/// Protobuf form of a [`Plugin`](crate::Plugin)
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Plugin {
+    /// The name of the plugin
+    #[prost(string, tag = "1")]
+    pub name: String,
+    /// The version of the plugin
+    #[prost(string, tag = "2")]
+    pub version: String,
+    /// The shared library the plugin was loaded from
+    #[prost(string, tag = "3")]
+    pub path: String,
+    /// Where to report bugs in the plugin
+    #[prost(string, optional, tag = "4")]
+    pub issues: Option<String>,
+}
+
+/// Protobuf form of a [`Terminal`](crate::environment::Terminal)
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Terminal {
+    /// The `TERM` environment variable
+    #[prost(string, optional, tag = "1")]
+    pub term: Option<String>,
+    /// The `COLORTERM` environment variable
+    #[prost(string, optional, tag = "2")]
+    pub colorterm: Option<String>,
+    /// Columns of the terminal, if attached to one
+    #[prost(uint32, optional, tag = "3")]
+    pub columns: Option<u32>,
+    /// Rows of the terminal, if attached to one
+    #[prost(uint32, optional, tag = "4")]
+    pub rows: Option<u32>,
+    /// Whether stdin is a terminal
+    #[prost(bool, tag = "5")]
+    pub stdin_tty: bool,
+    /// Whether stdout is a terminal
+    #[prost(bool, tag = "6")]
+    pub stdout_tty: bool,
+    /// Whether stderr is a terminal
+    #[prost(bool, tag = "7")]
+    pub stderr_tty: bool,
+}
+
+/// Protobuf form of a [`Locale`](crate::environment::Locale)
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Locale {
+    /// `LANG`, `LANGUAGE` and every `LC_*` variable that is set
+    #[prost(map = "string, string", tag = "1")]
+    pub variables: std::collections::HashMap<String, String>,
+    /// The active ANSI codepage, on Windows
+    #[prost(uint32, optional, tag = "2")]
+    pub codepage: Option<u32>,
+}
+
+/// Protobuf form of a [`Gpu`](crate::environment::Gpu)
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Gpu {
+    /// The PCI vendor id
+    #[prost(string, tag = "1")]
+    pub vendor_id: String,
+    /// The PCI device id
+    #[prost(string, optional, tag = "2")]
+    pub device_id: Option<String>,
+    /// The kernel driver bound to the device
+    #[prost(string, optional, tag = "3")]
+    pub driver: Option<String>,
+    /// The version of that driver
+    #[prost(string, optional, tag = "4")]
+    pub driver_version: Option<String>,
+}
+
+/// Protobuf form of [`Power`](crate::environment::Power)
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Power {
+    /// Whether the machine ran on battery
+    #[prost(bool, optional, tag = "1")]
+    pub on_battery: Option<bool>,
+    /// Charge of the battery in percent
+    #[prost(uint32, optional, tag = "2")]
+    pub battery_percent: Option<u32>,
+    /// The hottest thermal zone, in millidegrees Celsius
+    #[prost(int32, optional, tag = "3")]
+    pub max_temperature: Option<i32>,
+    /// How often the CPUs were throttled for heat since boot
+    #[prost(uint64, optional, tag = "4")]
+    pub throttle_count: Option<u64>,
+}
+
+/// Protobuf form of a [`report::Location`]
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Location {
+    /// The source file, as reported by the compiler
+    #[prost(string, tag = "1")]
+    pub file: String,
+    /// The line within the file
+    #[prost(uint32, tag = "2")]
+    pub line: u32,
+}
+
+/// Protobuf form of a [`report::Frame`]
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Frame {
+    /// The instruction pointer of the frame
+    #[prost(uint64, tag = "1")]
+    pub ip: u64,
+    /// The symbols resolved for the instruction pointer
+    #[prost(message, repeated, tag = "2")]
+    pub symbols: Vec<Symbol>,
+}
+
+/// Protobuf form of a [`report::Symbol`]
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Symbol {
+    /// The demangled name of the function, if known
+    #[prost(string, optional, tag = "1")]
+    pub name: Option<String>,
+    /// The source file of the function, if known
+    #[prost(string, optional, tag = "2")]
+    pub file: Option<String>,
+    /// The line within the source file, if known
+    #[prost(uint32, optional, tag = "3")]
+    pub line: Option<u32>,
+}
+
+/// A report together with its ed25519 signature
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SignedReport {
+    /// The encoded [`Report`] message
+    #[prost(bytes = "vec", tag = "1")]
+    pub report: Vec<u8>,
+    /// The signature of `report`
+    #[prost(bytes = "vec", tag = "2")]
+    pub signature: Vec<u8>,
+}
+
+impl From<&report::Report> for Report {
+    fn from(report: &report::Report) -> Self {
+        Report {
+            name: report.name.clone(),
+            version: report.version.clone(),
+            channel: report.channel.to_string(),
+            cause: report.cause.clone(),
+            location: report.location.as_ref().map(|l| Location {
+                file: l.file.clone(),
+                line: l.line,
+            }),
+            permalink: report.permalink.clone(),
+            backtrace: report.backtrace.iter().map(Frame::from).collect(),
+            idle_secs: report.idle_secs,
+            terminal: Some(Terminal {
+                term: report.terminal.term.clone(),
+                colorterm: report.terminal.colorterm.clone(),
+                columns: report.terminal.size.map(|(c, _)| c.into()),
+                rows: report.terminal.size.map(|(_, r)| r.into()),
+                stdin_tty: report.terminal.stdin_tty,
+                stdout_tty: report.terminal.stdout_tty,
+                stderr_tty: report.terminal.stderr_tty,
+            }),
+            locale: Some(Locale {
+                variables: report.locale.variables.iter().cloned().collect(),
+                codepage: report.locale.codepage,
+            }),
+            gpus: report
+                .gpus
+                .iter()
+                .map(|gpu| Gpu {
+                    vendor_id: gpu.vendor_id.clone(),
+                    device_id: gpu.device_id.clone(),
+                    driver: gpu.driver.clone(),
+                    driver_version: gpu.driver_version.clone(),
+                })
+                .collect(),
+            power: Some(Power {
+                on_battery: report.power.on_battery,
+                battery_percent: report.power.battery_percent.map(u32::from),
+                max_temperature: report.power.max_temperature,
+                throttle_count: report.power.throttle_count,
+            }),
+            build_id: report.build_id.clone(),
+            symbols_timed_out: report.symbols_timed_out,
+            fingerprint: report.fingerprint(),
+            session_id: report.session_id.clone(),
+            operating_system: report.operating_system.clone(),
+            timestamp: report.timestamp.clone(),
+            #[cfg(feature = "state")]
+            state: report
+                .state
+                .iter()
+                .map(|(name, value)| (name.clone(), value.to_string()))
+                .collect(),
+            #[cfg(not(feature = "state"))]
+            state: Default::default(),
+            ffi_boundary: report.ffi_boundary.clone(),
+            nested: report.nested.as_ref().map(|nested| NestedPanic {
+                original: nested.original.clone(),
+                in_drop: nested.in_drop.clone(),
+            }),
+            schema_version: report.schema_version,
+            plugin: report.plugin.as_ref().map(|plugin| Plugin {
+                name: plugin.name.clone(),
+                version: plugin.version.clone(),
+                path: plugin.path.display().to_string(),
+                issues: plugin.issues.clone(),
+            }),
+        }
+    }
+}
+
+impl From<&report::Frame> for Frame {
+    fn from(frame: &report::Frame) -> Self {
+        Frame {
+            ip: frame.ip as u64,
+            symbols: frame
+                .symbols
+                .iter()
+                .map(|symbol| Symbol {
+                    name: symbol.name.clone(),
+                    file: symbol.file.as_ref().map(|f| f.display().to_string()),
+                    line: symbol.line,
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,792 @@
+//! Structured description of a crash
+//!
+//! A [`Report`] captures everything we know about a panic once, so it can be
+//! rendered for the log file or serialized into other formats afterwards.
+
+use crate::assertion::Assertion;
+use crate::environment::{Gpu, Locale, Power, Terminal};
+use crate::format::{ReportFormat, ReportNaming, ReportSerializer};
+use crate::nested::{self, NestedPanic};
+use crate::permissions::Permissions;
+use crate::plugin::{self, Plugin};
+#[cfg(feature = "backtrace")]
+use crate::symbol_cache::SymbolCache;
+use crate::{Channel, Metadata};
+use core::mem;
+use std::fmt::{self, Write};
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "backtrace")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "backtrace")]
+use std::sync::{mpsc, Arc};
+#[cfg(feature = "backtrace")]
+use std::thread;
+#[cfg(feature = "backtrace")]
+use std::time::{Duration, Instant};
+
+/// Everything we know about a single panic
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report {
+    /// The version of the report layout, see [`Report::SCHEMA_VERSION`]
+    pub schema_version: u32,
+    /// The name of the crate that panicked
+    pub name: String,
+    /// The version of the crate that panicked
+    pub version: String,
+    /// The release channel the crate was built for
+    pub channel: Channel,
+    /// The operating system, see
+    /// [`operating_system`](crate::environment::operating_system)
+    pub operating_system: String,
+    /// When the panic happened, in RFC 3339 format
+    pub timestamp: String,
+    /// The build id, if the build set one
+    pub build_id: Option<String>,
+    /// The session the crash happened in, see
+    /// [`session_id`](crate::session_id)
+    pub session_id: Option<String>,
+    /// The panic message
+    pub cause: String,
+    /// Where in the source code the panic happened, if known
+    pub location: Option<Location>,
+    /// Link to the panic location in the source repository, if known
+    pub permalink: Option<String>,
+    /// Seconds between the last user action recorded with
+    /// [`note_activity`](crate::note_activity) and the panic
+    pub idle_secs: Option<u64>,
+    /// The FFI boundary the panic was stopped at, see
+    /// [`ffi_boundary`](crate::ffi_boundary)
+    pub ffi_boundary: Option<String>,
+    /// The earlier panics, if the process aborted because a destructor
+    /// panicked while unwinding
+    pub nested: Option<NestedPanic>,
+    /// The registered [`Plugin`](crate::Plugin) the innermost frames
+    /// belong to, if the panic passed through one
+    pub plugin: Option<Plugin>,
+    /// The terminal the application ran in
+    pub terminal: Terminal,
+    /// The locale the application ran with
+    pub locale: Locale,
+    /// The graphics adapters of the machine, with the `gpu-info` feature
+    pub gpus: Vec<Gpu>,
+    /// Power and thermal state, with the `power-info` feature
+    pub power: Power,
+    /// The stack frames leading up to the panic
+    pub backtrace: Vec<Frame>,
+    /// Whether resolving symbols took too long, leaving some frames
+    /// unresolved
+    pub symbols_timed_out: bool,
+    /// Snapshots of the registered
+    /// [`StateProvider`](crate::StateProvider)s, by section name
+    #[cfg(feature = "state")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub state: std::collections::BTreeMap<String, toml::Value>,
+}
+
+/// A position in a source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Location {
+    /// The source file, as reported by the compiler
+    pub file: String,
+    /// The line within the file
+    pub line: u32,
+}
+
+/// A single frame of a backtrace
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Frame {
+    /// The instruction pointer of the frame
+    pub ip: usize,
+    /// The symbols resolved for the instruction pointer; there can be
+    /// several of them if functions were inlined
+    pub symbols: Vec<Symbol>,
+}
+
+/// A resolved symbol of a backtrace frame
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Symbol {
+    /// The demangled name of the function, if known
+    pub name: Option<String>,
+    /// The source file of the function, if debug info is available
+    pub file: Option<PathBuf>,
+    /// The line within the source file, if debug info is available
+    pub line: Option<u32>,
+}
+
+impl Report {
+    /// The version of the report layout written by this crate
+    ///
+    /// It goes up whenever fields of report files are renamed, removed or
+    /// change their meaning, so tools reading reports can tell old layouts
+    /// from new ones. Reports written before versioning count as 0.
+    pub const SCHEMA_VERSION: u32 = 1;
+
+    /// Capture a report for a panic, including the current backtrace
+    pub fn new(panic_info: &PanicHookInfo, meta: &Metadata) -> Self {
+        let location = panic_info.location().map(|l| Location {
+            file: l.file().to_string(),
+            line: l.line(),
+        });
+        let permalink = location
+            .as_ref()
+            .and_then(|l| meta.permalink(&l.file, l.line));
+
+        let (backtrace, symbols_timed_out) = capture_backtrace();
+        let plugin = plugin::find(&backtrace);
+
+        let mut report = Report {
+            schema_version: Report::SCHEMA_VERSION,
+            name: meta.name.to_string(),
+            version: meta.version.to_string(),
+            channel: meta.channel,
+            operating_system: crate::environment::operating_system(),
+            timestamp: crate::clock::DateTime::now().rfc3339(),
+            build_id: Some(meta.build_id.to_string())
+                .filter(|build_id| !build_id.is_empty()),
+            session_id: crate::session_id().map(String::from),
+            cause: panic_message(panic_info)
+                .unwrap_or_else(|| "Unknown".into()),
+            location,
+            permalink,
+            idle_secs: crate::activity::seconds_since_activity(),
+            ffi_boundary: crate::ffi::current_boundary().map(String::from),
+            nested: None,
+            plugin,
+            terminal: Terminal::capture(),
+            locale: Locale::capture(),
+            gpus: Gpu::capture(),
+            power: Power::capture(),
+            backtrace,
+            symbols_timed_out,
+            #[cfg(feature = "state")]
+            state: Default::default(),
+        };
+        report.nested = nested::record(&report.cause, report.summary());
+        report
+    }
+
+    /// A single line describing the crash, such as
+    /// `myapp 1.2.3 panicked at src/io.rs:88: index out of bounds`
+    ///
+    /// Only the first line of the panic message is used, so the summary is
+    /// fit for titles and line-oriented logs. If a destructor panicked while
+    /// unwinding, the summary describes the original panic.
+    pub fn summary(&self) -> String {
+        const MAX_CAUSE_CHARS: usize = 200;
+
+        if let Some(nested) = &self.nested {
+            return format!(
+                "{}, then a destructor panicked while unwinding",
+                nested.original
+            );
+        }
+
+        let mut cause = self.cause.lines().next().unwrap_or_default();
+        if let Some((idx, _)) = cause.char_indices().nth(MAX_CAUSE_CHARS) {
+            cause = &cause[..idx];
+        }
+
+        match &self.location {
+            Some(location) => format!(
+                "{} {} panicked at {}:{}: {}",
+                self.name, self.version, location.file, location.line, cause
+            ),
+            None => {
+                format!("{} {} panicked: {}", self.name, self.version, cause)
+            }
+        }
+    }
+
+    /// A short id that is the same for repeated crashes of the same bug
+    ///
+    /// It covers the crate name and version, the panic location, the first
+    /// line of the panic message and the function names of the innermost
+    /// frames outside the standard library, but no addresses, which change
+    /// with every run.
+    pub fn fingerprint(&self) -> String {
+        const FRAMES: usize = 5;
+
+        let mut hash = Fnv1a::new();
+        let mut feed = |part: &str| {
+            hash.feed(part.as_bytes());
+            hash.feed(&[0]);
+        };
+
+        feed(&self.name);
+        feed(&self.version);
+        if let Some(location) = &self.location {
+            feed(&location.file);
+            feed(&location.line.to_string());
+        }
+        feed(self.cause.lines().next().unwrap_or_default());
+        let names = self
+            .backtrace
+            .iter()
+            .flat_map(|frame| frame.symbols.iter())
+            .filter_map(|symbol| symbol.name.as_deref())
+            .filter(|name| {
+                !["std", "core", "alloc", "__rust"]
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix))
+            })
+            .take(FRAMES);
+        for name in names {
+            feed(name);
+        }
+
+        format!("{:016x}", hash.finish())
+    }
+
+    /// Cut a panic message longer than `max_chars` characters down to
+    /// that, e.g. one holding a whole serialized document
+    ///
+    /// The cut is marked with the length and a hash of the whole message,
+    /// so crashes with the same long message still get the same
+    /// [`fingerprint`](Report::fingerprint), and different ones don't.
+    pub fn truncate_cause(&mut self, max_chars: usize) {
+        let idx = match self.cause.char_indices().nth(max_chars) {
+            Some((idx, _)) => idx,
+            None => return,
+        };
+        let mut hash = Fnv1a::new();
+        hash.feed(self.cause.as_bytes());
+        // on the same line, as the fingerprint covers the first one
+        let marker = format!(
+            "... ({} bytes in all, hash {:016x})",
+            self.cause.len(),
+            hash.finish()
+        );
+        self.cause.truncate(idx);
+        self.cause.push_str(&marker);
+    }
+
+    /// Render the report for the log file, keeping at most `max_lines`
+    /// lines of the panic message
+    pub(crate) fn render(&self, max_lines: Option<usize>) -> String {
+        let mut expl = String::new();
+
+        match &self.location {
+            Some(location) => {
+                expl.push_str(&format!(
+                    "Panic occurred in file '{}' at line {}\n",
+                    location.file, location.line
+                ));
+                if let Some(permalink) = &self.permalink {
+                    expl.push_str(&format!("Source: {}\n", permalink));
+                }
+            }
+            None => expl.push_str("Panic location unknown.\n"),
+        }
+        expl.push_str(&format!("Release channel: {}\n", self.channel));
+        expl.push_str(&format!(
+            "Operating system: {}\n",
+            self.operating_system
+        ));
+        expl.push_str(&format!("Time: {}\n", self.timestamp));
+        expl.push_str(&format!("Fingerprint: {}\n", self.fingerprint()));
+        if let Some(session_id) = &self.session_id {
+            expl.push_str(&format!("Session: {}\n", session_id));
+        }
+        if let Some(build_id) = &self.build_id {
+            expl.push_str(&format!("Build ID: {}\n", build_id));
+        }
+        if let Some(idle_secs) = self.idle_secs {
+            expl.push_str(&format!(
+                "Last user action: {} seconds before the crash\n",
+                idle_secs
+            ));
+        }
+        if let Some(boundary) = &self.ffi_boundary {
+            expl.push_str(&format!(
+                "Crossed FFI boundary: panic stopped in `{}`\n",
+                boundary
+            ));
+        }
+        if let Some(plugin) = &self.plugin {
+            expl.push_str(&format!(
+                "Plugin: {} {} ({})\n",
+                plugin.name,
+                plugin.version,
+                plugin.path.display()
+            ));
+        }
+        expl.push_str(&format!("Terminal: {}\n", self.terminal));
+        expl.push_str(&format!("Locale: {}\n", self.locale));
+        for gpu in &self.gpus {
+            expl.push_str(&format!("GPU: {}\n", gpu));
+        }
+        if !self.power.is_empty() {
+            expl.push_str(&format!("Power: {}\n", self.power));
+        }
+
+        if let Some(nested) = &self.nested {
+            expl.push_str(&format!(
+                "Panicked while panicking: a destructor panicked during \
+                 unwinding, so the process aborted\n\
+                 Original panic: {}\n\
+                 Panic in destructor: {}\n",
+                nested.original, nested.in_drop
+            ));
+        }
+
+        let cause = match Assertion::parse(&self.cause) {
+            Some(assertion) => assertion.to_block(),
+            None => self.cause.clone(),
+        };
+
+        #[allow(unused_mut)]
+        let mut rendered = format!(
+            "{}\n{}\n{}",
+            expl,
+            format_cause(&cause, max_lines),
+            self.format_backtrace()
+        );
+        #[cfg(feature = "state")]
+        if !self.state.is_empty() {
+            rendered.push_str("\n\n");
+            rendered.push_str(&crate::state::to_toml(&self.state));
+        }
+        rendered
+    }
+
+    pub(crate) fn format_backtrace(&self) -> String {
+        //We take padding for address and extra two letters
+        //to padd after index.
+        const HEX_WIDTH: usize = mem::size_of::<usize>() + 2;
+        //Padding for next lines after frame's address
+        const NEXT_SYMBOL_PADDING: usize = HEX_WIDTH + 6;
+
+        if cfg!(not(feature = "backtrace")) {
+            return "\nstack backtrace: <not captured in this build>".into();
+        }
+        // Targets without unwinding support in the backtrace library (or
+        // binaries stripped of unwind info) yield no frames at all
+        if self.backtrace.is_empty() {
+            return "\nstack backtrace: <unavailable on this platform>".into();
+        }
+
+        let mut backtrace = String::new();
+
+        //Here we iterate over backtrace frames
+        //(each corresponds to function's stack)
+        //We need to print its address
+        //and symbol(e.g. function name),
+        //if it is available
+        for (idx, frame) in self.backtrace.iter().enumerate() {
+            let ip = frame.ip as *const u8;
+            let _ = write!(backtrace, "\n{:4}: {:2$?}", idx, ip, HEX_WIDTH);
+
+            if frame.symbols.is_empty() {
+                let _ = write!(backtrace, " - <unresolved>");
+                continue;
+            }
+
+            for (idx, symbol) in frame.symbols.iter().enumerate() {
+                //Print symbols from this address,
+                //if there are several addresses
+                //we need to put it on next line
+                if idx != 0 {
+                    let _ =
+                        write!(backtrace, "\n{:1$}", "", NEXT_SYMBOL_PADDING);
+                }
+
+                if let Some(name) = &symbol.name {
+                    let _ = write!(backtrace, " - {}", name);
+                } else {
+                    let _ = write!(backtrace, " - <unknown>");
+                }
+
+                //See if there is debug information with file name and line
+                if let (Some(file), Some(line)) = (&symbol.file, symbol.line) {
+                    let _ = write!(
+                        backtrace,
+                        "\n{:3$}at {}:{}",
+                        "",
+                        file.display(),
+                        line,
+                        NEXT_SYMBOL_PADDING
+                    );
+                }
+            }
+        }
+
+        if self.symbols_timed_out {
+            let _ = write!(
+                backtrace,
+                "\n      (resolving symbols took too long, the remaining \
+                 frames are unresolved)"
+            );
+        }
+
+        format!("\nstack backtrace:{}", backtrace)
+    }
+}
+
+impl Report {
+    /// Render the report as a TOML document
+    pub fn to_toml(&self) -> String {
+        crate::format::to_toml(self)
+    }
+
+    /// Render the report as a Markdown document, ready to paste into a
+    /// GitHub issue
+    pub fn to_markdown(&self) -> String {
+        crate::format::to_markdown(self)
+    }
+
+    /// Render the report as a JSON document
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        crate::format::to_json(self)
+    }
+
+    /// Write the report as a new TOML file into `dir`, returning its path
+    ///
+    /// Files are named `report-<uuid>.toml`, so reports never overwrite
+    /// each other.
+    pub fn write_file<P: AsRef<Path>>(&self, dir: P) -> io::Result<PathBuf> {
+        self.write_file_as(dir, &ReportFormat::Toml)
+    }
+
+    /// Write the report as a new file into `dir` with `serializer`,
+    /// returning its path
+    ///
+    /// Files are named `report-<uuid>.<extension>`, see
+    /// [`ReportSerializer::extension`]. The report is written to a hidden
+    /// temporary file first and renamed once complete, so a file under the
+    /// final name is never a partial report.
+    pub fn write_file_as<P, S>(
+        &self,
+        dir: P,
+        serializer: &S,
+    ) -> io::Result<PathBuf>
+    where
+        P: AsRef<Path>,
+        S: ReportSerializer + ?Sized,
+    {
+        self.write_file_with(
+            dir,
+            serializer,
+            &ReportNaming::Uuid,
+            Permissions::default(),
+            false,
+        )
+    }
+
+    /// Write the report like [`Report::write_file_as`], naming the file
+    /// with `naming` and creating it with `permissions`
+    ///
+    /// With `durable`, the file is flushed to disk before it is renamed,
+    /// so it survives a power loss, see
+    /// [`Builder::durable_writes`](crate::Builder::durable_writes).
+    pub(crate) fn write_file_with<P, S>(
+        &self,
+        dir: P,
+        serializer: &S,
+        naming: &ReportNaming,
+        permissions: Permissions,
+        durable: bool,
+    ) -> io::Result<PathBuf>
+    where
+        P: AsRef<Path>,
+        S: ReportSerializer + ?Sized,
+    {
+        let dir = dir.as_ref();
+        let stem = naming.stem(self);
+        let extension = serializer.extension();
+        // timestamps and custom names can repeat, and other users' files
+        // can be in the way in a shared directory, but an earlier report
+        // must never be replaced
+        let mut count = 0;
+        let (path, temp, mut file) = loop {
+            count += 1;
+            let name = match count {
+                1 => format!("{}.{}", stem, extension),
+                _ => format!("{}-{}.{}", stem, count, extension),
+            };
+            let path = dir.join(&name);
+            if fs::symlink_metadata(crate::long_path::for_io(&path)).is_ok() {
+                continue;
+            }
+            let temp = dir.join(format!(".{}.tmp", name));
+            match permissions
+                .apply(OpenOptions::new().write(true).create_new(true))
+                .open(crate::long_path::for_io(&temp))
+            {
+                Ok(file) => break (path, temp, file),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e),
+            }
+        };
+
+        let written = (|| {
+            serializer.write(self, &mut file)?;
+            if durable {
+                file.sync_all()?;
+            }
+            fs::rename(
+                crate::long_path::for_io(&temp),
+                crate::long_path::for_io(&path),
+            )
+        })();
+        if let Err(e) = written {
+            let _ = fs::remove_file(crate::long_path::for_io(&temp));
+            return Err(e);
+        }
+        Ok(path)
+    }
+}
+
+#[cfg(feature = "prost")]
+impl Report {
+    /// Encode the report as protobuf, following `proto/report.proto`
+    pub fn to_protobuf(&self) -> Vec<u8> {
+        use prost::Message;
+
+        crate::proto::Report::from(self).encode_to_vec()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(None))
+    }
+}
+
+/// How long symbol resolution may take before the remaining frames are
+/// reported unresolved, keeping within the crash path's latency budget
+#[cfg(feature = "backtrace")]
+const RESOLVE_DEADLINE: Duration = Duration::from_millis(200);
+
+/// How many frames are resolved between checks whether we still wait for
+/// them
+#[cfg(feature = "backtrace")]
+const RESOLVE_CHUNK: usize = 8;
+
+/// Capture the frames leading up to the panic, and whether resolving their
+/// symbols ran out of time
+#[cfg(feature = "backtrace")]
+fn capture_backtrace() -> (Vec<Frame>, bool) {
+    let frames = backtrace::Backtrace::new_unresolved().frames().to_vec();
+    let ips: Vec<usize> = frames.iter().map(|f| f.ip() as usize).collect();
+
+    // only the frames earlier crashes didn't have are resolved
+    let cache = SymbolCache::open();
+    let cached: Vec<Option<Vec<Symbol>>> = ips
+        .iter()
+        .map(|&ip| cache.as_ref().and_then(|cache| cache.get(ip)))
+        .collect();
+    let uncached = frames
+        .into_iter()
+        .zip(&cached)
+        .filter(|(_, cached)| cached.is_none())
+        .map(|(frame, _)| frame)
+        .collect();
+    let (resolved, complete) = resolve_symbols(uncached);
+    let mut resolved = resolved.into_iter();
+    let symbols: Vec<Option<Vec<Symbol>>> = cached
+        .into_iter()
+        .map(|cached| cached.or_else(|| resolved.next().flatten()))
+        .collect();
+    if let Some(cache) = &cache {
+        cache.save(ips.iter().zip(&symbols).filter_map(|(&ip, symbols)| {
+            Some((ip, symbols.as_deref()?))
+        }));
+    }
+
+    let frames: Vec<Frame> = ips
+        .into_iter()
+        .zip(symbols)
+        .map(|(ip, symbols)| Frame {
+            ip,
+            symbols: symbols.unwrap_or_default(),
+        })
+        .collect();
+    let skip = hook_frames(&frames);
+    (frames.into_iter().skip(skip).collect(), !complete)
+}
+
+#[cfg(not(feature = "backtrace"))]
+fn capture_backtrace() -> (Vec<Frame>, bool) {
+    (Vec::new(), false)
+}
+
+/// Resolve the symbols of every frame on a separate thread, giving up on
+/// the frames that aren't done by the deadline
+///
+/// This is one thread rather than a pool: the backtrace library serializes
+/// resolution behind a global lock, so more threads wouldn't finish sooner,
+/// and the thread only lets us stop waiting. Cold symbol caches (notably on
+/// Windows) can otherwise take seconds for deep backtraces.
+///
+/// The thread resolves the frames in chunks of [`RESOLVE_CHUNK`] and hands
+/// each frame over as soon as it is done, so the frames resolved by the
+/// deadline are kept. Once we stop waiting, it stops at the end of the
+/// chunk instead of holding on to the library's lock for the rest.
+#[cfg(feature = "backtrace")]
+fn resolve_symbols(
+    frames: Vec<backtrace::BacktraceFrame>,
+) -> (Vec<Option<Vec<Symbol>>>, bool) {
+    let count = frames.len();
+    let (tx, rx) = mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let worker = frames.clone();
+    let stopped = Arc::clone(&stop);
+    let spawned = thread::Builder::new()
+        .name("human-panic-symbols".into())
+        .spawn(move || {
+            let mut frames = worker.into_iter().enumerate();
+            while !stopped.load(Ordering::Relaxed) {
+                let mut chunk = frames.by_ref().take(RESOLVE_CHUNK).peekable();
+                if chunk.peek().is_none() {
+                    break;
+                }
+                for (idx, mut frame) in chunk {
+                    frame.resolve();
+                    if tx.send((idx, symbols_of(&frame))).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+    if spawned.is_err() {
+        // e.g. targets without threads; resolve here without a deadline
+        let symbols = frames
+            .into_iter()
+            .map(|mut frame| {
+                frame.resolve();
+                Some(symbols_of(&frame))
+            })
+            .collect();
+        return (symbols, true);
+    }
+
+    let deadline = Instant::now() + RESOLVE_DEADLINE;
+    let mut symbols = vec![None; count];
+    let mut remaining = count;
+    while remaining > 0 {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(timeout) {
+            Ok((idx, resolved)) => {
+                symbols[idx] = Some(resolved);
+                remaining -= 1;
+            }
+            Err(_) => break,
+        }
+    }
+    stop.store(true, Ordering::Relaxed);
+    (symbols, remaining == 0)
+}
+
+#[cfg(feature = "backtrace")]
+fn symbols_of(frame: &backtrace::BacktraceFrame) -> Vec<Symbol> {
+    frame
+        .symbols()
+        .iter()
+        .map(|symbol| Symbol {
+            name: symbol.name().map(|n| n.to_string()),
+            file: symbol.filename().map(PathBuf::from),
+            line: symbol.lineno(),
+        })
+        .collect()
+}
+
+/// Count the frames belonging to the backtrace library, this crate and the
+/// hook closure, so the backtrace starts where the panic was raised
+///
+/// Depending on inlining a different number of frames lies between us and
+/// the runtime's `panic_with_hook`, so we look for it by name. If it can't
+/// be found (e.g. without symbols), nothing is skipped.
+#[cfg(feature = "backtrace")]
+fn hook_frames(frames: &[Frame]) -> usize {
+    frames
+        .iter()
+        .position(|frame| {
+            frame.symbols.iter().any(|symbol| {
+                symbol
+                    .name
+                    .as_ref()
+                    .is_some_and(|n| n.contains("panic_with_hook"))
+            })
+        })
+        .map(|idx| idx + 1)
+        .unwrap_or(0)
+}
+
+/// Extract the message a panic was raised with, if it has one
+fn panic_message(panic_info: &PanicHookInfo) -> Option<String> {
+    #[cfg(feature = "nightly")]
+    {
+        panic_info.message().map(|m| format!("{}", m))
+    }
+
+    #[cfg(not(feature = "nightly"))]
+    match (
+        panic_info.payload().downcast_ref::<&str>(),
+        panic_info.payload().downcast_ref::<String>(),
+    ) {
+        (Some(s), _) => Some(s.to_string()),
+        (_, Some(s)) => Some(s.to_string()),
+        (None, None) => None,
+    }
+}
+
+/// FNV-1a, which stays stable across Rust versions unlike `Hash`
+pub(crate) struct Fnv1a(u64);
+
+impl Fnv1a {
+    pub(crate) fn new() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn feed(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Indent every line of the panic message so that multi-line messages
+/// (e.g. pretty-printed asserts) stay aligned in the log
+fn format_cause(cause: &str, max_lines: Option<usize>) -> String {
+    const INDENT: &str = "   ";
+
+    let lines: Vec<&str> = cause.lines().collect();
+    let shown = match max_lines {
+        Some(max) => lines.len().min(max),
+        None => lines.len(),
+    };
+
+    let mut formatted = String::new();
+    for line in &lines[..shown] {
+        if !formatted.is_empty() {
+            formatted.push('\n');
+        }
+        formatted.push_str(INDENT);
+        formatted.push_str(line);
+    }
+
+    if shown < lines.len() {
+        let _ = write!(
+            formatted,
+            "\n{}... ({} more lines truncated)",
+            INDENT,
+            lines.len() - shown
+        );
+    }
+
+    formatted
+}
@@ -0,0 +1,231 @@
+//! One report per distinct crash
+//!
+//! With [`Builder::rollup`](crate::Builder::rollup), a crash whose
+//! [fingerprint](crate::Report::fingerprint) was seen before doesn't get a
+//! report file of its own. The crash message points to the report of its
+//! first occurrence instead, and `rollup.toml` in the report directory
+//! counts how often each crash happened and when:
+//!
+//! ```toml
+//! [51e23aef88c2d024]
+//! summary = "app 1.2.0 panicked at src/main.rs:8: oh no"
+//! report = "report-4cc0fa49-9ff7-402a-84c2-4eb5acb3ba28.toml"
+//! count = 3
+//! first_seen = "2024-05-01T12:00:00+02:00"
+//! last_seen = "2024-05-03T09:30:12+02:00"
+//! ```
+
+use crate::permissions::Permissions;
+use crate::Report;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+
+/// The name of the rollup file in the report directory
+const FILE_NAME: &str = "rollup.toml";
+
+/// How often a crash happened, from the rollup file
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Occurrences {
+    /// The crash's [fingerprint](crate::Report::fingerprint)
+    pub fingerprint: String,
+    /// The [summary](crate::Report::summary) of its first occurrence
+    pub summary: String,
+    /// The report file of its first occurrence
+    pub report: PathBuf,
+    /// How often it happened
+    pub count: u64,
+    /// When it happened first, in RFC 3339 format
+    pub first_seen: String,
+    /// When it happened last, in RFC 3339 format
+    pub last_seen: String,
+}
+
+/// How often each crash whose report files are in `dir` happened, see
+/// [`Builder::rollup`](crate::Builder::rollup)
+///
+/// Crashes are in the order they first happened. A directory without a
+/// rollup file has none.
+///
+/// ```no_run
+/// use human_panic_logger::read_rollup;
+///
+/// for crash in read_rollup("/var/log/app/crashes")? {
+///     println!("{}x {}", crash.count, crash.summary);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn read_rollup(dir: impl AsRef<Path>) -> io::Result<Vec<Occurrences>> {
+    let file = dir.as_ref().join(FILE_NAME);
+    match fs::read_to_string(crate::long_path::for_io(&file)) {
+        Ok(text) => Ok(parse(dir.as_ref(), &text)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Count `report` in the rollup file in `dir`, writing its report file with
+/// `write` unless the crash was seen before and its report file is still
+/// there, and return the report file
+///
+/// Two processes crashing at the same moment can lose a count, but never
+/// a report file.
+pub(crate) fn record(
+    dir: &Path,
+    report: &Report,
+    permissions: Permissions,
+    write: impl FnOnce() -> io::Result<PathBuf>,
+) -> io::Result<PathBuf> {
+    // an unreadable rollup file starts over rather than losing the report
+    let mut crashes = read_rollup(dir).unwrap_or_default();
+    let fingerprint = report.fingerprint();
+    let seen = crashes
+        .iter_mut()
+        .find(|crash| crash.fingerprint == fingerprint)
+        .filter(|crash| crash.report.is_file());
+    let file = match seen {
+        Some(crash) => {
+            crash.count += 1;
+            crash.last_seen = report.timestamp.clone();
+            crash.report.clone()
+        }
+        None => {
+            let file = write()?;
+            crashes.retain(|crash| crash.fingerprint != fingerprint);
+            crashes.push(Occurrences {
+                fingerprint,
+                summary: report.summary(),
+                report: file.clone(),
+                count: 1,
+                first_seen: report.timestamp.clone(),
+                last_seen: report.timestamp.clone(),
+            });
+            file
+        }
+    };
+    if let Err(e) = store(dir, &crashes, permissions) {
+        log::warn!("Could not update the crash rollup: {}", e);
+    }
+    Ok(file)
+}
+
+/// Write `crashes` to the rollup file in `dir`, replacing it as a whole
+fn store(
+    dir: &Path,
+    crashes: &[Occurrences],
+    permissions: Permissions,
+) -> io::Result<()> {
+    let mut text = String::from(
+        "# How often each crash happened, by fingerprint, written by \
+         human-panic\n",
+    );
+    for crash in crashes {
+        let report = crash.report.file_name().unwrap_or_default();
+        let _ = write!(
+            text,
+            "\n[{}]\nsummary = {}\nreport = {}\ncount = {}\n\
+             first_seen = {}\nlast_seen = {}\n",
+            crash.fingerprint,
+            crate::format::string(&crash.summary),
+            crate::format::string(&report.to_string_lossy()),
+            crash.count,
+            crate::format::string(&crash.first_seen),
+            crate::format::string(&crash.last_seen),
+        );
+    }
+
+    let file = dir.join(FILE_NAME);
+    let temp = dir.join(format!(".{}.tmp", FILE_NAME));
+    let written = (|| {
+        permissions
+            .apply(OpenOptions::new().write(true).create(true).truncate(true))
+            .open(crate::long_path::for_io(&temp))?
+            .write_all(text.as_bytes())?;
+        fs::rename(
+            crate::long_path::for_io(&temp),
+            crate::long_path::for_io(&file),
+        )
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(crate::long_path::for_io(&temp));
+    }
+    written
+}
+
+/// The crashes in `text`, the rollup file in `dir`
+///
+/// This only reads the layout [`store`] writes; anything else is skipped.
+fn parse(dir: &Path, text: &str) -> Vec<Occurrences> {
+    let mut crashes: Vec<Occurrences> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(fingerprint) =
+            line.strip_prefix('[').and_then(|l| l.strip_suffix(']'))
+        {
+            crashes.push(Occurrences {
+                fingerprint: fingerprint.to_string(),
+                summary: String::new(),
+                report: PathBuf::new(),
+                count: 0,
+                first_seen: String::new(),
+                last_seen: String::new(),
+            });
+            continue;
+        }
+        let pair = line.split_once('=');
+        let (crash, (key, value)) = match (crashes.last_mut(), pair) {
+            (Some(crash), Some(pair)) => (crash, pair),
+            _ => continue,
+        };
+        let value = value.trim();
+        match key.trim() {
+            "count" => crash.count = value.parse().unwrap_or_default(),
+            key => {
+                let value = match unquote(value) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                match key {
+                    "summary" => crash.summary = value,
+                    "report" => crash.report = dir.join(value),
+                    "first_seen" => crash.first_seen = value,
+                    "last_seen" => crash.last_seen = value,
+                    _ => {}
+                }
+            }
+        }
+    }
+    crashes
+}
+
+/// The value of a TOML basic string as [`string`](crate::format::string)
+/// quotes it
+fn unquote(quoted: &str) -> Option<String> {
+    let inner = quoted.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '"' => value.push('"'),
+            '\\' => value.push('\\'),
+            'n' => value.push('\n'),
+            'r' => value.push('\r'),
+            't' => value.push('\t'),
+            'u' => {
+                let code: String = chars.by_ref().take(4).collect();
+                let code = u32::from_str_radix(&code, 16).ok()?;
+                value.push(char::from_u32(code)?);
+            }
+            _ => return None,
+        }
+    }
+    Some(value)
+}
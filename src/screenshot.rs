@@ -0,0 +1,136 @@
+//! A screenshot saved along with the crash report
+//!
+//! What was on screen is often the only clue to a rendering bug in a GUI
+//! application. With [`Builder::screenshot`](crate::Builder::screenshot),
+//! the hook captures the screen after the crash message is shown and saves
+//! it next to the report file, e.g. `report-<uuid>.toml.png`, but only if
+//! the application's consent check agrees: a screenshot shows whatever
+//! else is on the user's screen.
+//!
+//! The screen is captured by the platform's own tools, so the feature adds
+//! no dependencies: `screencapture` on macOS, PowerShell on Windows, and
+//! `grim` on Wayland or ImageMagick's `import` on X11.
+
+use crate::permissions::Permissions;
+use crate::Report;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long the capture may take before the screenshot is given up
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Ask the user on the terminal whether to save a screenshot with the
+/// report of `report`
+///
+/// Without a terminal, or if nobody answers within 30 seconds, no
+/// screenshot is taken. GUI applications usually ask in a dialog of their
+/// own instead.
+pub fn ask_screenshot_consent(report: &Report) -> bool {
+    let prompt = format!(
+        "\n{} crashed. Save a screenshot with the crash report? It shows \
+         everything on your screen, and stays on your machine unless you \
+         send it. [y]es, [n]o: ",
+        report.name
+    );
+    crate::consent::confirm(&prompt)
+}
+
+/// Capture the screen to a file next to `report_file`
+///
+/// Returns the screenshot's path, or `None` if there is no tool to capture
+/// the screen with or it failed.
+pub(crate) fn save(
+    report_file: &Path,
+    permissions: Permissions,
+) -> Option<PathBuf> {
+    let mut path = report_file.as_os_str().to_owned();
+    path.push(".png");
+    let path = PathBuf::from(path);
+    let mut command = capture(&path)?;
+
+    // created first, so the screenshot gets the permissions of reports
+    permissions
+        .apply(OpenOptions::new().write(true).create(true).truncate(true))
+        .open(crate::long_path::for_io(&path))
+        .ok()?;
+    let captured = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .is_ok_and(|mut child| {
+            let start = Instant::now();
+            loop {
+                match child.try_wait() {
+                    Ok(Some(status)) => return status.success(),
+                    Ok(None) if start.elapsed() < TIMEOUT => {
+                        std::thread::sleep(Duration::from_millis(50))
+                    }
+                    _ => {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        return false;
+                    }
+                }
+            }
+        });
+    let written = fs::metadata(crate::long_path::for_io(&path))
+        .is_ok_and(|meta| meta.len() > 0);
+    if captured && written {
+        Some(path)
+    } else {
+        let _ = fs::remove_file(crate::long_path::for_io(&path));
+        None
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn capture(path: &Path) -> Option<Command> {
+    let mut command = Command::new("screencapture");
+    // no shutter sound
+    command.arg("-x").arg(path);
+    Some(command)
+}
+
+#[cfg(windows)]
+fn capture(path: &Path) -> Option<Command> {
+    const SCRIPT: &str = "Add-Type -AssemblyName System.Windows.Forms,\
+        System.Drawing; \
+        $s = [System.Windows.Forms.SystemInformation]::VirtualScreen; \
+        $b = New-Object System.Drawing.Bitmap $s.Width, $s.Height; \
+        $g = [System.Drawing.Graphics]::FromImage($b); \
+        $g.CopyFromScreen($s.Left, $s.Top, 0, 0, $b.Size); \
+        $b.Save($env:HUMAN_PANIC_SCREENSHOT, \
+        [System.Drawing.Imaging.ImageFormat]::Png)";
+
+    let mut command = Command::new("powershell.exe");
+    // the path goes through the environment, so no quoting can break it
+    command
+        .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .env("HUMAN_PANIC_SCREENSHOT", path);
+    Some(command)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn capture(path: &Path) -> Option<Command> {
+    let set = |var| std::env::var_os(var).is_some_and(|v| !v.is_empty());
+    let mut command = if set("WAYLAND_DISPLAY") {
+        Command::new("grim")
+    } else if set("DISPLAY") {
+        let mut command = Command::new("import");
+        command.args(["-window", "root"]);
+        command
+    } else {
+        // e.g. an SSH session or a text console
+        return None;
+    };
+    command.arg(path);
+    Some(command)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn capture(_: &Path) -> Option<Command> {
+    None
+}
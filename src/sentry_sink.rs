@@ -0,0 +1,196 @@
+//! Forwarding crash reports to Sentry
+//!
+//! Nothing is uploaded unless the application registers a [`SentrySink`]
+//! with the [`Builder`](crate::Builder), and the user agrees, see
+//! [`UploadConsent`](crate::UploadConsent). The report file is written
+//! either way, so users can still send it in if the upload fails.
+
+use crate::clock::DateTime;
+use crate::sink::ReportSink;
+use crate::Report;
+use sentry::protocol::{
+    Event, Exception, Frame, Level, Map, Mechanism, Stacktrace, Value,
+};
+use sentry::types::{Dsn, ParseDsnError};
+use sentry::{Client, ClientOptions};
+use std::borrow::Cow;
+use std::error::Error;
+use std::time::{Duration, SystemTime};
+
+/// Sends every report as an event to a Sentry project
+///
+/// The event carries the panic message, the backtrace, the metadata of the
+/// report as tags and [`Report::fingerprint`] to group it by.
+///
+/// ```no_run
+/// use human_panic_logger::{metadata, Builder, SentrySink};
+///
+/// let sentry = SentrySink::new("https://key@o0.ingest.sentry.io/0")
+///     .expect("the DSN is valid")
+///     .environment("production")
+///     .tag("edition", "community");
+/// Builder::new(metadata!(), "app.log")
+///     .sink(sentry)
+///     .install()
+///     .expect("crash reporting could not be set up");
+/// ```
+pub struct SentrySink {
+    client: Client,
+    environment: Option<String>,
+    tags: Map<String, String>,
+    timeout: Duration,
+}
+
+impl SentrySink {
+    /// Send events to the project of `dsn`
+    pub fn new(dsn: &str) -> Result<Self, ParseDsnError> {
+        let dsn: Dsn = dsn.parse()?;
+        let client = Client::from(sentry::apply_defaults(ClientOptions {
+            dsn: Some(dsn),
+            default_integrations: false,
+            ..ClientOptions::default()
+        }));
+        Ok(SentrySink {
+            client,
+            environment: None,
+            tags: Map::new(),
+            // the hook's budget is 250 ms, but an upload needs the network
+            timeout: Duration::from_secs(2),
+        })
+    }
+
+    /// Set the environment events are filed under, e.g. `"production"`
+    pub fn environment(mut self, environment: impl Into<String>) -> Self {
+        self.environment = Some(environment.into());
+        self
+    }
+
+    /// Add a tag to every event
+    pub fn tag(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+
+    /// Wait at most `timeout` for an event to be sent, 2 seconds by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The event for `report`
+    fn event(&self, report: &Report) -> Event<'static> {
+        let mut tags = Map::new();
+        tags.insert("channel".into(), report.channel.to_string());
+        tags.insert("os".into(), report.operating_system.clone());
+        if let Some(build_id) = &report.build_id {
+            tags.insert("build_id".into(), build_id.clone());
+        }
+        if let Some(session_id) = &report.session_id {
+            tags.insert("session_id".into(), session_id.clone());
+        }
+        if let Some(plugin) = &report.plugin {
+            tags.insert("plugin".into(), plugin.name.clone());
+        }
+        tags.extend(self.tags.clone());
+
+        let mut extra = Map::new();
+        if let Some(location) = &report.location {
+            let location = format!("{}:{}", location.file, location.line);
+            extra.insert("location".into(), Value::String(location));
+        }
+        if let Some(permalink) = &report.permalink {
+            let permalink = Value::String(permalink.clone());
+            extra.insert("permalink".into(), permalink);
+        }
+        if let Some(idle_secs) = report.idle_secs {
+            extra.insert("idle_secs".into(), Value::from(idle_secs));
+        }
+
+        let release = format!("{}@{}", report.name, report.version);
+        let exception = Exception {
+            ty: "panic".into(),
+            value: Some(report.cause.clone()),
+            stacktrace: stacktrace(report),
+            mechanism: Some(Mechanism {
+                ty: "panic".into(),
+                handled: Some(false),
+                ..Mechanism::default()
+            }),
+            ..Exception::default()
+        };
+
+        Event {
+            level: Level::Fatal,
+            message: Some(report.summary()),
+            fingerprint: Cow::Owned(vec![report.fingerprint().into()]),
+            release: Some(release.into()),
+            environment: self.environment.clone().map(Into::into),
+            timestamp: timestamp(&report.timestamp),
+            exception: vec![exception].into(),
+            tags,
+            extra,
+            ..Event::default()
+        }
+    }
+}
+
+impl ReportSink for SentrySink {
+    fn uploads(&self) -> bool {
+        true
+    }
+
+    fn submit(
+        &self,
+        report: &Report,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.client.capture_event(self.event(report), None);
+        match self.client.flush(Some(self.timeout)) {
+            true => Ok(()),
+            false => Err("timed out sending the event to Sentry".into()),
+        }
+    }
+}
+
+/// The backtrace of `report` in Sentry's order, outermost frame first
+///
+/// Functions inlined into a frame get frames of their own, as Sentry shows
+/// them.
+fn stacktrace(report: &Report) -> Option<Stacktrace> {
+    let mut frames = Vec::new();
+    for frame in report.backtrace.iter().rev() {
+        let addr = Some((frame.ip as u64).into());
+        if frame.symbols.is_empty() {
+            frames.push(Frame {
+                instruction_addr: addr,
+                ..Frame::default()
+            });
+        }
+        for symbol in frame.symbols.iter().rev() {
+            frames.push(Frame {
+                function: symbol.name.clone(),
+                filename: symbol.file.as_ref().map(|f| f.display().to_string()),
+                lineno: symbol.line.map(u64::from),
+                instruction_addr: addr,
+                ..Frame::default()
+            });
+        }
+    }
+    match frames.is_empty() {
+        true => None,
+        false => Some(Stacktrace {
+            frames,
+            ..Stacktrace::default()
+        }),
+    }
+}
+
+/// The time of the crash, or now if the report's timestamp can't be read
+fn timestamp(timestamp: &str) -> SystemTime {
+    DateTime::parse(timestamp)
+        .map(DateTime::system_time)
+        .unwrap_or_else(SystemTime::now)
+}
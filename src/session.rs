@@ -0,0 +1,67 @@
+//! Session ids shared by a chain of restarted processes
+//!
+//! Every setup starts or continues a session, whose id is included in all
+//! reports. When a supervisor restarts the application after a crash, the
+//! new process continues the crashed one's session, so fleet operators can
+//! tell which crash preceded which. The session is kept in a file next to
+//! the log file; supervisors can also pass one explicitly in the
+//! `HUMAN_PANIC_SESSION` environment variable.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Marks a session whose process crashed, so the next one continues it
+const CRASHED: &str = "crashed";
+
+struct Session {
+    id: String,
+    file: PathBuf,
+}
+
+static SESSION: OnceLock<Session> = OnceLock::new();
+
+/// The id of the current session, once the hook is installed
+pub fn session_id() -> Option<&'static str> {
+    SESSION.get().map(|session| session.id.as_str())
+}
+
+/// Start a new session, or continue the crashed one recorded next to
+/// `log_file`
+pub(crate) fn start(log_file: &Path) {
+    let mut file = log_file.as_os_str().to_owned();
+    file.push(".session");
+    let file = PathBuf::from(file);
+
+    let id = std::env::var("HUMAN_PANIC_SESSION")
+        .ok()
+        .filter(|id| !id.is_empty())
+        .or_else(|| crashed_session(&file))
+        .unwrap_or_else(crate::id::random);
+
+    let _ = fs::write(&file, format!("{}\n", id));
+    let _ = SESSION.set(Session { id, file });
+}
+
+/// The session recorded in `file`, if its process crashed
+fn crashed_session(file: &Path) -> Option<String> {
+    let contents = fs::read_to_string(file).ok()?;
+    let mut lines = contents.lines();
+    let id = lines.next()?.trim();
+    if lines.any(|line| line.trim() == CRASHED) && !id.is_empty() {
+        Some(id.to_string())
+    } else {
+        None
+    }
+}
+
+/// Record that the current session crashed
+pub(crate) fn mark_crashed() {
+    if let Some(session) = SESSION.get() {
+        let file = fs::OpenOptions::new().append(true).open(&session.file);
+        if let Ok(mut file) = file {
+            let _ = writeln!(file, "{}", CRASHED);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Files in directories shared with other users
+//!
+//! On Unix the temporary directory is shared by everyone on the machine,
+//! and so can be the directory of the log file. Reports go into a
+//! directory of the user's own there, and files another user got to first
+//! are never written to: the next free name is picked instead.
+
+use std::path::{Path, PathBuf};
+
+/// The user's own directory for report files in the temporary directory
+///
+/// That is `human-panic-<uid>`, created with mode 0700 if needed. If
+/// another user already created a directory of that name, the next free
+/// one is used, `human-panic-<uid>-2` and so on. Falls back to the
+/// temporary directory itself if no directory can be created.
+#[cfg(unix)]
+pub(crate) fn user_temp_dir() -> Option<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    let temp = std::env::temp_dir();
+    let uid = uid();
+    for count in 1..=MAX_CANDIDATES {
+        let dir = temp.join(numbered(&format!("human-panic-{}", uid), count));
+        match std::fs::symlink_metadata(&dir) {
+            Ok(meta) if meta.is_dir() && meta.uid() == uid => {
+                return Some(dir)
+            }
+            Ok(_) => continue,
+            Err(_) => {}
+        }
+        match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+            Ok(()) => return Some(dir),
+            // someone else was quicker
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(_) => break,
+        }
+    }
+    Some(temp)
+}
+
+/// The user's own directory for report files in the temporary directory
+///
+/// The temporary directory is the user's own here already.
+#[cfg(not(any(unix, target_os = "wasi")))]
+pub(crate) fn user_temp_dir() -> Option<PathBuf> {
+    Some(std::env::temp_dir())
+}
+
+/// None, as WASI has no temporary directory: `std::env::temp_dir` panics,
+/// and only directories the runtime preopened can be written to
+#[cfg(target_os = "wasi")]
+pub(crate) fn user_temp_dir() -> Option<PathBuf> {
+    None
+}
+
+/// `path`, or if another user's file is in the way, the first of
+/// `<stem>-2.<extension>`, `<stem>-3.<extension>` and so on that isn't
+#[cfg(unix)]
+pub(crate) fn own_file(path: &Path) -> PathBuf {
+    use std::os::unix::fs::MetadataExt;
+
+    let uid = uid();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().map(|e| e.to_string_lossy());
+    for count in 1..=MAX_CANDIDATES {
+        let mut name = numbered(&stem, count);
+        if let Some(extension) = &extension {
+            name = format!("{}.{}", name, extension);
+        }
+        let candidate = match count {
+            1 => path.to_path_buf(),
+            _ => path.with_file_name(name),
+        };
+        match std::fs::symlink_metadata(&candidate) {
+            Ok(meta) if !meta.is_file() || meta.uid() != uid => continue,
+            _ => return candidate,
+        }
+    }
+    path.to_path_buf()
+}
+
+/// `path`, as files can't be owned by other users in a way that matters
+/// here
+#[cfg(not(unix))]
+pub(crate) fn own_file(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// How many names to try before giving up
+#[cfg(unix)]
+const MAX_CANDIDATES: usize = 100;
+
+/// `name`, followed by `-<count>` from the second candidate on
+#[cfg(unix)]
+fn numbered(name: &str, count: usize) -> String {
+    match count {
+        1 => name.to_string(),
+        _ => format!("{}-{}", name, count),
+    }
+}
+
+#[cfg(unix)]
+fn uid() -> u32 {
+    extern "C" {
+        fn getuid() -> u32;
+    }
+
+    // SAFETY: `getuid` takes no arguments and cannot fail
+    unsafe { getuid() }
+}
@@ -0,0 +1,52 @@
+//! Quoting of paths for pasting into a shell
+
+use std::path::Path;
+
+/// Quote `path` so it can be pasted into a shell of the current platform
+///
+/// On Unix, paths with anything but letters, digits and `/._-+,:@%` are put
+/// in single quotes, which keep every character as is. On Windows, paths
+/// are put in double quotes, which `cmd` and PowerShell both understand,
+/// unless they contain characters PowerShell would expand there.
+///
+/// ```
+/// use human_panic_logger::shell_quote;
+///
+/// # #[cfg(unix)] {
+/// assert_eq!(shell_quote("/tmp/report.toml"), "/tmp/report.toml");
+/// assert_eq!(shell_quote("/tmp/my report.toml"), "'/tmp/my report.toml'");
+/// assert_eq!(shell_quote("/tmp/it's.toml"), r"'/tmp/it'\''s.toml'");
+/// # }
+/// # #[cfg(windows)] {
+/// assert_eq!(
+///     shell_quote(r"C:\Users\Zoë\report.toml"),
+///     r#""C:\Users\Zoë\report.toml""#
+/// );
+/// # }
+/// ```
+pub fn shell_quote<P: AsRef<Path>>(path: P) -> String {
+    let path = crate::long_path::for_display(path.as_ref());
+    quote(&path.to_string_lossy())
+}
+
+#[cfg(not(windows))]
+fn quote(path: &str) -> String {
+    let plain = !path.is_empty()
+        && path.chars().all(|c| {
+            c.is_ascii_alphanumeric() || "/._-+,:@%".contains(c)
+        });
+    if plain {
+        return path.to_string();
+    }
+    format!("'{}'", path.replace('\'', r"'\''"))
+}
+
+#[cfg(windows)]
+fn quote(path: &str) -> String {
+    // PowerShell expands these inside double quotes; single quotes keep
+    // them, but only PowerShell knows single quotes
+    if path.contains(['$', '`']) {
+        return format!("'{}'", path.replace('\'', "''"));
+    }
+    format!("\"{}\"", path)
+}
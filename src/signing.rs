@@ -0,0 +1,167 @@
+//! Tamper-evident reports
+//!
+//! With the `signing` feature, official builds can embed an ed25519 key and
+//! sign the protobuf encoding of every report. The vendor's ingestion
+//! endpoint then verifies the signature with the matching public key, so
+//! reports edited after the fact are rejected.
+//!
+//! Report files can be signed as well: with
+//! [`Builder::sign_reports`](crate::Builder::sign_reports), every report file
+//! gets a detached signature next to it, which [`verify_file`] checks.
+//!
+//! The private key is embedded in the binary, so anyone who has the binary
+//! can extract it and sign reports of their own. A signature shows that a
+//! report wasn't edited by a user who doesn't care to dig the key out, not
+//! that it came from an official build.
+//!
+//! ```no_run
+//! use human_panic_logger::signing::SigningKey;
+//! use human_panic_logger::Report;
+//!
+//! fn upload(report: &Report) {
+//!     // official builds embed the real key, e.g. with `include_bytes!`
+//!     let key = SigningKey::from_bytes(&[7; 32]);
+//!
+//!     let signed = report.sign(&key);
+//!     let bytes = signed.to_protobuf();
+//!     // ... send `bytes` to the ingestion endpoint
+//! #   let _ = bytes;
+//!
+//!     // the endpoint only knows the public key
+//!     assert!(signed.verify(&key.verifying_key()).is_ok());
+//! }
+//! ```
+
+use crate::permissions::Permissions;
+use crate::Report;
+use ed25519_dalek::Signer;
+use prost::Message;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+
+pub use ed25519_dalek::{Signature, SignatureError, SigningKey, VerifyingKey};
+
+/// A protobuf encoded report together with its signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedReport {
+    /// The report, encoded following `proto/report.proto`
+    pub report: Vec<u8>,
+    /// The signature of `report`
+    pub signature: Signature,
+}
+
+impl SignedReport {
+    /// Check that `report` was signed by the owner of `key` and wasn't
+    /// modified since
+    pub fn verify(&self, key: &VerifyingKey) -> Result<(), SignatureError> {
+        key.verify_strict(&self.report, &self.signature)
+    }
+
+    /// Decode the signed report, after checking its signature
+    pub fn verified_report(
+        &self,
+        key: &VerifyingKey,
+    ) -> Result<crate::proto::Report, SignatureError> {
+        self.verify(key)?;
+        crate::proto::Report::decode(self.report.as_slice())
+            .map_err(SignatureError::from_source)
+    }
+
+    /// Encode the report and its signature as a `SignedReport` message
+    pub fn to_protobuf(&self) -> Vec<u8> {
+        crate::proto::SignedReport {
+            report: self.report.clone(),
+            signature: self.signature.to_bytes().to_vec(),
+        }
+        .encode_to_vec()
+    }
+
+    /// Decode a `SignedReport` message; the signature is not checked yet
+    pub fn from_protobuf(bytes: &[u8]) -> Result<Self, SignatureError> {
+        let message = crate::proto::SignedReport::decode(bytes)
+            .map_err(SignatureError::from_source)?;
+        Ok(SignedReport {
+            report: message.report,
+            signature: Signature::from_slice(&message.signature)?,
+        })
+    }
+}
+
+impl Report {
+    /// Encode the report as protobuf and sign it with `key`
+    pub fn sign(&self, key: &SigningKey) -> SignedReport {
+        let report = self.to_protobuf();
+        let signature = key.sign(&report);
+        SignedReport { report, signature }
+    }
+}
+
+/// The path of the detached signature of the file at `path`
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sig");
+    name.into()
+}
+
+/// Sign the file at `path` with `key`, writing the signature in hex to
+/// [`signature_path`]
+///
+/// Returns the path of the signature.
+pub fn sign_file(path: &Path, key: &SigningKey) -> io::Result<PathBuf> {
+    sign_file_with(path, key, Permissions::default())
+}
+
+/// Sign the file at `path` like [`sign_file`], creating the signature with
+/// `permissions`
+pub(crate) fn sign_file_with(
+    path: &Path,
+    key: &SigningKey,
+    permissions: Permissions,
+) -> io::Result<PathBuf> {
+    let signature = key.sign(&fs::read(crate::long_path::for_io(path))?);
+    let hex: String = signature
+        .to_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    let signature_path = signature_path(path);
+    permissions
+        .apply(OpenOptions::new().write(true).create(true).truncate(true))
+        .open(crate::long_path::for_io(&signature_path))?
+        .write_all((hex + "\n").as_bytes())?;
+    Ok(signature_path)
+}
+
+/// Check that the file at `path` was signed by the owner of `key` with
+/// [`sign_file`] and wasn't modified since
+///
+/// ```no_run
+/// use human_panic_logger::signing::{verify_file, VerifyingKey};
+/// use std::path::Path;
+///
+/// let key = VerifyingKey::from_bytes(&[7; 32])?;
+/// verify_file(Path::new("report-0c1e.toml"), &key)?;
+/// # Ok::<(), human_panic_logger::signing::SignatureError>(())
+/// ```
+pub fn verify_file(
+    path: &Path,
+    key: &VerifyingKey,
+) -> Result<(), SignatureError> {
+    let contents = fs::read(crate::long_path::for_io(path))
+        .map_err(SignatureError::from_source)?;
+    let signature_path = signature_path(path);
+    let hex = fs::read_to_string(crate::long_path::for_io(&signature_path))
+        .map_err(SignatureError::from_source)?;
+    let hex = hex.trim();
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|idx| {
+            hex.get(idx..idx + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+        })
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(SignatureError::new)?;
+    let signature = Signature::from_slice(&bytes)?;
+    key.verify_strict(&contents, &signature)
+}
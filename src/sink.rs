@@ -0,0 +1,359 @@
+//! Destinations for crash reports
+//!
+//! Besides the report file the crash message points to, applications can
+//! register any number of [`ReportSink`]s with the
+//! [`Builder`](crate::Builder), e.g. to copy reports to a shared directory
+//! or hand them to a local daemon. Every sink gets every report, and a
+//! sink that fails doesn't keep the others from getting it.
+
+use crate::permissions::Permissions;
+use crate::{Report, ReportFormat, ReportNaming, ReportSerializer};
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Somewhere crash reports are sent
+///
+/// Sinks run inside the panic hook, one after the other, once the report
+/// file is written and the crash message printed. They must be quick and
+/// must not panic: a second panic aborts the process on the spot. Errors
+/// are logged and otherwise ignored.
+///
+/// Sinks that [upload](ReportSink::uploads) reports run after the others,
+/// on a thread started with the hook. The hook waits for them only so
+/// long, see [`Builder::upload_timeout`](crate::Builder::upload_timeout).
+///
+/// Closures taking a [`Report`] are sinks too:
+///
+/// ```
+/// use human_panic_logger::{metadata, Builder, Report};
+///
+/// let builder = Builder::new(metadata!(), "app.log").sink(
+///     |report: &Report| -> Result<(), Box<_>> {
+///         eprintln!("crash {}", report.fingerprint());
+///         Ok(())
+///     },
+/// );
+/// # drop(builder);
+/// ```
+pub trait ReportSink: Send + Sync {
+    /// Send `report` to this destination
+    ///
+    /// Sinks that upload fail with an [`io::Error`] of kind
+    /// [`NotConnected`](io::ErrorKind::NotConnected) or
+    /// [`TimedOut`](io::ErrorKind::TimedOut) when their server can't be
+    /// reached, so the crash message can tell users their report wasn't
+    /// sent.
+    fn submit(
+        &self,
+        report: &Report,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    /// Check the sink's configuration, when the hook is installed
+    ///
+    /// An error here makes [`Builder::install`](crate::Builder::install)
+    /// fail with [`SetupError::SinkConfig`](crate::SetupError::SinkConfig),
+    /// rather than the sink failing on every crash.
+    fn check(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+
+    /// Whether this sink sends reports off the machine
+    ///
+    /// Such sinks only get the reports the user agreed to send, see
+    /// [`UploadConsent`](crate::UploadConsent).
+    fn uploads(&self) -> bool {
+        false
+    }
+
+    /// Send the reports that couldn't be sent before, e.g. for lack of a
+    /// network connection, returning how many were sent
+    ///
+    /// This runs in the background when the hook is installed, and on
+    /// [`flush_pending_reports`]. Sinks that don't queue reports have
+    /// nothing to send.
+    fn flush_pending(&self) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        Ok(0)
+    }
+}
+
+/// The sinks of the installed hook
+pub(crate) type Sinks = Arc<Vec<Box<dyn ReportSink>>>;
+
+/// The sinks of the installed hook, for [`flush_pending_reports`]
+static INSTALLED: Mutex<Option<Sinks>> = Mutex::new(None);
+
+/// Remember the sinks of the installed hook, and send what they queued in
+/// earlier runs in the background
+pub(crate) fn install(sinks: &Sinks) {
+    *INSTALLED.lock().unwrap_or_else(|e| e.into_inner()) = Some(sinks.clone());
+    if !sinks.is_empty() {
+        let _ = std::thread::Builder::new()
+            .name("human-panic-flush".into())
+            .spawn(flush_pending_reports);
+    }
+}
+
+/// How long the hook waits for the sinks that upload by default
+pub(crate) const UPLOAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A report for the reporter thread, and where to say what became of it
+type Job = (Report, mpsc::Sender<Delivery>);
+
+/// What became of a report the sinks that upload got, from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Delivery {
+    /// Every sink sent it
+    Sent,
+    /// A sink failed, e.g. because its server turned the report down
+    Failed,
+    /// A sink couldn't reach its server, or not in time
+    Unreachable,
+}
+
+/// Runs the sinks that upload on a thread of its own, started with the
+/// hook, so that stalled DNS lookups or connections can't keep a crashed
+/// process from exiting
+pub(crate) struct Reporter {
+    sinks: Sinks,
+    /// The thread's queue, `None` without sinks that upload, or if the
+    /// thread couldn't start
+    jobs: Mutex<Option<mpsc::Sender<Job>>>,
+    timeout: Duration,
+}
+
+impl Reporter {
+    /// Start the thread for the sinks among `sinks` that upload, if any
+    pub(crate) fn spawn(sinks: &Sinks, timeout: Duration) -> Self {
+        Reporter {
+            sinks: sinks.clone(),
+            jobs: Mutex::new(start_reporter(sinks)),
+            timeout,
+        }
+    }
+
+    /// Send `report` to the sinks that upload, waiting for them at most
+    /// for the timeout
+    pub(crate) fn submit(&self, report: &Report) -> Delivery {
+        let (done, sent) = mpsc::channel();
+        let job = (report.clone(), done);
+        {
+            let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+            let queue = match jobs.as_ref() {
+                Some(queue) => queue,
+                // e.g. targets without threads
+                None => return upload(&self.sinks, report),
+            };
+            if let Err(mpsc::SendError(job)) = queue.send(job) {
+                // a sink panicked and took the thread with it; start another
+                // rather than upload here without a timeout
+                *jobs = start_reporter(&self.sinks);
+                let resent = match jobs.as_ref() {
+                    Some(queue) => queue.send(job).is_ok(),
+                    None => false,
+                };
+                if !resent {
+                    log::warn!(
+                        "Could not restart the thread sending crash reports"
+                    );
+                    return Delivery::Failed;
+                }
+            }
+        }
+        match sent.recv_timeout(self.timeout) {
+            Ok(delivery) => delivery,
+            Err(RecvTimeoutError::Timeout) => {
+                log::warn!(
+                    "Gave up waiting for the crash report to be sent after \
+                     {:?}",
+                    self.timeout
+                );
+                Delivery::Unreachable
+            }
+            // a sink panicked, which the default hook reported
+            Err(RecvTimeoutError::Disconnected) => Delivery::Failed,
+        }
+    }
+}
+
+/// Start a thread running the sinks among `sinks` that upload, returning
+/// its queue, if there are any and it started
+fn start_reporter(sinks: &Sinks) -> Option<mpsc::Sender<Job>> {
+    if !sinks.iter().any(|sink| sink.uploads()) {
+        return None;
+    }
+    let (tx, rx) = mpsc::channel::<Job>();
+    let sinks = sinks.clone();
+    std::thread::Builder::new()
+        .name("human-panic-reporter".into())
+        .spawn(move || {
+            for (report, done) in rx {
+                let _ = done.send(upload(&sinks, &report));
+            }
+        })
+        .ok()
+        .map(|_| tx)
+}
+
+/// Send `report` to the sinks that upload, one after the other
+fn upload(sinks: &Sinks, report: &Report) -> Delivery {
+    let mut delivery = Delivery::Sent;
+    for sink in sinks.iter().filter(|sink| sink.uploads()) {
+        if let Err(e) = sink.submit(report) {
+            log::warn!("Could not submit the crash report: {}", e);
+            let unreachable = e
+                .downcast_ref::<io::Error>()
+                .map(io::Error::kind)
+                .is_some_and(|kind| {
+                    matches!(
+                        kind,
+                        io::ErrorKind::NotConnected | io::ErrorKind::TimedOut
+                    )
+                });
+            delivery = delivery.max(match unreachable {
+                true => Delivery::Unreachable,
+                false => Delivery::Failed,
+            });
+        }
+    }
+    delivery
+}
+
+/// Send the reports the installed sinks queued because they couldn't be
+/// sent, e.g. without a network connection, and return how many were sent
+///
+/// This happens in the background when the hook is installed already.
+/// Applications can call it again, e.g. when they notice they are back
+/// online. Errors are logged.
+pub fn flush_pending_reports() -> usize {
+    let sinks = INSTALLED.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let mut sent = 0;
+    for sink in sinks.iter().flat_map(|sinks| sinks.iter()) {
+        match sink.flush_pending() {
+            Ok(count) => sent += count,
+            Err(e) => log::warn!("Could not send queued crash reports: {}", e),
+        }
+    }
+    sent
+}
+
+impl<F> ReportSink for F
+where
+    F: Fn(&Report) -> Result<(), Box<dyn Error + Send + Sync>> + Send + Sync,
+{
+    fn submit(
+        &self,
+        report: &Report,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self(report)
+    }
+}
+
+/// Writes every report to a new file in a directory
+///
+/// Files are named like those of
+/// [`Report::write_file_as`](crate::Report::write_file_as), unless set
+/// otherwise with [`FileSink::naming`].
+pub struct FileSink {
+    dir: PathBuf,
+    serializer: Box<dyn ReportSerializer>,
+    naming: ReportNaming,
+}
+
+impl FileSink {
+    /// Write TOML reports into `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FileSink {
+            dir: dir.into(),
+            serializer: Box::new(ReportFormat::Toml),
+            naming: ReportNaming::default(),
+        }
+    }
+
+    /// Write reports with `serializer` instead
+    pub fn serializer(
+        mut self,
+        serializer: impl ReportSerializer + 'static,
+    ) -> Self {
+        self.serializer = Box::new(serializer);
+        self
+    }
+
+    /// Name the files with `naming` instead
+    pub fn naming(mut self, naming: ReportNaming) -> Self {
+        self.naming = naming;
+        self
+    }
+}
+
+impl ReportSink for FileSink {
+    fn submit(
+        &self,
+        report: &Report,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        report.write_file_with(
+            &self.dir,
+            &*self.serializer,
+            &self.naming,
+            Permissions::default(),
+            false,
+        )?;
+        Ok(())
+    }
+}
+
+/// Writes every report to a stream, such as stderr or a socket
+///
+/// ```no_run
+/// use human_panic_logger::{metadata, Builder, ReportFormat, WriterSink};
+/// use std::net::TcpStream;
+///
+/// let socket = TcpStream::connect("127.0.0.1:7070")?;
+/// let stderr =
+///     WriterSink::new(std::io::stderr()).serializer(ReportFormat::Markdown);
+/// Builder::new(metadata!(), "app.log")
+///     .sink(stderr)
+///     .sink(WriterSink::new(socket))
+///     .install()
+///     .expect("crash reporting could not be set up");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct WriterSink<W> {
+    writer: Mutex<W>,
+    serializer: Box<dyn ReportSerializer>,
+}
+
+impl<W: Write + Send> WriterSink<W> {
+    /// Write TOML reports to `writer`
+    pub fn new(writer: W) -> Self {
+        WriterSink {
+            writer: Mutex::new(writer),
+            serializer: Box::new(ReportFormat::Toml),
+        }
+    }
+
+    /// Write reports with `serializer` instead
+    pub fn serializer(
+        mut self,
+        serializer: impl ReportSerializer + 'static,
+    ) -> Self {
+        self.serializer = Box::new(serializer);
+        self
+    }
+}
+
+impl<W: Write + Send> ReportSink for WriterSink<W> {
+    fn submit(
+        &self,
+        report: &Report,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut writer =
+            self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        self.serializer.write(report, &mut *writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
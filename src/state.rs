@@ -0,0 +1,175 @@
+//! Application state captured at crash time
+//!
+//! With the `state` feature, applications register [`StateProvider`]s with
+//! the [`Builder`](crate::Builder). When a panic happens, every provider
+//! takes a snapshot, which ends up in its own section of the report.
+//! How a section is written can be set with a [`SectionSerializer`], so
+//! the layout of reports stays the same as sections are added.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Section serializers by section name
+type Serializers = BTreeMap<String, Arc<dyn SectionSerializer>>;
+
+/// The registered section serializers
+static SERIALIZERS: Mutex<Serializers> = Mutex::new(BTreeMap::new());
+
+/// Something that can describe part of the application's state
+///
+/// Snapshots are taken inside the panic hook, so they must be quick and
+/// must not panic: a second panic aborts the process on the spot.
+///
+/// ```
+/// use human_panic_logger::{metadata, Builder, StateProvider};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// struct OpenFiles(AtomicUsize);
+///
+/// impl StateProvider for OpenFiles {
+///     fn snapshot(&self) -> toml::Value {
+///         let open = self.0.load(Ordering::Relaxed) as i64;
+///         toml::Value::Table(toml::toml! { open = open })
+///     }
+/// }
+///
+/// let builder = Builder::new(metadata!(), "app.log")
+///     .state("files", OpenFiles(AtomicUsize::new(0)));
+/// # drop(builder);
+/// ```
+pub trait StateProvider: Send + Sync {
+    /// Describe the current state
+    fn snapshot(&self) -> toml::Value;
+}
+
+impl<F> StateProvider for F
+where
+    F: Fn() -> toml::Value + Send + Sync,
+{
+    fn snapshot(&self) -> toml::Value {
+        self()
+    }
+}
+
+/// How a section of the application's state is written into reports
+///
+/// Both methods fall back to the snapshot as it was taken.
+///
+/// ```
+/// use human_panic_logger::{register_section_serializer, SectionSerializer};
+///
+/// /// Renders the `breadcrumbs` section as a table in Markdown reports
+/// struct Breadcrumbs;
+///
+/// impl SectionSerializer for Breadcrumbs {
+///     fn to_markdown(&self, snapshot: &toml::Value) -> Option<String> {
+///         let mut table = String::from("| Time | Event |\n|---|---|\n");
+///         for crumb in snapshot.get("crumbs")?.as_array()? {
+///             let time = crumb.get("time")?.as_str()?;
+///             let event = crumb.get("event")?.as_str()?;
+///             table.push_str(&format!("| {} | {} |\n", time, event));
+///         }
+///         Some(table)
+///     }
+/// }
+///
+/// register_section_serializer("breadcrumbs", Breadcrumbs);
+/// ```
+pub trait SectionSerializer: Send + Sync {
+    /// The section as it goes into the report, e.g. with keys renamed to
+    /// what earlier versions of the application used
+    ///
+    /// This is applied when the snapshot is taken, so it shows in every
+    /// format.
+    fn to_toml(&self, snapshot: &toml::Value) -> toml::Value {
+        snapshot.clone()
+    }
+
+    /// The section in Markdown reports, e.g. as a table
+    ///
+    /// By default, the section is shown as TOML.
+    fn to_markdown(&self, snapshot: &toml::Value) -> Option<String> {
+        let _ = snapshot;
+        None
+    }
+}
+
+/// Write the state section `name` with `serializer` from now on
+///
+/// Sections can be registered before their provider, and registering a
+/// section again replaces its serializer.
+pub fn register_section_serializer(
+    name: impl Into<String>,
+    serializer: impl SectionSerializer + 'static,
+) {
+    serializers().insert(name.into(), Arc::new(serializer));
+}
+
+/// The serializer registered for the section `name`, if any
+fn serializer(name: &str) -> Option<Arc<dyn SectionSerializer>> {
+    serializers().get(name).cloned()
+}
+
+fn serializers() -> MutexGuard<'static, Serializers> {
+    // a panic while registering leaves the map as it was
+    SERIALIZERS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The registered providers, by section name
+#[derive(Default)]
+pub(crate) struct Providers(Vec<(String, Box<dyn StateProvider>)>);
+
+impl Providers {
+    pub(crate) fn register(
+        &mut self,
+        name: String,
+        provider: Box<dyn StateProvider>,
+    ) {
+        self.0.push((name, provider));
+    }
+
+    /// Take a snapshot from every provider, laid out by the section's
+    /// serializer
+    pub(crate) fn snapshot(&self) -> BTreeMap<String, toml::Value> {
+        self.0
+            .iter()
+            .map(|(name, provider)| {
+                let snapshot = provider.snapshot();
+                let snapshot = match serializer(name) {
+                    Some(serializer) => serializer.to_toml(&snapshot),
+                    None => snapshot,
+                };
+                (name.clone(), snapshot)
+            })
+            .collect()
+    }
+}
+
+/// The sections of `state` their serializers render as Markdown, by name,
+/// and the rest
+pub(crate) fn to_markdown(
+    state: &BTreeMap<String, toml::Value>,
+) -> (Vec<(&str, String)>, BTreeMap<String, toml::Value>) {
+    let mut rendered = Vec::new();
+    let mut rest = BTreeMap::new();
+    for (name, snapshot) in state {
+        let markdown = serializer(name).and_then(|s| s.to_markdown(snapshot));
+        match markdown {
+            Some(markdown) => rendered.push((name.as_str(), markdown)),
+            None => {
+                rest.insert(name.clone(), snapshot.clone());
+            }
+        }
+    }
+    (rendered, rest)
+}
+
+/// Render snapshots as the `[state.<name>]` sections of a TOML document
+pub(crate) fn to_toml(state: &BTreeMap<String, toml::Value>) -> String {
+    let mut document = toml::Table::new();
+    document.insert(
+        "state".into(),
+        toml::Value::Table(state.clone().into_iter().collect()),
+    );
+    toml::to_string(&document).unwrap_or_default()
+}
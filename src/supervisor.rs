@@ -0,0 +1,66 @@
+//! Crash notifications for supervising processes
+//!
+//! A process that starts the application, such as an Electron shell or a
+//! service manager, can set `HUMAN_PANIC_SUPERVISOR` to the path of a Unix
+//! domain socket, or of a named pipe on Windows, that it listens on. Every
+//! crash is then summarized there in a single line of JSON, with the
+//! `name`, `version`, `summary`, `fingerprint` and `session_id` of the
+//! report, so the supervisor can show a crash dialog right away:
+//!
+//! ```text
+//! {"name":"app","version":"1.2","summary":"app 1.2 panicked: oh no",…}
+//! ```
+//!
+//! Child processes inherit the variable, and so notify the supervisor of
+//! their crashes as well, unless they remove it.
+
+use crate::format::string;
+use crate::Report;
+use std::io;
+#[cfg(any(unix, windows))]
+use std::io::Write;
+
+/// The socket or named pipe to notify of crashes
+const SUPERVISOR_VAR: &str = "HUMAN_PANIC_SUPERVISOR";
+
+/// Tell the supervisor about `report`, if there is one
+pub(crate) fn notify(report: &Report) -> io::Result<()> {
+    let path = match std::env::var_os(SUPERVISOR_VAR) {
+        Some(path) if !path.is_empty() => path,
+        _ => return Ok(()),
+    };
+    let mut line = format!(
+        "{{\"name\":{},\"version\":{},\"summary\":{},\"fingerprint\":{}",
+        string(&report.name),
+        string(&report.version),
+        string(&report.summary()),
+        string(&report.fingerprint()),
+    );
+    if let Some(session_id) = &report.session_id {
+        line.push_str(&format!(",\"session_id\":{}", string(session_id)));
+    }
+    line.push_str("}\n");
+    send(std::path::Path::new(&path), &line)
+}
+
+#[cfg(unix)]
+fn send(path: &std::path::Path, line: &str) -> io::Result<()> {
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    let mut stream = UnixStream::connect(path)?;
+    // a supervisor that doesn't read must not hang the crash path
+    stream.set_write_timeout(Some(Duration::from_secs(1)))?;
+    stream.write_all(line.as_bytes())
+}
+
+#[cfg(windows)]
+fn send(path: &std::path::Path, line: &str) -> io::Result<()> {
+    let mut pipe = std::fs::OpenOptions::new().write(true).open(path)?;
+    pipe.write_all(line.as_bytes())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn send(_path: &std::path::Path, _line: &str) -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,292 @@
+//! Symbols resolved in earlier crashes
+//!
+//! Resolving the symbols of a backtrace reads the debug info, which can
+//! take seconds while it isn't in the OS's caches. Once resolved, the frames
+//! are kept in a file in the report directory, so the next crash of the
+//! same build finds most of them there.
+//!
+//! Modules are loaded at a different address in every run, so each frame is
+//! stored by the module it is in and its distance from the module's base.
+//! Modules are told apart by their path, size and modification time, and
+//! for the application by the [build id](crate::Metadata::build_id) too,
+//! so a library updated in between doesn't get the symbols of the old one.
+//! On Linux every module the process had loaded when the hook was installed
+//! is covered; elsewhere only the one this crate is linked into, usually
+//! the executable, whose frames are measured from a function of this crate.
+//! The file is named after the application's build.
+
+use crate::permissions::Permissions;
+use crate::report::{Fnv1a, Symbol};
+use crate::Metadata;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
+
+/// The directory within the report directory caches are kept in
+const DIR: &str = "symbols";
+
+/// Where the symbols of the current build are kept
+struct Cache {
+    file: PathBuf,
+    /// The modules whose frames are cached
+    modules: Vec<Module>,
+    permissions: Permissions,
+}
+
+/// A module of the process, like the executable or a shared library
+struct Module {
+    /// A hash of the module's path, size and modification time
+    identity: u64,
+    /// The addresses the module was loaded at
+    range: Range<usize>,
+    /// The address its frames are measured from
+    base: usize,
+}
+
+static CACHE: OnceLock<Cache> = OnceLock::new();
+
+/// Keep the symbols of crashes in `report_dir` from now on
+///
+/// Caches of other builds of the application are deleted, as their
+/// addresses don't fit any longer.
+pub(crate) fn start(report_dir: &Path, meta: &Metadata, perms: Permissions) {
+    let anchor = anchor();
+    let mut modules: Vec<Module> = loaded_modules()
+        .into_iter()
+        .filter_map(|(path, range, base)| {
+            let mut identity = identity(&path)?;
+            if range.contains(&anchor) {
+                identity.feed(meta.build_id.as_bytes());
+            }
+            Some(Module {
+                identity: identity.finish(),
+                range,
+                base,
+            })
+        })
+        .collect();
+    let own = match modules.iter().position(|m| m.range.contains(&anchor)) {
+        Some(own) => own,
+        None => return,
+    };
+    // the application's module is looked up most often
+    modules.swap(0, own);
+
+    let dir = report_dir.join(DIR);
+    let prefix = format!("{}-", meta.name);
+    let file =
+        dir.join(format!("{}{:016x}.txt", prefix, modules[0].identity));
+    if !crate::long_path::for_io(&file).exists() {
+        remove_stale(&dir, &prefix);
+    }
+    let _ = CACHE.set(Cache {
+        file,
+        modules,
+        permissions: perms,
+    });
+}
+
+/// Delete the caches in `dir` whose names start with `prefix`
+fn remove_stale(dir: &Path, prefix: &str) {
+    let entries = match fs::read_dir(crate::long_path::for_io(dir)) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.filter_map(Result::ok) {
+        if entry.file_name().to_string_lossy().starts_with(prefix) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// The hash of the module file at `path`, if it is there
+fn identity(path: &Path) -> Option<Fnv1a> {
+    let meta = fs::metadata(path).ok()?;
+    let mut identity = Fnv1a::new();
+    identity.feed(path.to_string_lossy().as_bytes());
+    identity.feed(&meta.len().to_le_bytes());
+    if let Ok(since) = meta
+        .modified()
+        .map(|modified| modified.duration_since(UNIX_EPOCH))
+    {
+        identity.feed(&since.unwrap_or_default().as_nanos().to_le_bytes());
+    }
+    Some(identity)
+}
+
+/// A function of this crate, which is loaded wherever the crate is
+fn anchor() -> usize {
+    anchor as fn() -> usize as usize
+}
+
+/// The file, addresses and base address of every module mapped into the
+/// process
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn loaded_modules() -> Vec<(PathBuf, Range<usize>, usize)> {
+    let maps = fs::read_to_string("/proc/self/maps").unwrap_or_default();
+    let mut modules: Vec<(PathBuf, Range<usize>, usize)> = Vec::new();
+    for line in maps.lines() {
+        let mut fields = line.split_whitespace();
+        let mapping = (|| {
+            let (start, end) = fields.next()?.split_once('-')?;
+            let start = usize::from_str_radix(start, 16).ok()?;
+            let end = usize::from_str_radix(end, 16).ok()?;
+            let offset = usize::from_str_radix(fields.nth(1)?, 16).ok()?;
+            // no field before the path has a slash in it
+            let path = &line[line.find('/')?..];
+            Some((start..end, offset, path))
+        })();
+        let (range, offset, path) = match mapping {
+            // a file replaced since it was loaded isn't the one on disk
+            Some((_, _, path)) if path.ends_with(" (deleted)") => continue,
+            Some(mapping) => mapping,
+            None => continue,
+        };
+        match modules.iter_mut().find(|(known, ..)| known == Path::new(path)) {
+            Some((_, known, _)) => {
+                known.start = known.start.min(range.start);
+                known.end = known.end.max(range.end);
+            }
+            None => {
+                let base = range.start.saturating_sub(offset);
+                modules.push((PathBuf::from(path), range, base));
+            }
+        }
+    }
+    modules
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn loaded_modules() -> Vec<(PathBuf, Range<usize>, usize)> {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(_) => return Vec::new(),
+    };
+    let len = fs::metadata(&exe).map_or(0, |meta| meta.len() as usize);
+    // no frame of the module is further from the anchor than its size
+    let anchor = anchor();
+    let range = anchor.saturating_sub(len)..anchor.saturating_add(len);
+    vec![(exe, range, anchor)]
+}
+
+/// The symbols cached for the current build
+pub(crate) struct SymbolCache {
+    cache: &'static Cache,
+    /// The symbols of each frame by its module and distance from the
+    /// module's base
+    symbols: HashMap<(u64, isize), Vec<Symbol>>,
+}
+
+impl SymbolCache {
+    /// Read the cache, if it was started
+    pub(crate) fn open() -> Option<Self> {
+        let cache = CACHE.get()?;
+        let text = fs::read_to_string(crate::long_path::for_io(&cache.file))
+            .unwrap_or_default();
+        let mut symbols: HashMap<(u64, isize), Vec<Symbol>> = HashMap::new();
+        for line in text.lines() {
+            if let Some((key, symbol)) = parse_line(line) {
+                symbols.entry(key).or_default().push(symbol);
+            }
+        }
+        Some(SymbolCache { cache, symbols })
+    }
+
+    /// The symbols of the frame at `ip`, if they were cached
+    pub(crate) fn get(&self, ip: usize) -> Option<Vec<Symbol>> {
+        self.symbols.get(&self.key(ip)?).cloned()
+    }
+
+    /// Add the symbols of `frames` that aren't cached yet
+    ///
+    /// Frames without symbols are left out, and so are symbols that can't
+    /// be written on a line of their own, which are rare.
+    pub(crate) fn save<'a>(
+        &self,
+        frames: impl Iterator<Item = (usize, &'a [Symbol])>,
+    ) {
+        let mut lines = String::new();
+        for (ip, symbols) in frames {
+            let (module, offset) = match self.key(ip) {
+                Some(key) if !self.symbols.contains_key(&key) => key,
+                _ => continue,
+            };
+            let fields: Option<Vec<_>> = symbols.iter().map(fields).collect();
+            match fields {
+                Some(fields) if !fields.is_empty() => {
+                    for (name, file, line) in fields {
+                        let _ = writeln!(
+                            lines,
+                            "{:016x}\t{}\t{}\t{}\t{}",
+                            module, offset, name, file, line
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        if lines.is_empty() {
+            return;
+        }
+
+        let cache = self.cache;
+        if let Some(dir) = cache.file.parent() {
+            let _ = cache.permissions.create_dir_all(dir);
+        }
+        let file = cache
+            .permissions
+            .apply(OpenOptions::new().create(true).append(true))
+            .open(crate::long_path::for_io(&cache.file));
+        // one write, so crashes of several processes don't mix their lines
+        if let Ok(mut file) = file {
+            let _ = file.write_all(lines.as_bytes());
+        }
+    }
+
+    /// The module `ip` is in and how far it is from the module's base, if
+    /// it is in one of the cached modules
+    fn key(&self, ip: usize) -> Option<(u64, isize)> {
+        let module = self
+            .cache
+            .modules
+            .iter()
+            .find(|module| module.range.contains(&ip))?;
+        Some((module.identity, ip.wrapping_sub(module.base) as isize))
+    }
+}
+
+/// A line of the cache: the module and offset of the frame and one of its
+/// symbols
+fn parse_line(line: &str) -> Option<((u64, isize), Symbol)> {
+    let mut fields = line.split('\t');
+    let module = u64::from_str_radix(fields.next()?, 16).ok()?;
+    let offset = fields.next()?.parse().ok()?;
+    let (name, file, line) = (fields.next()?, fields.next()?, fields.next()?);
+    if fields.next().is_some() {
+        return None;
+    }
+    let symbol = Symbol {
+        name: Some(name.to_string()).filter(|name| !name.is_empty()),
+        file: Some(PathBuf::from(file)).filter(|_| !file.is_empty()),
+        line: line.parse().ok(),
+    };
+    Some(((module, offset), symbol))
+}
+
+/// The name, file and line of `symbol` for a line of the cache, unless
+/// they have tabs or line breaks in them
+fn fields(symbol: &Symbol) -> Option<(&str, &str, String)> {
+    let name = symbol.name.as_deref().unwrap_or("");
+    let file = match &symbol.file {
+        Some(file) => file.to_str()?,
+        None => "",
+    };
+    let line = symbol.line.map(|line| line.to_string()).unwrap_or_default();
+    let plain = |text: &str| !text.contains(['\t', '\n', '\r']);
+    (plain(name) && plain(file)).then_some((name, file, line))
+}
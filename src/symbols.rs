@@ -0,0 +1,90 @@
+//! De-obfuscation of symbol names
+//!
+//! Vendors who obfuscate symbol names ship builds whose backtraces only show
+//! opaque names. Reports record the build id (see [`setup_panic_logger!`]),
+//! so internal tooling can pick the mapping file of that build and restore
+//! the real names with [`Report::deobfuscate`].
+//!
+//! [`setup_panic_logger!`]: crate::setup_panic_logger
+
+use crate::Report;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A mapping from obfuscated to original symbol names
+///
+/// The mapping file has one `<obfuscated> <original>` pair per line. Blank
+/// lines and lines starting with `#` are ignored.
+///
+/// ```
+/// use human_panic_logger::SymbolMap;
+///
+/// let map = SymbolMap::parse("# build 4f2a\nx9f1 app\nk2 parse_config\n");
+///
+/// assert_eq!(map.lookup("k2"), Some("parse_config"));
+/// assert_eq!(map.resolve("x9f1::k2"), "app::parse_config");
+/// assert_eq!(map.resolve("std::rt::lang_start"), "std::rt::lang_start");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolMap {
+    names: HashMap<String, String>,
+}
+
+impl SymbolMap {
+    /// Parse a mapping file's contents
+    pub fn parse(text: &str) -> Self {
+        let names = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                match (parts.next(), parts.next()) {
+                    (Some(obfuscated), Some(original)) => {
+                        Some((obfuscated.to_string(), original.to_string()))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+        SymbolMap { names }
+    }
+
+    /// Read and parse a mapping file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        fs::read_to_string(path).map(|text| Self::parse(&text))
+    }
+
+    /// The original name of a single obfuscated name
+    pub fn lookup(&self, obfuscated: &str) -> Option<&str> {
+        self.names.get(obfuscated).map(String::as_str)
+    }
+
+    /// Restore a symbol path, replacing every `::` separated segment that
+    /// has a mapping and keeping the others
+    pub fn resolve(&self, symbol: &str) -> String {
+        if let Some(original) = self.lookup(symbol) {
+            return original.to_string();
+        }
+        symbol
+            .split("::")
+            .map(|segment| self.lookup(segment).unwrap_or(segment))
+            .collect::<Vec<_>>()
+            .join("::")
+    }
+}
+
+impl Report {
+    /// Replace obfuscated symbol names in the backtrace using `map`
+    pub fn deobfuscate(&mut self, map: &SymbolMap) {
+        for frame in &mut self.backtrace {
+            for symbol in &mut frame.symbols {
+                if let Some(name) = &symbol.name {
+                    symbol.name = Some(map.resolve(name));
+                }
+            }
+        }
+    }
+}
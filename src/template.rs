@@ -0,0 +1,176 @@
+//! Placeholders in texts applications provide
+//!
+//! Templates, like the
+//! [crash message](crate::Settings#structfield.message_template) or the URL
+//! of a [custom issue tracker](crate::IssueTracker::Custom), name the values
+//! filled in between braces, e.g. `{name}`. `{{` and `}}` stand for braces
+//! of their own.
+//!
+//! In texts written right to left, like Arabic or Hebrew ones, the values
+//! shown to users are isolated from the text around them. Otherwise the
+//! terminal would lay out a path or URL together with its neighbours, and
+//! e.g. move the full stop after it to its other side.
+
+/// A piece of a template
+enum Piece<'a> {
+    /// Text kept as it is
+    Text(&'a str),
+    /// The name of a value to fill in
+    Placeholder(&'a str),
+}
+
+/// Split `template` into its pieces, or tell what is wrong with it
+fn pieces(template: &str) -> Result<Vec<Piece<'_>>, String> {
+    let mut pieces = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        if start > 0 {
+            pieces.push(Piece::Text(&rest[..start]));
+        }
+        let brace = &rest[start..start + 1];
+        rest = &rest[start + 1..];
+        if let Some(after) = rest.strip_prefix(brace) {
+            pieces.push(Piece::Text(brace));
+            rest = after;
+            continue;
+        }
+        if brace == "}" {
+            return Err("unmatched '}', write '}}' for a brace".into());
+        }
+        let end = rest
+            .find(['{', '}'])
+            .filter(|&end| rest[end..].starts_with('}'))
+            .ok_or("unclosed '{', write '{{' for a brace")?;
+        if end == 0 {
+            return Err("empty placeholder '{}'".into());
+        }
+        pieces.push(Piece::Placeholder(&rest[..end]));
+        rest = &rest[end + 1..];
+    }
+    if !rest.is_empty() {
+        pieces.push(Piece::Text(rest));
+    }
+    Ok(pieces)
+}
+
+/// Check that `template` is well-formed and only names `placeholders`
+pub(crate) fn check(
+    template: &str,
+    placeholders: &[&str],
+) -> Result<(), String> {
+    for piece in pieces(template)? {
+        match piece {
+            Piece::Placeholder(name) if !placeholders.contains(&name) => {
+                return Err(format!(
+                    "unknown placeholder {{{}}}, expected one of {}",
+                    name,
+                    placeholders
+                        .iter()
+                        .map(|name| format!("{{{}}}", name))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Whether `template` names `placeholder`
+pub(crate) fn uses(template: &str, placeholder: &str) -> bool {
+    pieces(template).is_ok_and(|pieces| {
+        pieces.iter().any(|piece| match piece {
+            Piece::Placeholder(name) => *name == placeholder,
+            Piece::Text(_) => false,
+        })
+    })
+}
+
+/// `template` with every placeholder replaced by its `value`
+///
+/// Placeholders without a value are kept as they are written.
+pub(crate) fn render<S: AsRef<str>>(
+    template: &str,
+    value: impl Fn(&str) -> Option<S>,
+) -> Result<String, String> {
+    let mut rendered = String::with_capacity(template.len());
+    for piece in pieces(template)? {
+        match piece {
+            Piece::Text(text) => rendered.push_str(text),
+            Piece::Placeholder(name) => match value(name) {
+                Some(value) => rendered.push_str(value.as_ref()),
+                None => {
+                    rendered.push('{');
+                    rendered.push_str(name);
+                    rendered.push('}');
+                }
+            },
+        }
+    }
+    Ok(rendered)
+}
+
+/// `template` like [`render`] does, with the values isolated if the
+/// template is written right to left
+pub(crate) fn render_isolated<S: AsRef<str>>(
+    template: &str,
+    value: impl Fn(&str) -> Option<S>,
+) -> Result<String, String> {
+    let rtl = is_rtl(template);
+    render(template, |name| {
+        let value = value(name)?;
+        Some(match rtl {
+            true => isolate(value.as_ref()),
+            false => value.as_ref().to_string(),
+        })
+    })
+}
+
+/// Whether `text` has letters of a script written right to left
+pub(crate) fn is_rtl(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(
+            u32::from(c),
+            // Hebrew, Arabic, Syriac, Thaana, N'Ko and their neighbours
+            0x0590..=0x08FF
+                // presentation forms of Hebrew and Arabic
+                | 0xFB1D..=0xFDFF
+                // but not U+FEFF, the byte order mark
+                | 0xFE70..=0xFEFE
+                // historic scripts, e.g. Phoenician
+                | 0x10800..=0x10FFF
+                | 0x1E800..=0x1EFFF
+        )
+    })
+}
+
+/// `value` between a first strong isolate and a pop directional isolate,
+/// so it is laid out in its own direction
+pub(crate) fn isolate(value: &str) -> String {
+    format!("\u{2068}{}\u{2069}", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_order_mark_is_not_right_to_left() {
+        assert!(!is_rtl("\u{FEFF}{name} crashed, see \"{report_path}\"."));
+        assert!(is_rtl("\u{FEFF}{name} קרס."));
+    }
+
+    #[test]
+    fn values_are_isolated_only_in_right_to_left_text() {
+        let value = |_: &str| Some("/tmp/report.toml");
+        assert_eq!(
+            render_isolated("See \"{path}\".", value).unwrap(),
+            "See \"/tmp/report.toml\"."
+        );
+        assert_eq!(
+            render_isolated("ראו \"{path}\".", value).unwrap(),
+            "ראו \"\u{2068}/tmp/report.toml\u{2069}\"."
+        );
+    }
+}
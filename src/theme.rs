@@ -0,0 +1,179 @@
+//! The look of the crash message
+
+use crate::color::{self, ColorSpec};
+
+/// The colors and layout of the crash message
+///
+/// Start from one of the built-in themes and change what doesn't fit, e.g.
+/// to match the palette of the rest of an application:
+///
+/// ```
+/// use human_panic_logger::{metadata, Builder, Color, Style, Theme};
+///
+/// let theme = Theme {
+///     heading: Style::new().fg(Color::Magenta).bold(),
+///     highlight: Style::new().fg(Color::Rgb(0, 175, 255)),
+///     error: Style::new().fg(Color::Red),
+///     ..Theme::CLASSIC
+/// };
+/// let builder = Builder::new(metadata!(), "app.log").theme(theme);
+/// # drop(builder);
+/// ```
+///
+/// Without the `color` feature, or when stderr isn't a terminal, only the
+/// layout applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// The opening line
+    pub heading: Style,
+    /// Regular text
+    pub body: Style,
+    /// Paths and links, which users need to find at a glance
+    pub highlight: Style,
+    /// The line saying the application crashed
+    pub error: Style,
+    /// Whether paragraphs are set apart by blank lines
+    pub paragraph_breaks: bool,
+}
+
+impl Theme {
+    /// White text with blank lines between paragraphs
+    pub const CLASSIC: Theme = {
+        let white = Style::new().fg(Color::White);
+        Theme {
+            heading: white,
+            body: white,
+            highlight: white,
+            error: white,
+            paragraph_breaks: true,
+        }
+    };
+
+    /// Uncolored text without blank lines, for tools with terse output
+    pub const MINIMAL: Theme = Theme {
+        heading: Style::new(),
+        body: Style::new(),
+        highlight: Style::new(),
+        error: Style::new(),
+        paragraph_breaks: false,
+    };
+
+    /// Bold, bright text on a black background with paths highlighted,
+    /// for users who need strong contrast
+    pub const HIGH_CONTRAST: Theme = {
+        // bright white; an intense white would brighten the background to
+        // gray as well
+        let body = Style::new().fg(Color::Ansi256(15)).bg(Color::Black);
+        Theme {
+            heading: body.bold(),
+            body,
+            highlight: body.fg(Color::Ansi256(11)).bold(),
+            error: body,
+            paragraph_breaks: true,
+        }
+    };
+
+    /// What goes between paragraphs
+    pub(crate) fn paragraph_break(self) -> &'static str {
+        match self.paragraph_breaks {
+            true => "\n",
+            false => "",
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::CLASSIC
+    }
+}
+
+/// The colors and weight of some text, the terminal's own by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    bold: bool,
+}
+
+impl Style {
+    /// Text in the terminal's colors
+    pub const fn new() -> Self {
+        Style {
+            fg: None,
+            bg: None,
+            bold: false,
+        }
+    }
+
+    /// Text in `color`
+    pub const fn fg(mut self, color: Color) -> Self {
+        self.fg = Some(color);
+        self
+    }
+
+    /// Text on a `color` background
+    pub const fn bg(mut self, color: Color) -> Self {
+        self.bg = Some(color);
+        self
+    }
+
+    /// Bold text
+    pub const fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    pub(crate) fn spec(self) -> ColorSpec {
+        let mut spec = ColorSpec::new();
+        spec.set_fg(self.fg.map(Color::term))
+            .set_bg(self.bg.map(Color::term))
+            .set_bold(self.bold);
+        spec
+    }
+}
+
+/// A terminal color
+///
+/// The first eight are the terminal's own, so they follow its color scheme.
+/// [`Rgb`](Color::Rgb) needs a terminal with true color support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// The terminal's black
+    Black,
+    /// The terminal's red
+    Red,
+    /// The terminal's green
+    Green,
+    /// The terminal's yellow
+    Yellow,
+    /// The terminal's blue
+    Blue,
+    /// The terminal's magenta
+    Magenta,
+    /// The terminal's cyan
+    Cyan,
+    /// The terminal's white
+    White,
+    /// One of the 256 colors of xterm-compatible terminals
+    Ansi256(u8),
+    /// A color by its red, green and blue
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    fn term(self) -> color::Color {
+        match self {
+            Color::Black => color::Color::Black,
+            Color::Red => color::Color::Red,
+            Color::Green => color::Color::Green,
+            Color::Yellow => color::Color::Yellow,
+            Color::Blue => color::Color::Blue,
+            Color::Magenta => color::Color::Magenta,
+            Color::Cyan => color::Color::Cyan,
+            Color::White => color::Color::White,
+            Color::Ansi256(n) => color::Color::Ansi256(n),
+            Color::Rgb(r, g, b) => color::Color::Rgb(r, g, b),
+        }
+    }
+}